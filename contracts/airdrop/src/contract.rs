@@ -3,7 +3,7 @@ use std::ops::Div;
 use cosmwasm_std::{Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Order, QuerierWrapper, Response, StdError, StdResult, SubMsg, to_binary, Uint128, WasmMsg, WasmQuery};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cw0::{maybe_addr, must_pay};
+use cw0::{Expiration, maybe_addr, must_pay};
 use cw2::{get_contract_version, set_contract_version};
 use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
 use cw_storage_plus::Bound;
@@ -12,6 +12,9 @@ use platform_registry::{AddressBaseInfoResponse, PlatformRegistryQueryMsg};
 use staking::msg::MemberResponse as StakingMemberResponse;
 use staking::msg::QueryMsg as StakingQueryMsg;
 
+use ownership::{query_pending_owner};
+use roles::{is_authorized, query_role_members, Role};
+
 use crate::error::ContractError;
 use crate::msg::{ExecuteMsg, InstantiateMissionSmartContracts, InstantiateMsg, MemberListResponse, MemberListResponseItem, MemberResponse, MemberResponseItem, MigrateMsg, Missions, QueryMsg, RegisterMemberItem};
 use crate::state::{CONFIG, Config, FeeConfig, Member, MEMBERS, MissionSmartContracts, STATE, State};
@@ -32,7 +35,7 @@ pub fn instantiate(
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     let config = Config {
-        owner: deps.api.addr_validate(&msg.owner)?,
+        owner: Some(deps.api.addr_validate(&msg.owner)?),
         terraland_token: deps.api.addr_validate(&msg.terraland_token)?,
         fee_config: msg.fee_config,
         mission_smart_contracts: mission_smart_contracts_from(&deps, msg.mission_smart_contracts)?,
@@ -47,9 +50,9 @@ pub fn instantiate(
 fn mission_smart_contracts_from(deps: &DepsMut, m: Option<InstantiateMissionSmartContracts>) -> StdResult<MissionSmartContracts> {
     let res = match m {
         Some(m) => MissionSmartContracts {
-            lp_staking: option_addr_validate(&deps, &m.lp_staking)?,
-            tland_staking: option_addr_validate(&deps, &m.tland_staking)?,
-            platform_registry: option_addr_validate(&deps, &m.platform_registry)?,
+            lp_staking: option_addr_validate(deps, &m.lp_staking)?,
+            tland_staking: option_addr_validate(deps, &m.tland_staking)?,
+            platform_registry: option_addr_validate(deps, &m.platform_registry)?,
         },
         None => MissionSmartContracts {
             lp_staking: None,
@@ -62,7 +65,7 @@ fn mission_smart_contracts_from(deps: &DepsMut, m: Option<InstantiateMissionSmar
 
 fn option_addr_validate(deps: &DepsMut, value: &Option<String>) -> StdResult<Option<Addr>> {
     let v = match value {
-        Some(str) => Some(deps.api.addr_validate(&str)?),
+        Some(str) => Some(deps.api.addr_validate(str)?),
         None => None,
     };
     Ok(v)
@@ -87,8 +90,15 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateConfig { owner, fee_config, mission_smart_contracts } =>
-            execute_update_config(deps, env, info, owner, fee_config, mission_smart_contracts),
+        ExecuteMsg::UpdateConfig { fee_config, mission_smart_contracts } =>
+            execute_update_config(deps, env, info, fee_config, mission_smart_contracts),
+        ExecuteMsg::ProposeOwner { new_owner, expires } =>
+            execute_propose_owner(deps, env, info, new_owner, expires),
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => execute_cancel_ownership_proposal(deps, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, info),
+        ExecuteMsg::GrantRole { role, address } => execute_grant_role(deps, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => execute_revoke_role(deps, info, role, address),
         ExecuteMsg::RegisterMembers(members) =>
             execute_register_members(deps, env, info, members),
         ExecuteMsg::RemoveMembers(addresses) =>
@@ -105,24 +115,18 @@ pub fn execute_update_config(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    new_owner: Option<String>,
     new_fee_config: Option<Vec<FeeConfig>>,
     new_mission_smart_contracts: Option<InstantiateMissionSmartContracts>,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized admin
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Admin, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
-    let api = deps.api;
     let new_mission_sc = mission_smart_contracts_from(&deps, new_mission_smart_contracts)?;
 
     CONFIG.update(deps.storage, |mut existing_config| -> StdResult<_> {
-        // update new owner if set
-        if let Some(addr) = new_owner {
-            existing_config.owner = api.addr_validate(&addr)?;
-        }
         if let Some(fee_config) = new_fee_config {
             existing_config.fee_config = fee_config;
         }
@@ -146,15 +150,82 @@ pub fn execute_update_config(
         .add_attribute("sender", info.sender))
 }
 
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_owner: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(ownership::execute_propose_owner(deps, &env, &info, &cfg.owner, new_owner, expires)?)
+}
+
+pub fn execute_accept_ownership(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let (new_owner, res) = ownership::execute_accept_ownership(deps.branch(), &env, &info)?;
+
+    CONFIG.update(deps.storage, |mut existing_config| -> StdResult<_> {
+        existing_config.owner = Some(new_owner);
+        Ok(existing_config)
+    })?;
+
+    Ok(res)
+}
+
+pub fn execute_cancel_ownership_proposal(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(ownership::execute_cancel_ownership_proposal(deps, &info, &cfg.owner)?)
+}
+
+pub fn execute_renounce_ownership(
+    mut deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    let res = ownership::execute_renounce_ownership(deps.branch(), &info, &cfg.owner)?;
+
+    cfg.owner = None;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(res)
+}
+
+pub fn execute_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(roles::execute_grant_role(deps, &info, &cfg.owner, role, address)?)
+}
+
+pub fn execute_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(roles::execute_revoke_role(deps, &info, &cfg.owner, role, address)?)
+}
+
 pub fn execute_register_members(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     members: Vec<RegisterMemberItem>,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized operator
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Operator, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -191,9 +262,9 @@ pub fn execute_remove_members(
     info: MessageInfo,
     addresses: Vec<String>,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized operator
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Operator, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -264,7 +335,7 @@ pub fn execute_claim(
 }
 
 fn calc_claim_amount(missions: &Missions, member: &Member) -> StdResult<Uint128> {
-    let passed_missions_num = calc_missions_passed(&missions);
+    let passed_missions_num = calc_missions_passed(missions);
 
     // amount earned equals amount multiplied by percentage of passed missions
     let amount_earned = member.amount
@@ -285,9 +356,9 @@ pub fn execute_ust_withdraw(
     recipient: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized treasurer
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Treasurer, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -310,9 +381,9 @@ pub fn execute_token_withdraw(
     token: String,
     recipient: String,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized treasurer
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Treasurer, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -370,6 +441,9 @@ fn must_pay_fee(info: &MessageInfo, cfg: &Config, operation: String) -> Result<(
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps.storage)?),
+        QueryMsg::RoleMembers { role, start_after, limit } =>
+            to_binary(&query_role_members(deps, role, start_after, limit)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
         QueryMsg::Member { address } => to_binary(&query_member(deps, address)?),
         QueryMsg::ListMembers { start_after, limit } =>
@@ -378,11 +452,11 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
 }
 
 pub fn query_config(deps: Deps) -> StdResult<Config> {
-    Ok(CONFIG.load(deps.storage)?)
+    CONFIG.load(deps.storage)
 }
 
 pub fn query_state(deps: Deps) -> StdResult<State> {
-    Ok(STATE.load(deps.storage)?)
+    STATE.load(deps.storage)
 }
 
 pub fn query_member(deps: Deps, addr: String) -> StdResult<MemberResponse> {
@@ -451,7 +525,7 @@ fn check_missions(querier: &QuerierWrapper, cfg: &Config, addr: &Addr) -> StdRes
     if let Some(contract_addr) = cfg.mission_smart_contracts.lp_staking.clone() {
         let query = WasmQuery::Smart {
             contract_addr: contract_addr.to_string(),
-            msg: to_binary(&StakingQueryMsg::Member {
+            msg: to_binary(&StakingQueryMsg::Staker {
                 address: addr.to_string(),
             })?,
         }.into();
@@ -494,5 +568,5 @@ fn calc_missions_passed(missions: &Missions) -> u32 {
         passed += 1;
     }
 
-    return passed;
+    passed
 }
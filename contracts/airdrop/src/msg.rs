@@ -1,6 +1,8 @@
 use cosmwasm_std::Uint128;
+use cw0::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use roles::Role;
 use crate::state::FeeConfig;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -22,10 +24,25 @@ pub struct InstantiateMissionSmartContracts {
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     UpdateConfig {
-        owner: Option<String>,
         fee_config: Option<Vec<FeeConfig>>,
         mission_smart_contracts: Option<InstantiateMissionSmartContracts>,
     },
+    /// Only owner. Proposes a new owner, who has to accept the ownership before it expires.
+    /// The current owner keeps control until then.
+    ProposeOwner {
+        new_owner: String,
+        expires: Option<Expiration>,
+    },
+    /// Only pending owner. Takes over the ownership.
+    AcceptOwnership {},
+    /// Only owner or pending owner. Drops the ownership proposal.
+    CancelOwnershipProposal {},
+    /// Only owner. Leaves the contract without an owner, forever.
+    RenounceOwnership {},
+    /// Only admin. Gives the role to the address.
+    GrantRole { role: Role, address: String },
+    /// Only admin. Takes the role from the address.
+    RevokeRole { role: Role, address: String },
     Claim {},
     RegisterMembers (
         Vec<RegisterMemberItem>
@@ -50,6 +67,12 @@ pub struct MigrateMsg {}
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    PendingOwner {},
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     State {},
     Member {
         address: String
@@ -5,7 +5,8 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
-    pub owner: Addr,
+    /// None once the ownership is renounced
+    pub owner: Option<Addr>,
     pub terraland_token: Addr,
     pub fee_config: Vec<FeeConfig>,
     pub mission_smart_contracts: MissionSmartContracts,
@@ -1,13 +1,19 @@
 use std::env::current_dir;
 use std::fs::create_dir_all;
 
-use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+use cosmwasm_schema::{export_schema, export_schema_with_title, remove_schemas, schema_for};
+use cw4::{HooksResponse, TotalWeightResponse};
 use staking::state::Config as ConfigResponse;
+use ownership::PendingOwnerResponse;
+use roles::RoleMembersResponse;
 use staking::state::State as StateResponse;
 
 pub use staking::msg::{
     ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg, ReceiveMsg, MemberResponse,
-    MemberListResponse,
+    MemberListResponse, RewardRateResponse, AprResponse, ProjectedRewardResponse,
+    RemainingRewardsResponse, SimulateInstantClaimResponse, OwnerOfResponse, NftInfoResponse,
+    AllNftInfoResponse, TokensResponse, NumTokensResponse, ContractInfoResponse, Cw721ReceiveMsg,
+    SlashesResponse, MemberHistoryResponse, CapacityResponse, WhitelistResponse,
 };
 
 fn main() {
@@ -23,7 +29,29 @@ fn main() {
     export_schema(&schema_for!(MigrateMsg), &out_dir);
 
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(PendingOwnerResponse), &out_dir);
+    export_schema(&schema_for!(RoleMembersResponse), &out_dir);
     export_schema(&schema_for!(StateResponse), &out_dir);
     export_schema(&schema_for!(MemberListResponse), &out_dir);
     export_schema(&schema_for!(MemberResponse), &out_dir);
+    export_schema(&schema_for!(RewardRateResponse), &out_dir);
+    export_schema(&schema_for!(AprResponse), &out_dir);
+    export_schema(&schema_for!(ProjectedRewardResponse), &out_dir);
+    export_schema(&schema_for!(RemainingRewardsResponse), &out_dir);
+    export_schema(&schema_for!(SimulateInstantClaimResponse), &out_dir);
+    export_schema_with_title(&schema_for!(cw4::MemberResponse), &out_dir, "Cw4MemberResponse");
+    export_schema_with_title(&schema_for!(cw4::MemberListResponse), &out_dir, "Cw4MemberListResponse");
+    export_schema(&schema_for!(TotalWeightResponse), &out_dir);
+    export_schema(&schema_for!(HooksResponse), &out_dir);
+    export_schema(&schema_for!(SlashesResponse), &out_dir);
+    export_schema(&schema_for!(MemberHistoryResponse), &out_dir);
+    export_schema(&schema_for!(CapacityResponse), &out_dir);
+    export_schema(&schema_for!(WhitelistResponse), &out_dir);
+    export_schema(&schema_for!(OwnerOfResponse), &out_dir);
+    export_schema(&schema_for!(NftInfoResponse), &out_dir);
+    export_schema(&schema_for!(AllNftInfoResponse), &out_dir);
+    export_schema(&schema_for!(TokensResponse), &out_dir);
+    export_schema(&schema_for!(NumTokensResponse), &out_dir);
+    export_schema(&schema_for!(ContractInfoResponse), &out_dir);
+    export_schema(&schema_for!(Cw721ReceiveMsg), &out_dir);
 }
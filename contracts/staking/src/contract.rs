@@ -1,17 +1,22 @@
 use std::cmp;
-use std::ops::{Div, Mul};
+use std::convert::TryFrom;
+use std::str::FromStr;
 
-use cosmwasm_std::{Addr, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Env, from_slice, MessageInfo, Order, Response, StdError, StdResult, SubMsg, to_binary, Uint128, WasmMsg, WasmQuery};
+use cosmwasm_std::{Addr, Api, BankMsg, Binary, Coin, Decimal, Decimal256, Deps, DepsMut, Empty, Env, from_slice, MessageInfo, Order, Response, StdError, StdResult, Storage, SubMsg, to_binary, Uint128, Uint256, WasmMsg, WasmQuery};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cw0::{Duration, maybe_addr, must_pay};
+use cw0::{Duration, Expiration, maybe_addr, must_pay};
 use cw20::{Balance, BalanceResponse, Cw20CoinVerified, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
 use cw2::{get_contract_version, set_contract_version};
-use cw_storage_plus::Bound;
+use cw4::{HooksResponse, Member, MemberChangedHookMsg, MemberDiff, TotalWeightResponse};
+use cw_storage_plus::{Bound, U64Key};
+
+use ownership::{query_pending_owner};
+use roles::{is_authorized, query_role_members, Role};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MemberListResponse, MemberListResponseItem, MemberResponse, MemberResponseItem, MigrateMsg, NewConfig, QueryMsg, ReceiveMsg};
-use crate::state::{CLAIMS, Config, CONFIG, MemberInfo, MEMBERS, State, STATE};
+use crate::msg::{CapacityResponse, MemberHistoryResponse, SlashesResponse, WhitelistResponse, AllNftInfoResponse, AprResponse, ContractInfoResponse, Cw721ReceiveMsg, NftApproval, NftInfoResponse, NumTokensResponse, OwnerOfResponse, PositionNftExtension, TokensResponse, AssetAmount, AssetApr, ExecuteMsg, InstantiateMsg, MemberListResponse, MemberListResponseItem, MemberResponse, MemberResponseItem, MigrateMsg, NewConfig, PositionResponseItem, ProjectedRewardResponse, QueryMsg, ReceiveMsg, RemainingRewardsResponse, RewardRateResponse, SimulateInstantClaimResponse, RewardResponseItem};
+use crate::state::{HISTORY_SEQ, HistoryAction, HistoryEntry, MEMBER_HISTORY, WHITELIST, PenaltyDestination, SLASH_SEQ, SLASHES, SlashRecord, NUM_POSITIONS, OWNER_POSITIONS, POSITION_SEQ, POSITIONS, PositionApproval, StakingPosition, AssetInfo, AUTO_COMPOUND, Claim, CLAIM_SEQ, CLAIMS, TOTAL_CLAIMS, Config, CONFIG, HARVEST_CURSOR, HOOKS, legacy, LOCK_ENDS, LockedPosition, LockEnd, LockTier, MemberInfo, MemberReward, MEMBER_WEIGHTS, MEMBERS, RewardAsset, RewardIndex, Schedule, State, STATE};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:fcq-staking";
@@ -19,28 +24,51 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const YEAR_IN_SEC: u64 = 365*24*3600;
 
+// cw721 contract info of the position NFTs
+const POSITION_NFT_NAME: &str = "Staking Position";
+const POSITION_NFT_SYMBOL: &str = "STAKE";
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+// expired member history entries removed with a single new entry
+const MAX_PRUNED_HISTORY: usize = 10;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     let config = Config {
-        owner: deps.api.addr_validate(&msg.owner)?,
-        staking_token: deps.api.addr_validate(&msg.staking_token)?,
-        terraland_token: deps.api.addr_validate(&msg.terraland_token)?,
+        owner: Some(deps.api.addr_validate(&msg.owner)?),
+        staking_token: validate_asset_info(deps.api, msg.staking_token)?,
         unbonding_period: msg.unbonding_period,
         burn_address: deps.api.addr_validate(&msg.burn_address)?,
         instant_claim_percentage_loss: msg.instant_claim_percentage_loss,
-        distribution_schedule: msg.distribution_schedule,
+        reward_assets: validate_reward_assets(
+            deps.api, msg.reward_assets, &[], env.block.time.seconds())?,
         fee_config: msg.fee_config,
+        lock_tiers: validate_lock_tiers(msg.lock_tiers)?,
+        disable_rebond: msg.disable_rebond,
+        position_nfts: msg.position_nfts,
+        emergency: false,
+        penalty_destination: validate_penalty_destination(deps.api, msg.penalty_destination)?,
+        max_total_stake: msg.max_total_stake,
+        max_stake_per_address: msg.max_stake_per_address,
+        whitelist_until: msg.whitelist_until,
+        history_retention: msg.history_retention.filter(|r| *r != 0),
+        retired_assets: vec![],
     };
+    assert_penalty_destination(&config)?;
 
     let state = State {
         total_stake: Default::default(),
+        total_weight: Default::default(),
         last_updated: Default::default(),
         global_reward_index: Default::default(),
         num_of_members: Default::default(),
@@ -48,21 +76,338 @@ pub fn instantiate(
 
     CONFIG.save(deps.storage, &config)?;
     STATE.save(deps.storage, &state)?;
+    for address in msg.whitelist {
+        WHITELIST.save(deps.storage, &deps.api.addr_validate(&address)?, &Empty {})?;
+    }
 
     Ok(Response::default())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
     let version = get_contract_version(deps.storage)?;
     if version.contract != CONTRACT_NAME {
         return Err(ContractError::CannotMigrate {
             previous_contract: version.contract,
         });
     }
+
+    // config with a single reward in terraland tokens does not load as the current one
+    if let Ok(legacy_config) = legacy::CONFIG.load(deps.storage) {
+        migrate_single_reward(deps.storage, legacy_config)?;
+    }
+
+    // staking token was always a cw20 token before native coins were supported
+    if let Ok(legacy_config) = legacy::CW20_STAKING_CONFIG.load(deps.storage) {
+        CONFIG.save(deps.storage, &Config {
+            owner: legacy_config.owner,
+            staking_token: AssetInfo::Token { contract_addr: legacy_config.staking_token.into() },
+            unbonding_period: legacy_config.unbonding_period,
+            burn_address: legacy_config.burn_address,
+            instant_claim_percentage_loss: legacy_config.instant_claim_percentage_loss,
+            reward_assets: legacy_config.reward_assets,
+            fee_config: legacy_config.fee_config,
+            lock_tiers: legacy_config.lock_tiers,
+            disable_rebond: legacy_config.disable_rebond,
+            position_nfts: false,
+            emergency: false,
+            penalty_destination: PenaltyDestination::Burn {},
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: 0,
+            history_retention: None,
+            retired_assets: vec![],
+        })?;
+    }
+
+    // nothing is locked before lock tiers, so the weight equals the stake
+    let mut state = STATE.load(deps.storage)?;
+    if state.total_weight.is_zero() && !state.total_stake.is_zero() {
+        state.total_weight = state.total_stake;
+        STATE.save(deps.storage, &state)?;
+    }
+
+    migrate_claim_ids(deps.storage)?;
+
+    // tokens owed to the members are tracked since the migration
+    if TOTAL_CLAIMS.may_load(deps.storage)?.is_none() {
+        migrate_owed_tokens(deps.storage)?;
+    }
+
+    // extra weight of the locked positions ends at the lock end since the migration
+    if LOCK_ENDS.range(deps.storage, None, None, Order::Ascending).next().is_none() {
+        migrate_lock_ends(deps.storage)?;
+    }
+
+    // cw4 weights are tracked since the migration
+    if MEMBER_WEIGHTS.range(deps.storage, None, None, Order::Ascending).next().is_none() {
+        migrate_member_weights(deps.storage, env.block.height)?;
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     Ok(Response::default())
 }
 
+/// Gives ids to the claims created before they were introduced
+fn migrate_claim_ids(storage: &mut dyn Storage) -> StdResult<()> {
+    let all_claims: StdResult<Vec<_>> = CLAIMS
+        .range(storage, None, None, Order::Ascending)
+        .collect();
+    let mut id = CLAIM_SEQ.may_load(storage)?.unwrap_or_default();
+    for (key, mut claims) in all_claims? {
+        if claims.iter().all(|c| c.id != 0) {
+            continue;
+        }
+        for claim in claims.iter_mut().filter(|c| c.id == 0) {
+            id += 1;
+            claim.id = id;
+        }
+        CLAIMS.save(storage, &Addr::unchecked(String::from_utf8(key)?), &claims)?;
+    }
+    CLAIM_SEQ.save(storage, &id)
+}
+
+/// Sums the existing claims and the rewards accrued until the last update and not withdrawn
+fn migrate_owed_tokens(storage: &mut dyn Storage) -> StdResult<()> {
+    let total_claims = CLAIMS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, claims)| claims.iter().map(|c| c.amount).sum::<Uint128>()))
+        .sum::<StdResult<Uint128>>()?;
+    TOTAL_CLAIMS.save(storage, &total_claims)?;
+
+    let members = MEMBERS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, info)| info))
+        .collect::<StdResult<Vec<_>>>()?;
+    let positions = POSITIONS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, position)| position.info))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut state = STATE.load(storage)?;
+    for global in state.global_reward_index.iter_mut() {
+        let mut unwithdrawn = Uint128::zero();
+        for info in members.iter().chain(positions.iter()) {
+            let reward = info.rewards.iter()
+                .find(|r| r.asset == global.asset)
+                .cloned()
+                .unwrap_or_default();
+            unwithdrawn += compute_member_reward(info.weight(), &reward, global.index)? - reward.withdrawn;
+        }
+        global.unwithdrawn = unwithdrawn;
+    }
+    STATE.save(storage, &state)
+}
+
+/// Schedules the extra weight of the locked positions to be removed at their lock end. The
+/// positions past the lock end stop counting at the migration.
+fn migrate_lock_ends(storage: &mut dyn Storage) -> StdResult<()> {
+    let mut positions = MEMBERS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, info)| info.positions))
+        .collect::<StdResult<Vec<_>>>()?
+        .concat();
+    positions.extend(POSITIONS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, position)| position.info.positions))
+        .collect::<StdResult<Vec<_>>>()?
+        .concat());
+
+    let mut state = STATE.load(storage)?;
+    for position in positions {
+        if position.lock_end > state.last_updated {
+            add_lock_end_weight(storage, position.lock_end, position.extra_weight())?;
+        } else {
+            state.total_weight = state.total_weight.saturating_sub(position.extra_weight());
+            LOCK_ENDS.save(storage, U64Key::new(position.lock_end), &LockEnd {
+                extra_weight: Uint128::zero(),
+                reward_index: Some(state.global_reward_index.clone()),
+            })?;
+        }
+    }
+    STATE.save(storage, &state)
+}
+
+/// Sets cw4 weights of the existing members
+fn migrate_member_weights(storage: &mut dyn Storage, height: u64) -> StdResult<()> {
+    let members: StdResult<Vec<_>> = MEMBERS
+        .range(storage, None, None, Order::Ascending)
+        .collect();
+    for (key, _) in members? {
+        let address = Addr::unchecked(String::from_utf8(key)?);
+        update_member_weight(storage, height, &address)?;
+    }
+
+    Ok(())
+}
+
+/// Converts the single reward stream into the first reward asset
+fn migrate_single_reward(storage: &mut dyn Storage, legacy_config: legacy::Config) -> StdResult<()> {
+    let asset = legacy_config.terraland_token.to_string();
+
+    CONFIG.save(storage, &Config {
+        owner: legacy_config.owner,
+        staking_token: AssetInfo::Token { contract_addr: legacy_config.staking_token.into() },
+        unbonding_period: legacy_config.unbonding_period,
+        burn_address: legacy_config.burn_address,
+        instant_claim_percentage_loss: legacy_config.instant_claim_percentage_loss,
+        reward_assets: vec![RewardAsset {
+            info: AssetInfo::Token { contract_addr: asset.clone() },
+            distribution_schedule: legacy_config.distribution_schedule,
+        }],
+        fee_config: legacy_config.fee_config,
+        lock_tiers: vec![],
+        disable_rebond: false,
+        position_nfts: false,
+        emergency: false,
+        penalty_destination: PenaltyDestination::Burn {},
+        max_total_stake: None,
+        max_stake_per_address: None,
+        whitelist_until: 0,
+        history_retention: None,
+        retired_assets: vec![],
+    })?;
+
+    let legacy_state = legacy::STATE.load(storage)?;
+    STATE.save(storage, &State {
+        total_stake: legacy_state.total_stake,
+        total_weight: legacy_state.total_stake,
+        last_updated: legacy_state.last_updated,
+        global_reward_index: vec![RewardIndex {
+            asset: asset.clone(),
+            index: to_decimal256(legacy_state.global_reward_index)?,
+            remainder: Uint128::zero(),
+            unwithdrawn: Uint128::zero(),
+        }],
+        num_of_members: legacy_state.num_of_members,
+    })?;
+
+    let members: StdResult<Vec<_>> = legacy::MEMBERS
+        .range(storage, None, None, Order::Ascending)
+        .collect();
+    for (key, info) in members? {
+        let address = Addr::unchecked(String::from_utf8(key)?);
+        MEMBERS.save(storage, &address, &MemberInfo {
+            stake: info.stake,
+            rewards: vec![MemberReward {
+                asset: asset.clone(),
+                pending_reward: info.pending_reward,
+                reward_index: to_decimal256(info.reward_index)?,
+                withdrawn: info.withdrawn,
+            }],
+            positions: vec![],
+        })?;
+    }
+
+    Ok(())
+}
+
+fn validate_penalty_destination(
+    api: &dyn Api,
+    destination: PenaltyDestination,
+) -> StdResult<PenaltyDestination> {
+    match destination {
+        PenaltyDestination::Treasury { address } => Ok(PenaltyDestination::Treasury {
+            address: api.addr_validate(&address)?.into(),
+        }),
+        destination => Ok(destination),
+    }
+}
+
+/// Redistributed penalty is a reward in staking tokens
+fn assert_penalty_destination(cfg: &Config) -> Result<(), ContractError> {
+    if cfg.penalty_destination == (PenaltyDestination::Redistribute {})
+        && !cfg.reward_assets.iter().any(|a| a.info == cfg.staking_token) {
+        return Err(ContractError::CannotRedistribute {});
+    }
+    Ok(())
+}
+
+/// Every tier locks for some time, a lock ending when it starts would never be passed by
+/// the reward accrual
+fn validate_lock_tiers(lock_tiers: Vec<LockTier>) -> Result<Vec<LockTier>, ContractError> {
+    for (i, tier) in lock_tiers.iter().enumerate() {
+        if tier.multiplier < Decimal::one() {
+            return Err(ContractError::InvalidLockTier {});
+        }
+        if tier.duration == 0 {
+            return Err(ContractError::ZeroLockDuration {});
+        }
+        if lock_tiers[..i].iter().any(|t| t.duration == tier.duration) {
+            return Err(ContractError::DuplicateLockTier(tier.duration));
+        }
+    }
+    Ok(lock_tiers)
+}
+
+/// Validates token addresses and makes sure every asset is listed once. Schedules of
+/// the existing assets cannot change, schedules of the new ones cannot start in the past.
+fn validate_reward_assets(
+    api: &dyn Api,
+    reward_assets: Vec<RewardAsset>,
+    existing: &[RewardAsset],
+    time: u64,
+) -> Result<Vec<RewardAsset>, ContractError> {
+    let mut validated: Vec<RewardAsset> = vec![];
+    for mut reward_asset in reward_assets {
+        reward_asset.info = validate_asset_info(api, reward_asset.info)?;
+        if validated.iter().any(|a| a.info.id() == reward_asset.info.id()) {
+            return Err(ContractError::DuplicateRewardAsset(reward_asset.info.id().to_string()));
+        }
+
+        match existing.iter().find(|a| a.info == reward_asset.info) {
+            Some(current) => {
+                if current.distribution_schedule != reward_asset.distribution_schedule {
+                    return Err(ContractError::ScheduleChanged(reward_asset.info.id().to_string()));
+                }
+            }
+            None => {
+                validate_schedules(&reward_asset.distribution_schedule)?;
+                if let Some(first) = reward_asset.distribution_schedule.first() {
+                    assert_not_in_past(first, time)?;
+                }
+            }
+        }
+        validated.push(reward_asset);
+    }
+    Ok(validated)
+}
+
+fn validate_asset_info(api: &dyn Api, asset: AssetInfo) -> StdResult<AssetInfo> {
+    match asset {
+        AssetInfo::Token { contract_addr } => Ok(AssetInfo::Token {
+            contract_addr: api.addr_validate(&contract_addr)?.into(),
+        }),
+        native => Ok(native),
+    }
+}
+
+/// Schedules have to be sorted, cannot overlap and have to last
+fn validate_schedules(distribution_schedule: &[Schedule]) -> Result<(), ContractError> {
+    for (i, schedule) in distribution_schedule.iter().enumerate() {
+        if schedule.end_time <= schedule.start_time {
+            return Err(ContractError::InvalidSchedule {
+                reason: "schedule has to end after it starts".to_string(),
+            });
+        }
+        if i > 0 && schedule.start_time < distribution_schedule[i - 1].end_time {
+            return Err(ContractError::InvalidSchedule {
+                reason: "schedules have to be sorted and cannot overlap".to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn assert_not_in_past(schedule: &Schedule, time: u64) -> Result<(), ContractError> {
+    if schedule.start_time < time {
+        return Err(ContractError::InvalidSchedule {
+            reason: "schedule cannot start in the past".to_string(),
+        });
+    }
+    Ok(())
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -72,11 +417,47 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::UpdateConfig(new_config) => execute_update_config(deps, env, info, new_config),
+        ExecuteMsg::AddSchedule { asset, schedule } =>
+            execute_add_schedule(deps, env, info, asset, schedule),
+        ExecuteMsg::ExtendSchedule { asset, end_time, amount } =>
+            execute_extend_schedule(deps, env, info, asset, end_time, amount),
+        ExecuteMsg::CancelFutureSchedule { asset, start_time } =>
+            execute_cancel_future_schedule(deps, env, info, asset, start_time),
+        ExecuteMsg::ProposeOwner { new_owner, expires } =>
+            execute_propose_owner(deps, env, info, new_owner, expires),
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => execute_cancel_ownership_proposal(deps, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, info),
+        ExecuteMsg::GrantRole { role, address } => execute_grant_role(deps, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => execute_revoke_role(deps, info, role, address),
+        ExecuteMsg::UpdateWhitelist { add, remove } => execute_update_whitelist(deps, info, add, remove),
+        ExecuteMsg::AddHook { addr } => execute_add_hook(deps, info, addr),
+        ExecuteMsg::RemoveHook { addr } => execute_remove_hook(deps, info, addr),
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+        ExecuteMsg::Bond { lock_duration } =>
+            execute_bond(deps, env, Balance::from(info.funds), info.sender, lock_duration),
+        ExecuteMsg::UnbondPosition { token_id, tokens } =>
+            execute_unbond_position(deps, env, info, token_id, tokens),
+        ExecuteMsg::TransferNft { recipient, token_id } =>
+            execute_transfer_nft(deps, env, info, recipient, token_id),
+        ExecuteMsg::SendNft { contract, token_id, msg } =>
+            execute_send_nft(deps, env, info, contract, token_id, msg),
+        ExecuteMsg::Approve { spender, token_id, expires } =>
+            execute_approve(deps, env, info, spender, token_id, expires),
+        ExecuteMsg::Revoke { spender, token_id } => execute_revoke(deps, info, spender, token_id),
+        ExecuteMsg::Slash { address, percentage, reason } =>
+            execute_slash(deps, env, info, address, percentage, reason),
+        ExecuteMsg::EnableEmergency {} => execute_enable_emergency(deps, info),
+        ExecuteMsg::EmergencyWithdraw {} => execute_emergency_withdraw(deps, env, info),
         ExecuteMsg::Unbond { tokens: amount } => execute_unbond(deps, env, info, amount),
-        ExecuteMsg::Claim {} => execute_claim(deps, env, info),
-        ExecuteMsg::InstantClaim {} => execute_instant_claim(deps, env, info),
-        ExecuteMsg::Withdraw {} => execute_withdraw(deps, env, info),
+        ExecuteMsg::Rebond { amount } => execute_rebond(deps, env, info, amount),
+        ExecuteMsg::Claim { amount } => execute_claim(deps, env, info, amount),
+        ExecuteMsg::InstantClaim { amount, claim_ids } =>
+            execute_instant_claim(deps, env, info, amount, claim_ids),
+        ExecuteMsg::Withdraw { assets } => execute_withdraw(deps, env, info, assets),
+        ExecuteMsg::Compound {} => execute_compound(deps, env, info),
+        ExecuteMsg::SetAutoCompound { enabled } => execute_set_auto_compound(deps, info, enabled),
+        ExecuteMsg::Harvest { limit } => execute_harvest(deps, env, limit),
         ExecuteMsg::UstWithdraw { recipient, amount } =>
             execute_ust_withdraw(deps, env, info, recipient, amount),
         ExecuteMsg::TokenWithdraw { token, recipient } =>
@@ -86,24 +467,50 @@ pub fn execute(
 
 pub fn execute_update_config(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     new_config: NewConfig,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized admin
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Admin, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
     let api = deps.api;
 
-    CONFIG.update(deps.storage, |mut exists| -> StdResult<_> {
-        if let Some(addr) = new_config.owner {
-            exists.owner = api.addr_validate(&addr)?;
+    // stake and claims are paid out in the bonded asset
+    let staking_token = match new_config.staking_token.clone() {
+        Some(asset) => {
+            let asset = validate_asset_info(api, asset)?;
+            let owed = STATE.load(deps.storage)?.total_stake
+                + TOTAL_CLAIMS.may_load(deps.storage)?.unwrap_or_default();
+            if asset != cfg.staking_token && !owed.is_zero() {
+                return Err(ContractError::StakingTokenInUse {});
+            }
+            Some(asset)
+        }
+        None => None,
+    };
+
+    let reward_assets = match new_config.reward_assets.clone() {
+        Some(reward_assets) => {
+            let reward_assets = validate_reward_assets(
+                api, reward_assets, &cfg.reward_assets, env.block.time.seconds())?;
+
+            // rewards until now are accrued according to the current schedules
+            checkpoint_rewards(deps.storage, &cfg, env.block.time.seconds())?;
+
+            Some(reward_assets)
         }
-        if let Some(addr) = new_config.staking_token {
-            exists.staking_token = api.addr_validate(&addr)?;
+        None => None,
+    };
+    let lock_tiers = new_config.lock_tiers.clone().map(validate_lock_tiers).transpose()?;
+    let previous_assets: Vec<_> = cfg.reward_assets.iter().map(|a| a.info.clone()).collect();
+
+    let cfg = CONFIG.update(deps.storage, |mut exists| -> StdResult<_> {
+        if let Some(asset) = staking_token {
+            exists.staking_token = asset;
         }
         if let Some(addr) = new_config.burn_address {
             exists.burn_address = api.addr_validate(&addr)?;
@@ -114,352 +521,1946 @@ pub fn execute_update_config(
         if let Some(percentage) = new_config.instant_claim_percentage_loss {
             exists.instant_claim_percentage_loss = percentage;
         }
-        if let Some(schedule) = new_config.distribution_schedule {
-            exists.distribution_schedule = schedule;
+        if let Some(reward_assets) = reward_assets {
+            // removed assets stay withdrawable, added ones are not retired anymore
+            for removed in exists.reward_assets.iter().filter(|a| !reward_assets.iter().any(|r| r.info == a.info)) {
+                exists.retired_assets.push(removed.info.clone());
+            }
+            exists.retired_assets.retain(|a| !reward_assets.iter().any(|r| r.info == *a));
+            exists.reward_assets = reward_assets;
         }
         if let Some(fee_config) = new_config.fee_config {
             exists.fee_config = fee_config;
         }
+        if let Some(lock_tiers) = lock_tiers {
+            exists.lock_tiers = lock_tiers;
+        }
+        if let Some(disable_rebond) = new_config.disable_rebond {
+            exists.disable_rebond = disable_rebond;
+        }
+        if let Some(position_nfts) = new_config.position_nfts {
+            exists.position_nfts = position_nfts;
+        }
+        if let Some(destination) = new_config.penalty_destination {
+            exists.penalty_destination = validate_penalty_destination(api, destination)?;
+        }
+        if let Some(max) = new_config.max_total_stake {
+            exists.max_total_stake = Some(max).filter(|m| !m.is_zero());
+        }
+        if let Some(max) = new_config.max_stake_per_address {
+            exists.max_stake_per_address = Some(max).filter(|m| !m.is_zero());
+        }
+        if let Some(time) = new_config.whitelist_until {
+            exists.whitelist_until = time;
+        }
+        if let Some(retention) = new_config.history_retention {
+            exists.history_retention = Some(retention).filter(|r| *r != 0);
+        }
         Ok(exists)
     })?;
+    assert_penalty_destination(&cfg)?;
+
+    // schedules of new reward assets have to be funded as if added with `AddSchedule`
+    for reward_asset in cfg.reward_assets.iter().filter(|a| !previous_assets.contains(&a.info)) {
+        assert_schedule_funded(deps.as_ref(), &env, &cfg.staking_token, reward_asset)?;
+    }
 
     Ok(Response::new()
         .add_attribute("action", "update_config")
         .add_attribute("sender", info.sender))
 }
 
-pub fn execute_receive(
+pub fn execute_add_schedule(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    wrapper: Cw20ReceiveMsg,
+    asset: String,
+    schedule: Schedule,
 ) -> Result<Response, ContractError> {
-    // info.sender is the address of the cw20 contract (that re-sent this message).
-    // wrapper.sender is the address of the user that requested the cw20 contract to send this.
-    // This cannot be fully trusted (the cw20 contract can fake it), so only use it for actions
-    // in the address's favor (like paying/bonding tokens, not withdrawls)
-    let msg: ReceiveMsg = from_slice(&wrapper.msg)?;
-    let balance = Balance::Cw20(Cw20CoinVerified {
-        address: info.sender,
-        amount: wrapper.amount,
-    });
-    let api = deps.api;
-    match msg {
-        ReceiveMsg::Bond {} => {
-            execute_bond(deps, env, balance, api.addr_validate(&wrapper.sender)?)
-        }
+    // authorized admin
+    let mut cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Admin, &info.sender) {
+        return Err(ContractError::Unauthorized {});
     }
+
+    let now = env.block.time.seconds();
+    checkpoint_rewards(deps.storage, &cfg, now)?;
+
+    let reward_asset = find_reward_asset_mut(&mut cfg.reward_assets, &asset)?;
+    assert_not_in_past(&schedule, now)?;
+    reward_asset.distribution_schedule.push(schedule);
+    validate_schedules(&reward_asset.distribution_schedule)?;
+    assert_schedule_funded(deps.as_ref(), &env, &cfg.staking_token, reward_asset)?;
+
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_schedule")
+        .add_attribute("asset", asset)
+        .add_attribute("sender", info.sender))
 }
 
-pub fn execute_bond(
+pub fn execute_extend_schedule(
     deps: DepsMut,
     env: Env,
-    amount: Balance,
-    sender: Addr,
+    info: MessageInfo,
+    asset: String,
+    end_time: u64,
+    amount: Uint128,
 ) -> Result<Response, ContractError> {
-    let cfg = CONFIG.load(deps.storage)?;
-
-    // ensure the sent token was proper
-    let amount = match &amount {
-        Balance::Cw20(token) => {
-            if token.address == cfg.staking_token {
-                Ok(token.amount)
-            } else {
-                Err(ContractError::InvalidToken(token.address.to_string()))
-            }
-        }
-        _ => Err(ContractError::MissedToken {})
-    }?;
-
-    let mut state = STATE.load(deps.storage)?;
-    let mut member_info = MEMBERS.may_load(deps.storage, &sender)?
-        .unwrap_or(Default::default());
+    // authorized admin
+    let mut cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Admin, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    // compute reward and updates member info with new rewards
-    update_member_reward(&state, &cfg, env.block.time.seconds(), &mut member_info)?;
+    let now = env.block.time.seconds();
+    checkpoint_rewards(deps.storage, &cfg, now)?;
 
-    // update member stake
-    member_info.stake += amount;
+    let reward_asset = find_reward_asset_mut(&mut cfg.reward_assets, &asset)?;
+    let schedules = &mut reward_asset.distribution_schedule;
+    let last = schedules.last_mut().ok_or(ContractError::ScheduleNotFound {})?;
+    if last.end_time <= now {
+        return Err(ContractError::InvalidSchedule {
+            reason: "last schedule has ended, add a new one".to_string(),
+        });
+    }
+    if end_time <= last.end_time {
+        return Err(ContractError::InvalidSchedule {
+            reason: "schedule can only end later".to_string(),
+        });
+    }
 
-    // update state with new stake and global_reward_index
-    state.total_stake += amount;
-    state.last_updated = env.block.time.seconds();
-    state.global_reward_index = member_info.reward_index;
-    if !MEMBERS.has(deps.storage, &sender) {
-        state.num_of_members += 1;
+    if last.start_time < now {
+        // split the running schedule, so the elapsed part keeps its rate
+        let elapsed = distributed_until(last, now);
+        let rest = Schedule {
+            amount: last.amount - elapsed + amount,
+            start_time: now,
+            end_time,
+        };
+        last.amount = elapsed;
+        last.end_time = now;
+        schedules.push(rest);
+    } else {
+        last.amount += amount;
+        last.end_time = end_time;
     }
+    assert_schedule_funded(deps.as_ref(), &env, &cfg.staking_token, reward_asset)?;
 
-    // save new member info and state in storage
-    MEMBERS.save(deps.storage, &sender, &member_info)?;
-    STATE.save(deps.storage, &state)?;
+    CONFIG.save(deps.storage, &cfg)?;
 
     Ok(Response::new()
-        .add_attribute("action", "bond")
+        .add_attribute("action", "extend_schedule")
+        .add_attribute("asset", asset)
+        .add_attribute("end_time", end_time.to_string())
         .add_attribute("amount", amount)
-        .add_attribute("sender", sender))
+        .add_attribute("sender", info.sender))
 }
 
-fn update_member_reward(state: &State, cfg: &Config, time: u64, member_info: &mut MemberInfo) -> StdResult<()> {
-    let global_reward_index = compute_reward_index(&cfg, &state, time)?;
-
-    let reward = compute_member_reward(&member_info, global_reward_index);
-
-    member_info.reward_index = global_reward_index;
-    member_info.pending_reward = reward;
+pub fn execute_cancel_future_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: String,
+    start_time: u64,
+) -> Result<Response, ContractError> {
+    // authorized admin
+    let mut cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Admin, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    Ok(())
-}
+    let now = env.block.time.seconds();
+    checkpoint_rewards(deps.storage, &cfg, now)?;
 
-fn compute_reward_index(cfg: &Config, state: &State, time: u64) -> StdResult<Decimal> {
-    // if there is first stake, the reward index is 0
-    if state.last_updated == 0 {
-        return Ok(Decimal::zero());
+    let reward_asset = find_reward_asset_mut(&mut cfg.reward_assets, &asset)?;
+    let i = reward_asset.distribution_schedule.iter()
+        .position(|s| s.start_time == start_time)
+        .ok_or(ContractError::ScheduleNotFound {})?;
+    if start_time <= now {
+        return Err(ContractError::InvalidSchedule {
+            reason: "schedule has already started".to_string(),
+        });
     }
+    reward_asset.distribution_schedule.remove(i);
 
-    // if we are outside distribution schedule then Error
-    let (i, j) = find_distribution_schedule_range(
-        &cfg, state.last_updated, time);
-
-    let mut distributed_amount = Uint128::zero();
+    CONFIG.save(deps.storage, &cfg)?;
 
-    for id in i..=j {
-        if id < 0 || id >= cfg.distribution_schedule.len() as i32 {
-            continue;
-        }
+    Ok(Response::new()
+        .add_attribute("action", "cancel_future_schedule")
+        .add_attribute("asset", asset)
+        .add_attribute("start_time", start_time.to_string())
+        .add_attribute("sender", info.sender))
+}
 
-        let schedule = &cfg.distribution_schedule[id as usize];
+fn find_reward_asset_mut<'a>(
+    reward_assets: &'a mut [RewardAsset],
+    asset: &str,
+) -> Result<&'a mut RewardAsset, ContractError> {
+    reward_assets.iter_mut()
+        .find(|a| a.info.id() == asset)
+        .ok_or_else(|| ContractError::UnknownRewardAsset(asset.to_string()))
+}
 
-        // compute distributed amount per second for current schedule
-        let distributed_amount_per_sec = schedule.amount
-            .div(Uint128::from(schedule.end_time - schedule.start_time));
+/// Accrues rewards until the given time according to the current schedules
+fn checkpoint_rewards(storage: &mut dyn Storage, cfg: &Config, time: u64) -> StdResult<()> {
+    let mut state = STATE.load(storage)?;
+    update_global_reward_index(storage, cfg, &mut state, time)?;
+    STATE.save(storage, &state)
+}
 
-        // distributed amount per second multiplied by time elapsed in this schedule
-        distributed_amount += distributed_amount_per_sec
-            .mul(Uint128::from(cmp::min(time, schedule.end_time) -
-                cmp::max(state.last_updated, schedule.start_time)));
+/// Fails unless the contract holds tokens for the rest of the schedule on top of what it
+/// owes: rewards distributed and not withdrawn yet, and for the staking token also the stake
+/// and the claims. Rewards have to be accrued until now.
+fn assert_schedule_funded(
+    deps: Deps,
+    env: &Env,
+    staking_token: &AssetInfo,
+    reward_asset: &RewardAsset,
+) -> Result<(), ContractError> {
+    let required = remaining_rewards(&reward_asset.distribution_schedule, env.block.time.seconds());
+    if required.is_zero() {
+        return Ok(());
     }
 
-    // global reward index is increased by distributed amount per staked token
-    if state.total_stake.is_zero() {
-        Ok(state.global_reward_index)
-    } else {
-        Ok(state.global_reward_index
-            + Decimal::from_ratio(distributed_amount, state.total_stake))
+    let state = STATE.load(deps.storage)?;
+    let mut owed = global_reward_index(&state, reward_asset.info.id()).unwithdrawn;
+    if reward_asset.info == *staking_token {
+        owed += state.total_stake + TOTAL_CLAIMS.may_load(deps.storage)?.unwrap_or_default();
+    }
+    let balance = query_asset_balance(deps, &reward_asset.info, &env.contract.address)?
+        .saturating_sub(owed);
+
+    if balance < required {
+        return Err(ContractError::InsufficientRewardFunds {
+            asset: reward_asset.info.id().to_string(),
+            balance,
+            required,
+        });
     }
+    Ok(())
 }
 
-fn find_distribution_schedule_range(cfg: &Config, start_time: u64, end_time: u64) -> (i32, i32) {
-    let mut start = -1;
-    let mut end = -1;
-
-    for (i, schedule) in cfg.distribution_schedule.iter().enumerate() {
-        if start_time >= schedule.start_time && start_time < schedule.end_time {
-            start = i as i32
-        } else if start_time >= schedule.end_time {
-            start = (i + 1) as i32
-        }
-
-        if end_time >= schedule.start_time && end_time < schedule.end_time {
-            end = i as i32
-        } else if start_time >= schedule.end_time {
-            end = (i + 1) as i32
+fn query_asset_balance(deps: Deps, asset: &AssetInfo, address: &Addr) -> StdResult<Uint128> {
+    match asset {
+        AssetInfo::Token { contract_addr } => {
+            let res: BalanceResponse = deps.querier.query_wasm_smart(
+                contract_addr,
+                &Cw20QueryMsg::Balance { address: address.to_string() },
+            )?;
+            Ok(res.balance)
         }
+        AssetInfo::NativeToken { denom } => Ok(deps.querier.query_balance(address, denom)?.amount),
     }
+}
 
-    (start, end)
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_owner: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(ownership::execute_propose_owner(deps, &env, &info, &cfg.owner, new_owner, expires)?)
 }
 
-fn compute_member_reward(member_info: &MemberInfo, global_reward_index: Decimal) -> Uint128 {
-    let pending_reward = member_info.stake * global_reward_index
-        - member_info.stake * member_info.reward_index;
+pub fn execute_accept_ownership(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let (new_owner, res) = ownership::execute_accept_ownership(deps.branch(), &env, &info)?;
+
+    CONFIG.update(deps.storage, |mut existing_config| -> StdResult<_> {
+        existing_config.owner = Some(new_owner);
+        Ok(existing_config)
+    })?;
 
-    return member_info.pending_reward + pending_reward;
+    Ok(res)
 }
 
-pub fn execute_unbond(
+pub fn execute_cancel_ownership_proposal(
     deps: DepsMut,
-    env: Env,
     info: MessageInfo,
-    amount: Uint128,
 ) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
+    Ok(ownership::execute_cancel_ownership_proposal(deps, &info, &cfg.owner)?)
+}
 
-    // sender has to pay fee to unbond
-    must_pay_fee(&info, &cfg, "unbond".to_string())?;
+pub fn execute_renounce_ownership(
+    mut deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    let res = ownership::execute_renounce_ownership(deps.branch(), &info, &cfg.owner)?;
 
-    // provide them a claim
-    CLAIMS.create_claim(
-        deps.storage,
-        &info.sender,
-        amount,
-        Duration::Time(cfg.unbonding_period).after(&env.block),
-    )?;
+    cfg.owner = None;
+    CONFIG.save(deps.storage, &cfg)?;
 
-    let mut state = STATE.load(deps.storage)?;
-    let mut member_info = MEMBERS.may_load(deps.storage, &info.sender)?
-        .ok_or(ContractError::MemberNotFound {})?;
+    Ok(res)
+}
 
-    // compute reward and updates member info with new rewards
-    update_member_reward(&state, &cfg, env.block.time.seconds(), &mut member_info)?;
+pub fn execute_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(roles::execute_grant_role(deps, &info, &cfg.owner, role, address)?)
+}
 
-    // update member stake
-    member_info.stake = member_info.stake.checked_sub(amount).map_err(StdError::overflow)?;
+pub fn execute_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(roles::execute_revoke_role(deps, &info, &cfg.owner, role, address)?)
+}
+
+pub fn execute_update_whitelist(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    // authorized admin
+    let cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Admin, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for address in add.iter() {
+        WHITELIST.save(deps.storage, &deps.api.addr_validate(address)?, &Empty {})?;
+    }
+    for address in remove.iter() {
+        WHITELIST.remove(deps.storage, &deps.api.addr_validate(address)?);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_whitelist")
+        .add_attribute("sender", info.sender)
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
 
+pub fn execute_add_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    // authorized admin
+    let cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Admin, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    HOOKS.add_hook(deps.storage, deps.api.addr_validate(&addr)?)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_hook")
+        .add_attribute("sender", info.sender)
+        .add_attribute("hook", addr))
+}
+
+pub fn execute_remove_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    // authorized admin
+    let cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Admin, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    HOOKS.remove_hook(deps.storage, deps.api.addr_validate(&addr)?)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_hook")
+        .add_attribute("sender", info.sender)
+        .add_attribute("hook", addr))
+}
+
+pub fn execute_unbond_position(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    assert_not_emergency(&cfg)?;
+
+    // sender has to pay fee to unbond
+    must_pay_fee(&info, &cfg, "unbond".to_string())?;
+
+    let mut position = load_position(deps.storage, &token_id)?;
+    if position.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // provide them a claim
+    create_claim(
+        deps.storage,
+        &info.sender,
+        amount,
+        Duration::Time(cfg.unbonding_period).after(&env.block),
+    )?;
+
+    let mut state = STATE.load(deps.storage)?;
+    update_global_reward_index(deps.storage, &cfg, &mut state, env.block.time.seconds())?;
+    update_member_reward(deps.storage, &cfg, &state, &mut position.info)?;
+    unstake_tokens(&mut state, &mut position.info, amount)?;
+
+    // nothing is left, the position is burnt after paying out its rewards
+    let mut messages = vec![];
+    if position.info.stake.is_zero() {
+        messages = reward_payout_msgs(deps.storage, &env, &cfg, &mut state, &mut position.info, &info.sender)?;
+        burn_position(deps.storage, &info.sender, &token_id)?;
+    } else {
+        POSITIONS.save(deps.storage, &token_id, &position)?;
+    }
+    STATE.save(deps.storage, &state)?;
+    record_history(
+        deps.storage, &env, &cfg, &info.sender, HistoryAction::Unbond, cfg.staking_token.id(), amount)?;
+
+    let diffs = update_member_weight(deps.storage, env.block.height, &info.sender)?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_submessages(member_changed_hook_msgs(deps.storage, diffs)?)
+        .add_attribute("action", "unbond_position")
+        .add_attribute("token_id", token_id)
+        .add_attribute("amount", amount)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_transfer_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let messages = transfer_position(deps.storage, &env, &info.sender, &recipient, &token_id)?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "transfer_nft")
+        .add_attribute("sender", info.sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("token_id", token_id))
+}
+
+pub fn execute_send_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    token_id: String,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let contract = deps.api.addr_validate(&contract)?;
+    let mut messages = transfer_position(deps.storage, &env, &info.sender, &contract, &token_id)?;
+
+    // let the contract know it received the position
+    let receive = Cw721ReceiveMsg {
+        sender: info.sender.to_string(),
+        token_id: token_id.clone(),
+        msg,
+    };
+    messages.push(SubMsg::new(receive.into_cosmos_msg(contract.clone())?));
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "send_nft")
+        .add_attribute("sender", info.sender)
+        .add_attribute("recipient", contract)
+        .add_attribute("token_id", token_id))
+}
+
+pub fn execute_approve(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    token_id: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let mut position = load_position(deps.storage, &token_id)?;
+    if position.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::ApprovalExpired {});
+    }
+
+    // the approval replaces the previous one of the spender
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    position.approvals.retain(|a| a.spender != spender_addr);
+    position.approvals.push(PositionApproval { spender: spender_addr, expires });
+    POSITIONS.save(deps.storage, &token_id, &position)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "approve")
+        .add_attribute("sender", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("token_id", token_id))
+}
+
+pub fn execute_revoke(
+    deps: DepsMut,
+    info: MessageInfo,
+    spender: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let mut position = load_position(deps.storage, &token_id)?;
+    if position.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    position.approvals.retain(|a| a.spender != spender_addr);
+    POSITIONS.save(deps.storage, &token_id, &position)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke")
+        .add_attribute("sender", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("token_id", token_id))
+}
+
+/// Moves the position to the recipient. Rewards accrued until now are paid to the previous
+/// holder, the recipient gets them from now on. Returns the payout and hook messages.
+fn transfer_position(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    recipient: &Addr,
+    token_id: &str,
+) -> Result<Vec<SubMsg>, ContractError> {
+    let cfg = CONFIG.load(storage)?;
+    assert_not_emergency(&cfg)?;
+    let mut position = load_position(storage, token_id)?;
+
+    // only the holder or an approved spender can transfer
+    let approved = position.approvals.iter()
+        .any(|a| a.spender == *sender && !a.expires.is_expired(&env.block));
+    if position.owner != *sender && !approved {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // the recipient holds the stake of the position from now on
+    if position.owner != *recipient {
+        if env.block.time.seconds() < cfg.whitelist_until && !WHITELIST.has(storage, recipient) {
+            return Err(ContractError::NotWhitelisted {});
+        }
+        assert_address_cap(storage, &cfg, recipient, position.info.stake)?;
+    }
+
+    let mut state = STATE.load(storage)?;
+    update_global_reward_index(storage, &cfg, &mut state, env.block.time.seconds())?;
+    update_member_reward(storage, &cfg, &state, &mut position.info)?;
+    let previous_owner = position.owner.clone();
+    let mut messages = reward_payout_msgs(storage, env, &cfg, &mut state, &mut position.info, &previous_owner)?;
+
+    OWNER_POSITIONS.remove(storage, (&previous_owner, token_id));
+    OWNER_POSITIONS.save(storage, (recipient, token_id), &Empty {})?;
+    position.owner = recipient.clone();
+    position.approvals = vec![];
+    POSITIONS.save(storage, token_id, &position)?;
+    STATE.save(storage, &state)?;
+
+    // the stake of the position counts to the cw4 weight of the holder
+    let mut diffs = update_member_weight(storage, env.block.height, &previous_owner)?;
+    diffs.extend(update_member_weight(storage, env.block.height, recipient)?);
+    messages.extend(member_changed_hook_msgs(storage, diffs)?);
+
+    Ok(messages)
+}
+
+/// Messages sending all rewards of the position not withdrawn yet to the recipient,
+/// recorded as withdrawn in their history
+fn reward_payout_msgs(
+    storage: &mut dyn Storage,
+    env: &Env,
+    cfg: &Config,
+    state: &mut State,
+    position: &mut MemberInfo,
+    recipient: &Addr,
+) -> StdResult<Vec<SubMsg>> {
+    let mut messages = vec![];
+    for asset in withdrawable_assets(cfg) {
+        let amount = take_reward(state, position, asset.id())?;
+        if !amount.is_zero() {
+            messages.push(transfer_asset_msg(asset, recipient, amount)?);
+            record_history(storage, env, cfg, recipient, HistoryAction::Withdraw, asset.id(), amount)?;
+        }
+    }
+    Ok(messages)
+}
+
+fn load_position(storage: &dyn Storage, token_id: &str) -> Result<StakingPosition, ContractError> {
+    POSITIONS.may_load(storage, token_id)?
+        .ok_or_else(|| ContractError::PositionNotFound(token_id.to_string()))
+}
+
+/// Saves the position under the next id
+fn mint_position(storage: &mut dyn Storage, owner: &Addr, info: MemberInfo) -> StdResult<String> {
+    let id = POSITION_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+    POSITION_SEQ.save(storage, &id)?;
+    let num_positions = NUM_POSITIONS.may_load(storage)?.unwrap_or_default();
+    NUM_POSITIONS.save(storage, &(num_positions + 1))?;
+
+    let token_id = id.to_string();
+    POSITIONS.save(storage, &token_id, &StakingPosition {
+        owner: owner.clone(),
+        approvals: vec![],
+        info,
+    })?;
+    OWNER_POSITIONS.save(storage, (owner, &token_id), &Empty {})?;
+    Ok(token_id)
+}
+
+fn burn_position(storage: &mut dyn Storage, owner: &Addr, token_id: &str) -> StdResult<()> {
+    POSITIONS.remove(storage, token_id);
+    OWNER_POSITIONS.remove(storage, (owner, token_id));
+    NUM_POSITIONS.update(storage, |n| -> StdResult<_> { Ok(n - 1) })?;
+    Ok(())
+}
+
+/// Positions held by the owner with their token ids
+fn held_positions(storage: &dyn Storage, owner: &Addr) -> StdResult<Vec<(String, StakingPosition)>> {
+    let token_ids: StdResult<Vec<_>> = OWNER_POSITIONS
+        .prefix(owner)
+        .keys(storage, None, None, Order::Ascending)
+        .map(|key| String::from_utf8(key).map_err(StdError::from))
+        .collect();
+    token_ids?.into_iter()
+        .map(|token_id| {
+            let position = POSITIONS.load(storage, &token_id)?;
+            Ok((token_id, position))
+        })
+        .collect()
+}
+
+pub fn execute_slash(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    percentage: Decimal,
+    reason: String,
+) -> Result<Response, ContractError> {
+    // authorized oracle
+    let cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Oracle, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if percentage.is_zero() || percentage > Decimal::one() {
+        return Err(ContractError::InvalidSlashPercentage {});
+    }
+
+    let addr = deps.api.addr_validate(&address)?;
+    let time = env.block.time.seconds();
+    let mut state = STATE.load(deps.storage)?;
+    update_global_reward_index(deps.storage, &cfg, &mut state, time)?;
+
+    // rewards accrued until now are kept
+    let mut amount = Uint128::zero();
+    if let Some(mut member_info) = MEMBERS.may_load(deps.storage, &addr)? {
+        update_member_reward(deps.storage, &cfg, &state, &mut member_info)?;
+        amount += slash_stake(deps.storage, &mut state, &mut member_info, percentage)?;
+        MEMBERS.save(deps.storage, &addr, &member_info)?;
+    }
+    for (token_id, mut position) in held_positions(deps.storage, &addr)? {
+        update_member_reward(deps.storage, &cfg, &state, &mut position.info)?;
+        amount += slash_stake(deps.storage, &mut state, &mut position.info, percentage)?;
+        POSITIONS.save(deps.storage, &token_id, &position)?;
+    }
+
+    let mut claims = CLAIMS.may_load(deps.storage, &addr)?.unwrap_or_default();
+    let mut slashed_claims = Uint128::zero();
+    for claim in claims.iter_mut() {
+        let slashed = claim.amount * percentage;
+        claim.amount -= slashed;
+        slashed_claims += slashed;
+    }
+    claims.retain(|c| !c.amount.is_zero());
+    CLAIMS.save(deps.storage, &addr, &claims)?;
+    reduce_total_claims(deps.storage, slashed_claims)?;
+    amount += slashed_claims;
+
+    if amount.is_zero() {
+        return Err(ContractError::NothingToSlash {});
+    }
+
+    let messages = penalty_msgs(&cfg, &mut state, amount)?;
+    STATE.save(deps.storage, &state)?;
+
+    let id = SLASH_SEQ.may_load(deps.storage)?.unwrap_or_default() + 1;
+    SLASH_SEQ.save(deps.storage, &id)?;
+    SLASHES.save(deps.storage, (&addr, U64Key::new(id)), &SlashRecord {
+        id,
+        percentage,
+        reason: reason.clone(),
+        amount,
+        time,
+    })?;
+    record_history(deps.storage, &env, &cfg, &addr, HistoryAction::Slash, cfg.staking_token.id(), amount)?;
+
+    let diffs = update_member_weight(deps.storage, env.block.height, &addr)?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_submessages(member_changed_hook_msgs(deps.storage, diffs)?)
+        .add_attribute("action", "slash")
+        .add_attribute("sender", info.sender)
+        .add_attribute("address", addr)
+        .add_attribute("percentage", percentage.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute("reason", reason))
+}
+
+/// Takes the percentage of the stake, locked positions lose the same part of their tokens.
+/// Member reward has to be up to date. Returns the slashed amount.
+fn slash_stake(
+    storage: &mut dyn Storage,
+    state: &mut State,
+    member_info: &mut MemberInfo,
+    percentage: Decimal,
+) -> StdResult<Uint128> {
+    let weight = member_info.weight();
+
+    let mut slashed = (member_info.stake - member_info.locked_stake()) * percentage;
+    for position in member_info.positions.iter_mut() {
+        let extra_weight = position.extra_weight();
+        let amount = position.amount * percentage;
+        position.amount -= amount;
+        slashed += amount;
+        remove_lock_end_weight(storage, position.lock_end, extra_weight - position.extra_weight())?;
+    }
+    member_info.positions.retain(|p| !p.amount.is_zero());
+    member_info.stake -= slashed;
+
+    state.total_stake -= slashed;
+    state.total_weight = state.total_weight - weight + member_info.weight();
+    Ok(slashed)
+}
+
+pub fn execute_enable_emergency(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // authorized pauser
+    let mut cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Pauser, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_not_emergency(&cfg)?;
+
+    cfg.emergency = true;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "enable_emergency")
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_emergency_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg.emergency {
+        return Err(ContractError::EmergencyNotActive {});
+    }
+
+    // stake is taken as it is, rewards are not computed
+    let mut state = STATE.load(deps.storage)?;
+    let mut amount = Uint128::zero();
+    if let Some(member_info) = MEMBERS.may_load(deps.storage, &info.sender)? {
+        amount += member_info.stake;
+        state.total_stake = state.total_stake.saturating_sub(member_info.stake);
+        remove_member_weight(deps.storage, &mut state, member_info)?;
+        state.num_of_members = state.num_of_members.saturating_sub(1);
+        MEMBERS.remove(deps.storage, &info.sender);
+        AUTO_COMPOUND.remove(deps.storage, &info.sender);
+    }
+    for (token_id, position) in held_positions(deps.storage, &info.sender)? {
+        amount += position.info.stake;
+        state.total_stake = state.total_stake.saturating_sub(position.info.stake);
+        remove_member_weight(deps.storage, &mut state, position.info)?;
+        burn_position(deps.storage, &info.sender, &token_id)?;
+    }
+
+    // claims are paid out whether released or not
+    let claims = CLAIMS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    let claimed = claims.iter().map(|c| c.amount).sum();
+    CLAIMS.remove(deps.storage, &info.sender);
+    reduce_total_claims(deps.storage, claimed)?;
+    amount += claimed;
+
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    STATE.save(deps.storage, &state)?;
+    record_history(
+        deps.storage, &env, &cfg, &info.sender, HistoryAction::EmergencyWithdraw, cfg.staking_token.id(), amount)?;
+
+    let diffs = update_member_weight(deps.storage, env.block.height, &info.sender)?;
+
+    Ok(Response::new()
+        .add_submessage(transfer_asset_msg(&cfg.staking_token, &info.sender, amount)?)
+        .add_submessages(member_changed_hook_msgs(deps.storage, diffs)?)
+        .add_attribute("action", "emergency_withdraw")
+        .add_attribute("amount", amount)
+        .add_attribute("sender", info.sender))
+}
+
+fn assert_not_emergency(cfg: &Config) -> Result<(), ContractError> {
+    if cfg.emergency {
+        return Err(ContractError::EmergencyActive {});
+    }
+    Ok(())
+}
+
+/// Saves the cw4 weight of the member, which is the stake including the held positions.
+/// Returns the change, if any.
+fn update_member_weight(
+    storage: &mut dyn Storage,
+    height: u64,
+    addr: &Addr,
+) -> StdResult<Vec<MemberDiff>> {
+    let stake = address_stake(storage, addr)?;
+    let old = MEMBER_WEIGHTS.may_load(storage, addr)?;
+    let new = if stake.is_zero() { None } else { Some(to_cw4_weight(stake)?) };
+    if old == new {
+        return Ok(vec![]);
+    }
+
+    match new {
+        Some(weight) => MEMBER_WEIGHTS.save(storage, addr, &weight, height)?,
+        None => MEMBER_WEIGHTS.remove(storage, addr, height)?,
+    }
+    Ok(vec![MemberDiff::new(addr, old, new)])
+}
+
+/// Member stake together with the held positions
+fn address_stake(storage: &dyn Storage, addr: &Addr) -> StdResult<Uint128> {
+    let positions_stake: Uint128 = held_positions(storage, addr)?.iter()
+        .map(|(_, p)| p.info.stake)
+        .sum();
+    Ok(MEMBERS.may_load(storage, addr)?.unwrap_or_default().stake + positions_stake)
+}
+
+fn to_cw4_weight(stake: Uint128) -> StdResult<u64> {
+    u64::try_from(stake.u128()).map_err(|_| StdError::generic_err("Stake exceeds the cw4 weight range"))
+}
+
+/// Messages notifying the hooks about the weight changes
+fn member_changed_hook_msgs(storage: &dyn Storage, diffs: Vec<MemberDiff>) -> StdResult<Vec<SubMsg>> {
+    if diffs.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let msg = MemberChangedHookMsg::new(diffs);
+    HOOKS.prepare_hooks(storage, |hook| msg.clone().into_cosmos_msg(hook).map(SubMsg::new))
+}
+
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // info.sender is the address of the cw20 contract (that re-sent this message).
+    // wrapper.sender is the address of the user that requested the cw20 contract to send this.
+    // This cannot be fully trusted (the cw20 contract can fake it), so only use it for actions
+    // in the address's favor (like paying/bonding tokens, not withdrawls)
+    let msg: ReceiveMsg = from_slice(&wrapper.msg)?;
+    let balance = Balance::Cw20(Cw20CoinVerified {
+        address: info.sender,
+        amount: wrapper.amount,
+    });
+    let api = deps.api;
+    match msg {
+        ReceiveMsg::Bond { lock_duration } => {
+            execute_bond(deps, env, balance, api.addr_validate(&wrapper.sender)?, lock_duration)
+        }
+    }
+}
+
+pub fn execute_bond(
+    deps: DepsMut,
+    env: Env,
+    amount: Balance,
+    sender: Addr,
+    lock_duration: Option<u64>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    // ensure the sent token was proper
+    let amount = match (&cfg.staking_token, &amount) {
+        (AssetInfo::Token { contract_addr }, Balance::Cw20(token)) => {
+            if token.address == *contract_addr {
+                Ok(token.amount)
+            } else {
+                Err(ContractError::InvalidToken(token.address.to_string()))
+            }
+        }
+        (AssetInfo::NativeToken { denom }, Balance::Native(coins)) => {
+            match coins.0.as_slice() {
+                [] => Err(ContractError::MissedToken {}),
+                [coin] if coin.denom == *denom && !coin.amount.is_zero() => Ok(coin.amount),
+                _ => Err(ContractError::InvalidToken(denom.clone())),
+            }
+        }
+        _ => Err(ContractError::MissedToken {})
+    }?;
+
+    let (messages, token_id) = add_stake(deps.storage, &env, &cfg, &sender, amount, lock_duration)?;
+    record_history(deps.storage, &env, &cfg, &sender, HistoryAction::Bond, cfg.staking_token.id(), amount)?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "bond")
+        .add_attribute("amount", amount)
+        .add_attribute("sender", sender)
+        .add_attributes(token_id.map(|id| ("token_id", id))))
+}
+
+pub fn execute_rebond(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if cfg.disable_rebond {
+        return Err(ContractError::RebondDisabled {});
+    }
+
+    // sender has to pay fee to rebond
+    must_pay_fee(&info, &cfg, "rebond".to_string())?;
+
+    let mut claims = CLAIMS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    let available = claims.iter().map(|c| c.amount).sum();
+    if amount.is_zero() || amount > available {
+        return Err(ContractError::RebondExceedsClaims { available });
+    }
+
+    // take the claims back, the newest ones first
+    let mut left = amount;
+    for claim in claims.iter_mut().rev() {
+        let take = cmp::min(claim.amount, left);
+        claim.amount -= take;
+        left -= take;
+    }
+    claims.retain(|c| !c.amount.is_zero());
+    CLAIMS.save(deps.storage, &info.sender, &claims)?;
+    reduce_total_claims(deps.storage, amount)?;
+
+    let (messages, token_id) = add_stake(deps.storage, &env, &cfg, &info.sender, amount, None)?;
+    record_history(
+        deps.storage, &env, &cfg, &info.sender, HistoryAction::Rebond, cfg.staking_token.id(), amount)?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "rebond")
+        .add_attribute("amount", amount)
+        .add_attribute("sender", info.sender)
+        .add_attributes(token_id.map(|id| ("token_id", id))))
+}
+
+/// Bonds the tokens, to the member stake or to a new position NFT when they are enabled.
+/// Returns messages notifying the hooks and id of the minted position.
+fn add_stake(
+    storage: &mut dyn Storage,
+    env: &Env,
+    cfg: &Config,
+    sender: &Addr,
+    amount: Uint128,
+    lock_duration: Option<u64>,
+) -> Result<(Vec<SubMsg>, Option<String>), ContractError> {
+    assert_not_emergency(cfg)?;
+
+    let time = env.block.time.seconds();
+    let mut state = STATE.load(storage)?;
+    assert_bond_allowed(storage, cfg, &state, sender, amount, time)?;
+    update_global_reward_index(storage, cfg, &mut state, time)?;
+
+    let token_id = if cfg.position_nfts {
+        // the position earns rewards from now on
+        let mut position = MemberInfo::default();
+        update_member_reward(storage, cfg, &state, &mut position)?;
+        stake_tokens(storage, cfg, &mut state, &mut position, amount, lock_duration, time)?;
+
+        Some(mint_position(storage, sender, position)?)
+    } else {
+        let mut member_info = MEMBERS.may_load(storage, sender)?
+            .unwrap_or(Default::default());
+
+        // compute reward and updates member info with new rewards
+        update_member_reward(storage, cfg, &state, &mut member_info)?;
+        stake_tokens(storage, cfg, &mut state, &mut member_info, amount, lock_duration, time)?;
+        if !MEMBERS.has(storage, sender) {
+            state.num_of_members += 1;
+        }
+
+        MEMBERS.save(storage, sender, &member_info)?;
+        None
+    };
+    STATE.save(storage, &state)?;
+
+    let diffs = update_member_weight(storage, env.block.height, sender)?;
+    Ok((member_changed_hook_msgs(storage, diffs)?, token_id))
+}
+
+/// Only whitelisted addresses can bond during the whitelist window, bonds cannot exceed
+/// the stake caps
+fn assert_bond_allowed(
+    storage: &dyn Storage,
+    cfg: &Config,
+    state: &State,
+    sender: &Addr,
+    amount: Uint128,
+    time: u64,
+) -> Result<(), ContractError> {
+    if time < cfg.whitelist_until && !WHITELIST.has(storage, sender) {
+        return Err(ContractError::NotWhitelisted {});
+    }
+    if let Some(max) = cfg.max_total_stake {
+        let remaining = max.saturating_sub(state.total_stake);
+        if amount > remaining {
+            return Err(ContractError::TotalStakeCapExceeded { remaining });
+        }
+    }
+    assert_address_cap(storage, cfg, sender, amount)
+}
+
+/// Stake of the address cannot grow over the stake cap per address
+fn assert_address_cap(
+    storage: &dyn Storage,
+    cfg: &Config,
+    addr: &Addr,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(max) = cfg.max_stake_per_address {
+        let remaining = max.saturating_sub(address_stake(storage, addr)?);
+        if amount > remaining {
+            return Err(ContractError::AddressStakeCapExceeded { remaining });
+        }
+    }
+    Ok(())
+}
+
+/// Most of the reward the address can compound without exceeding the stake caps, nothing
+/// before the whitelist window ends unless whitelisted
+fn compound_limit(
+    storage: &dyn Storage,
+    cfg: &Config,
+    state: &State,
+    addr: &Addr,
+    time: u64,
+) -> StdResult<Uint128> {
+    if time < cfg.whitelist_until && !WHITELIST.has(storage, addr) {
+        return Ok(Uint128::zero());
+    }
+
+    let mut limit = Uint128::MAX;
+    if let Some(max) = cfg.max_total_stake {
+        limit = cmp::min(limit, max.saturating_sub(state.total_stake));
+    }
+    if let Some(max) = cfg.max_stake_per_address {
+        limit = cmp::min(limit, max.saturating_sub(address_stake(storage, addr)?));
+    }
+    Ok(limit)
+}
+
+/// Adds the tokens to the stake, locked for the tier duration if set. Member reward has
+/// to be up to date.
+fn stake_tokens(
+    storage: &mut dyn Storage,
+    cfg: &Config,
+    state: &mut State,
+    member_info: &mut MemberInfo,
+    amount: Uint128,
+    lock_duration: Option<u64>,
+    time: u64,
+) -> Result<(), ContractError> {
+    let weight = member_info.weight();
+
+    // lock the bond for the tier duration
+    if let Some(duration) = lock_duration {
+        let tier = cfg.lock_tiers.iter()
+            .find(|t| t.duration == duration)
+            .ok_or(ContractError::LockTierNotFound(duration))?;
+        let position = LockedPosition {
+            amount,
+            multiplier: tier.multiplier,
+            lock_end: time + duration,
+        };
+        add_lock_end_weight(storage, position.lock_end, position.extra_weight())?;
+        member_info.positions.push(position);
+    }
+
+    // update member stake
+    member_info.stake += amount;
+
+    // update state with new stake and weight
+    state.total_stake += amount;
+    state.total_weight = state.total_weight - weight + member_info.weight();
+    Ok(())
+}
+
+/// Takes the tokens from the stake, they cannot be locked. Member reward has to be up to date.
+fn unstake_tokens(
+    state: &mut State,
+    member_info: &mut MemberInfo,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let weight = member_info.weight();
+
+    // locked tokens stay bonded until the lock ends
+    if amount > member_info.stake.saturating_sub(member_info.locked_stake()) {
+        return Err(ContractError::StakeLocked {});
+    }
+
+    // update member stake
+    member_info.stake = member_info.stake.checked_sub(amount).map_err(StdError::overflow)?;
+
+    // update state with new stake and weight
+    state.total_stake -= amount;
+    state.total_weight = state.total_weight - weight + member_info.weight();
+    Ok(())
+}
+
+/// Updates global reward indexes until the given time and saves the indexes at the lock
+/// ends passed
+fn update_global_reward_index(
+    storage: &mut dyn Storage,
+    cfg: &Config,
+    state: &mut State,
+    time: u64,
+) -> StdResult<()> {
+    for (lock_end, info) in accrue_reward_index(storage, cfg, state, time)? {
+        LOCK_ENDS.save(storage, U64Key::new(lock_end), &info)?;
+    }
+    Ok(())
+}
+
+/// Accrues global reward indexes until the given time. The accrual stops at every lock end
+/// passed and the extra weight of the positions ending there is removed from the total
+/// weight. Returns the lock ends passed with the indexes at them.
+fn accrue_reward_index(
+    storage: &dyn Storage,
+    cfg: &Config,
+    state: &mut State,
+    time: u64,
+) -> StdResult<Vec<(u64, LockEnd)>> {
+    let mut passed = vec![];
+    if time > state.last_updated {
+        let lock_ends: Vec<_> = LOCK_ENDS
+            .range(
+                storage,
+                Some(Bound::exclusive(U64Key::new(state.last_updated))),
+                Some(Bound::inclusive(U64Key::new(time))),
+                Order::Ascending,
+            )
+            .collect::<StdResult<_>>()?;
+        for (key, mut lock_end) in lock_ends {
+            let lock_end_time = u64::from_be_bytes(<[u8; 8]>::try_from(key.as_slice())
+                .map_err(|_| StdError::generic_err("Invalid lock end key"))?);
+            accrue_reward_index_until(cfg, state, lock_end_time)?;
+            state.total_weight = state.total_weight.saturating_sub(lock_end.extra_weight);
+            lock_end.reward_index = Some(state.global_reward_index.clone());
+            passed.push((lock_end_time, lock_end));
+        }
+    }
+    accrue_reward_index_until(cfg, state, time)?;
+
+    Ok(passed)
+}
+
+/// Updates global reward index of every reward asset until the given time, the total
+/// weight cannot change in between
+fn accrue_reward_index_until(cfg: &Config, state: &mut State, time: u64) -> StdResult<()> {
+    for reward_asset in cfg.reward_assets.iter() {
+        let asset = reward_asset.info.id();
+        let reward_index = compute_reward_index(
+            &reward_asset.distribution_schedule, state, global_reward_index(state, asset), time)?;
+
+        match state.global_reward_index.iter_mut().find(|r| r.asset == asset) {
+            Some(global) => *global = reward_index,
+            None => state.global_reward_index.push(reward_index),
+        }
+    }
+    state.last_updated = time;
+
+    Ok(())
+}
+
+fn global_reward_index(state: &State, asset: &str) -> RewardIndex {
+    state.global_reward_index.iter()
+        .find(|r| r.asset == asset)
+        .cloned()
+        .unwrap_or_else(|| RewardIndex {
+            asset: asset.to_string(),
+            index: Decimal256::zero(),
+            remainder: Uint128::zero(),
+            unwithdrawn: Uint128::zero(),
+        })
+}
+
+/// Updates member rewards to the global reward indexes, which have to be up to date.
+/// Positions past the lock end are released, their extra weight earns until the lock end.
+fn update_member_reward(
+    storage: &dyn Storage,
+    cfg: &Config,
+    state: &State,
+    member_info: &mut MemberInfo,
+) -> StdResult<()> {
+    let ended = member_info.release_expired_positions(state.last_updated).into_iter()
+        .map(|p| Ok((p.extra_weight(), reward_index_at(storage, cfg, p.lock_end)?)))
+        .collect::<StdResult<Vec<_>>>()?;
+    let weight = member_info.weight();
+    for global in state.global_reward_index.iter() {
+        let reward = match member_info.rewards.iter().position(|r| r.asset == global.asset) {
+            Some(i) => &mut member_info.rewards[i],
+            None => {
+                member_info.rewards.push(MemberReward {
+                    asset: global.asset.clone(),
+                    ..Default::default()
+                });
+                member_info.rewards.last_mut().unwrap()
+            }
+        };
+
+        // extra weight of the ended positions until their lock end
+        let ended_reward = ended.iter()
+            .filter_map(|(extra_weight, reward_index)| reward_index.iter()
+                .find(|r| r.asset == global.asset && r.index > reward.reward_index)
+                .map(|r| Uint256::from(*extra_weight) * (r.index - reward.reward_index)))
+            .fold(Uint256::zero(), |total, reward| total + reward);
+
+        reward.pending_reward = compute_member_reward(weight, reward, global.index)? + to_uint128(ended_reward)?;
+        reward.reward_index = global.index;
+    }
+
+    Ok(())
+}
+
+/// Global reward indexes at the lock end. They are computed from the stored state when the
+/// lock end was only passed by a query.
+fn reward_index_at(storage: &dyn Storage, cfg: &Config, lock_end: u64) -> StdResult<Vec<RewardIndex>> {
+    let saved = LOCK_ENDS.may_load(storage, U64Key::new(lock_end))?
+        .and_then(|l| l.reward_index);
+    if let Some(reward_index) = saved {
+        return Ok(reward_index);
+    }
+
+    let mut state = STATE.load(storage)?;
+    accrue_reward_index(storage, cfg, &mut state, lock_end)?;
+    Ok(state.global_reward_index)
+}
+
+/// Schedules the extra weight of a position to be removed from the total weight at its
+/// lock end
+fn add_lock_end_weight(storage: &mut dyn Storage, lock_end: u64, extra_weight: Uint128) -> StdResult<()> {
+    LOCK_ENDS.update(storage, U64Key::new(lock_end), |info| -> StdResult<_> {
+        let mut info = info.unwrap_or_default();
+        info.extra_weight += extra_weight;
+        Ok(info)
+    })?;
+    Ok(())
+}
+
+/// Extra weight of a position stops counting before its lock end, so it is not removed
+/// again at the lock end
+fn remove_lock_end_weight(storage: &mut dyn Storage, lock_end: u64, extra_weight: Uint128) -> StdResult<()> {
+    LOCK_ENDS.update(storage, U64Key::new(lock_end), |info| -> StdResult<_> {
+        let mut info = info.unwrap_or_default();
+        info.extra_weight = info.extra_weight.saturating_sub(extra_weight);
+        Ok(info)
+    })?;
+    Ok(())
+}
+
+/// Removes the member weight from the total weight. Ended positions do not count anymore,
+/// the ones still locked are not removed again at their lock end.
+fn remove_member_weight(storage: &mut dyn Storage, state: &mut State, mut member_info: MemberInfo) -> StdResult<()> {
+    member_info.release_expired_positions(state.last_updated);
+    state.total_weight = state.total_weight.saturating_sub(member_info.weight());
+    for position in member_info.positions.iter() {
+        remove_lock_end_weight(storage, position.lock_end, position.extra_weight())?;
+    }
+    Ok(())
+}
+
+/// Increases the reward index by the amount distributed since the last update per unit of
+/// weight. The index is rounded down and the rest is kept as the remainder, so the index
+/// never accounts for more tokens than the schedules distributed.
+fn compute_reward_index(
+    distribution_schedule: &[Schedule],
+    state: &State,
+    reward_index: RewardIndex,
+    time: u64,
+) -> StdResult<RewardIndex> {
+    // nothing is distributed before the first stake or while nothing is staked
+    if state.last_updated == 0 || state.total_weight.is_zero() {
+        return Ok(reward_index);
+    }
+
+    let distributed_amount = distributed_between(distribution_schedule, state.last_updated, time);
+    add_to_reward_index(reward_index, distributed_amount, state.total_weight)
+}
+
+/// Increases the index by the amount per unit of weight. The part too small to increase
+/// the index is carried in the remainder.
+fn add_to_reward_index(
+    reward_index: RewardIndex,
+    amount: Uint128,
+    total_weight: Uint128,
+) -> StdResult<RewardIndex> {
+    // in 10^-18 tokens, the precision of the index
+    let scaled_amount = Uint256::from(amount) * decimal_fractional()
+        + Uint256::from(reward_index.remainder);
+    let total_weight = Uint256::from(total_weight);
+    let index_increase = scaled_amount / total_weight;
+    let remainder = scaled_amount - index_increase * total_weight;
+
+    Ok(RewardIndex {
+        index: reward_index.index + Decimal256::from_ratio(index_increase, decimal_fractional()),
+        remainder: to_uint128(remainder)?,
+        unwithdrawn: reward_index.unwithdrawn + amount,
+        ..reward_index
+    })
+}
+
+fn to_uint128(value: Uint256) -> StdResult<Uint128> {
+    Uint128::try_from(value).map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+fn to_decimal256(value: Decimal) -> StdResult<Decimal256> {
+    Decimal256::from_str(&value.to_string())
+}
+
+fn decimal_fractional() -> Uint256 {
+    Uint256::from(1_000_000_000_000_000_000u128)
+}
+
+/// Amount distributed in the second starting at the given time, exactly as it is accrued
+fn reward_rate(distribution_schedule: &[Schedule], time: u64) -> Uint128 {
+    distributed_between(distribution_schedule, time, time + 1)
+}
+
+/// Amount the schedule active at the given time distributes in a year, without rounding
+/// its rate first
+fn yearly_reward(distribution_schedule: &[Schedule], time: u64) -> Uint128 {
+    distribution_schedule.iter()
+        .find(|s| s.start_time <= time && time < s.end_time)
+        .map(|s| s.amount.multiply_ratio(YEAR_IN_SEC, s.end_time - s.start_time))
+        .unwrap_or_default()
+}
+
+/// Part of the schedule distributed until the given time. It is computed from the start of
+/// the schedule, so rounding never accumulates and the whole amount is distributed at the end.
+fn distributed_until(schedule: &Schedule, time: u64) -> Uint128 {
+    if time <= schedule.start_time {
+        Uint128::zero()
+    } else if time >= schedule.end_time {
+        schedule.amount
+    } else {
+        schedule.amount.multiply_ratio(time - schedule.start_time, schedule.end_time - schedule.start_time)
+    }
+}
+
+/// Amount distributed by the schedules between the given times
+fn distributed_between(distribution_schedule: &[Schedule], from: u64, to: u64) -> Uint128 {
+    distribution_schedule.iter()
+        .map(|schedule| distributed_until(schedule, to) - distributed_until(schedule, cmp::min(from, to)))
+        .sum()
+}
+
+/// Part of the schedules not distributed until the given time
+fn remaining_rewards(distribution_schedule: &[Schedule], time: u64) -> Uint128 {
+    distribution_schedule.iter()
+        .map(|schedule| schedule.amount - distributed_until(schedule, time))
+        .sum()
+}
+
+/// Reward accrued since the member reward index, rounded down once
+fn compute_member_reward(
+    weight: Uint128,
+    reward: &MemberReward,
+    global_reward_index: Decimal256,
+) -> StdResult<Uint128> {
+    let pending_reward = to_uint128(Uint256::from(weight) * (global_reward_index - reward.reward_index))?;
+
+    Ok(reward.pending_reward + pending_reward)
+}
+
+pub fn execute_unbond(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    assert_not_emergency(&cfg)?;
+
+    // sender has to pay fee to unbond
+    must_pay_fee(&info, &cfg, "unbond".to_string())?;
+
+    // provide them a claim
+    create_claim(
+        deps.storage,
+        &info.sender,
+        amount,
+        Duration::Time(cfg.unbonding_period).after(&env.block),
+    )?;
+
+    let mut state = STATE.load(deps.storage)?;
+    let mut member_info = MEMBERS.may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::MemberNotFound {})?;
+
+    // compute reward and updates member info with new rewards
+    update_global_reward_index(deps.storage, &cfg, &mut state, env.block.time.seconds())?;
+    update_member_reward(deps.storage, &cfg, &state, &mut member_info)?;
+    unstake_tokens(&mut state, &mut member_info, amount)?;
+
+    // save new member info and state in storage
+    MEMBERS.save(deps.storage, &info.sender, &member_info)?;
+    STATE.save(deps.storage, &state)?;
+    record_history(
+        deps.storage, &env, &cfg, &info.sender, HistoryAction::Unbond, cfg.staking_token.id(), amount)?;
+
+    let diffs = update_member_weight(deps.storage, env.block.height, &info.sender)?;
+
+    Ok(Response::new()
+        .add_submessages(member_changed_hook_msgs(deps.storage, diffs)?)
+        .add_attribute("action", "unbond")
+        .add_attribute("amount", amount)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    // sender has to pay fee to claim
+    must_pay_fee(&info, &cfg, "claim".to_string())?;
+
+    // get amount of tokens to release
+    let mut claims = CLAIMS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    let (release, _) = take_claims(
+        &cfg, &mut claims, |c| c.release_at.is_expired(&env.block), amount, env.block.time.seconds());
+    if release.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    CLAIMS.save(deps.storage, &info.sender, &claims)?;
+    reduce_total_claims(deps.storage, release)?;
+    record_history(
+        deps.storage, &env, &cfg, &info.sender, HistoryAction::Claim, cfg.staking_token.id(), release)?;
+
+    // create message to transfer staking tokens
+    let message = transfer_asset_msg(&cfg.staking_token, &info.sender, release)?;
+
+    Ok(Response::new()
+        .add_submessage(message)
+        .add_attribute("action", "claim")
+        .add_attribute("tokens", coin_to_string(release, cfg.staking_token.id()))
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_instant_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Option<Uint128>,
+    claim_ids: Option<Vec<u64>>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_not_emergency(&config)?;
+
+    // sender has to pay fee to instant claim
+    must_pay_fee(&info, &config, "instant_claim".to_string())?;
+
+    let mut claims = CLAIMS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    let (release, fee) = take_instant_claims(
+        &config, &mut claims, amount, claim_ids, env.block.time.seconds())?;
+    CLAIMS.save(deps.storage, &info.sender, &claims)?;
+    reduce_total_claims(deps.storage, release + fee)?;
+
+    // create message to release staking tokens to owner
+    let mut messages = vec![transfer_asset_msg(&config.staking_token, &info.sender, release)?];
+
+    // fee goes to the penalty destination, nothing is taken for released claims
+    let mut state = STATE.load(deps.storage)?;
+    update_global_reward_index(deps.storage, &config, &mut state, env.block.time.seconds())?;
+    messages.extend(penalty_msgs(&config, &mut state, fee)?);
+    STATE.save(deps.storage, &state)?;
+
+    let asset = config.staking_token.id();
+    record_history(deps.storage, &env, &config, &info.sender, HistoryAction::InstantClaim, asset, release)?;
+    record_history(deps.storage, &env, &config, &info.sender, HistoryAction::InstantClaimFee, asset, fee)?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "instant_claim")
+        .add_attribute("tokens", coin_to_string(release, config.staking_token.id()))
+        .add_attribute("fee", coin_to_string(fee, config.staking_token.id()))
+        .add_attribute("sender", info.sender))
+}
+
+/// Sends the penalty in staking tokens to its destination. Redistributed penalty increases
+/// the staking token reward index, so the global indexes have to be up to date.
+fn penalty_msgs(cfg: &Config, state: &mut State, amount: Uint128) -> StdResult<Vec<SubMsg>> {
+    if amount.is_zero() {
+        return Ok(vec![]);
+    }
+
+    let recipient = match &cfg.penalty_destination {
+        PenaltyDestination::Burn {} => cfg.burn_address.clone(),
+        PenaltyDestination::Treasury { address } => Addr::unchecked(address),
+        PenaltyDestination::Redistribute {} => {
+            let total_weight = state.total_weight;
+            let asset = cfg.staking_token.id();
+            match state.global_reward_index.iter_mut().find(|r| r.asset == asset) {
+                Some(reward_index) if !total_weight.is_zero() => {
+                    *reward_index = add_to_reward_index(reward_index.clone(), amount, total_weight)?;
+                    return Ok(vec![]);
+                }
+                // nobody is left to get it
+                _ => cfg.burn_address.clone(),
+            }
+        }
+    };
+    Ok(vec![transfer_asset_msg(&cfg.staking_token, &recipient, amount)?])
+}
+
+/// Takes the claims selected by the ids, all of them if not set, for the instant claim.
+/// Returns tokens released to the member and the burnt fee.
+fn take_instant_claims(
+    cfg: &Config,
+    claims: &mut Vec<Claim>,
+    amount: Option<Uint128>,
+    claim_ids: Option<Vec<u64>>,
+    time: u64,
+) -> Result<(Uint128, Uint128), ContractError> {
+    if let Some(id) = claim_ids.iter().flatten().find(|id| !claims.iter().any(|c| c.id == **id)) {
+        return Err(ContractError::ClaimNotFound(*id));
+    }
+
+    let (taken, fee) = take_claims(cfg, claims, |c| match &claim_ids {
+        Some(ids) => ids.contains(&c.id),
+        None => true,
+    }, amount, time);
+    if taken.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    Ok((taken - fee, fee))
+}
+
+/// Takes up to amount tokens from the selected claims, oldest first, and removes the emptied
+/// ones. Returns the taken tokens and the instant claim fee for the time left until their
+/// release.
+fn take_claims<F: Fn(&Claim) -> bool>(
+    cfg: &Config,
+    claims: &mut Vec<Claim>,
+    select: F,
+    amount: Option<Uint128>,
+    time: u64,
+) -> (Uint128, Uint128) {
+    let mut taken = Uint128::zero();
+    let mut fee = Uint128::zero();
+
+    for claim in claims.iter_mut().filter(|c| select(c)) {
+        let take = match amount {
+            Some(amount) => cmp::min(claim.amount, amount - taken),
+            None => claim.amount,
+        };
+        claim.amount -= take;
+        taken += take;
+        fee += instant_claim_fee(cfg, claim, take, time);
+    }
+    claims.retain(|c| !c.amount.is_zero());
+
+    (taken, fee)
+}
+
+/// Part of the percentage loss proportional to the time left until the release
+fn instant_claim_fee(cfg: &Config, claim: &Claim, amount: Uint128, time: u64) -> Uint128 {
+    if cfg.unbonding_period == 0 {
+        return Uint128::zero();
+    }
+
+    let remaining = match claim.release_at {
+        Expiration::AtTime(release_at) => release_at.seconds().saturating_sub(time),
+        _ => cfg.unbonding_period,
+    };
+    amount.multiply_ratio(
+        cfg.instant_claim_percentage_loss * cmp::min(remaining, cfg.unbonding_period),
+        100 * cfg.unbonding_period,
+    )
+}
+
+fn create_claim(
+    storage: &mut dyn Storage,
+    addr: &Addr,
+    amount: Uint128,
+    release_at: Expiration,
+) -> StdResult<()> {
+    let id = CLAIM_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+    CLAIM_SEQ.save(storage, &id)?;
+
+    let mut claims = CLAIMS.may_load(storage, addr)?.unwrap_or_default();
+    claims.push(Claim { id, amount, release_at });
+    CLAIMS.save(storage, addr, &claims)?;
+
+    let total_claims = TOTAL_CLAIMS.may_load(storage)?.unwrap_or_default();
+    TOTAL_CLAIMS.save(storage, &(total_claims + amount))
+}
+
+/// Tokens taken from the claims are not owed anymore
+fn reduce_total_claims(storage: &mut dyn Storage, amount: Uint128) -> StdResult<()> {
+    let total_claims = TOTAL_CLAIMS.may_load(storage)?.unwrap_or_default();
+    TOTAL_CLAIMS.save(storage, &total_claims.saturating_sub(amount))
+}
+
+/// Appends the entry to the member history. Zero amounts are not recorded. Entries past
+/// the retention are pruned, oldest first and at most `MAX_PRUNED_HISTORY` at once, so the
+/// cost stays bounded.
+fn record_history(
+    storage: &mut dyn Storage,
+    env: &Env,
+    cfg: &Config,
+    addr: &Addr,
+    action: HistoryAction,
+    asset: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    let time = env.block.time.seconds();
+
+    if let Some(retention) = cfg.history_retention {
+        let expired: StdResult<Vec<_>> = MEMBER_HISTORY
+            .prefix(addr)
+            .range(storage, None, None, Order::Ascending)
+            .take(MAX_PRUNED_HISTORY)
+            .map(|item| item.map(|(_, entry)| entry))
+            .take_while(|entry| !matches!(entry, Ok(e) if e.time + retention >= time))
+            .map(|entry| entry.map(|e| e.id))
+            .collect();
+        for id in expired? {
+            MEMBER_HISTORY.remove(storage, (addr, U64Key::new(id)));
+        }
+    }
+
+    let id = HISTORY_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+    HISTORY_SEQ.save(storage, &id)?;
+    MEMBER_HISTORY.save(storage, (addr, U64Key::new(id)), &HistoryEntry {
+        id,
+        action,
+        asset: asset.to_string(),
+        amount,
+        time,
+        height: env.block.height,
+    })
+}
+
+pub fn execute_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    assets: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    assert_not_emergency(&cfg)?;
+
+    // sender has to pay fee to withdraw
+    must_pay_fee(&info, &cfg, "withdraw".to_string())?;
+
+    let mut state = STATE.load(deps.storage)?;
+    let is_member = MEMBERS.has(deps.storage, &info.sender);
+    let mut member_info = MEMBERS.may_load(deps.storage, &info.sender)?
+        .unwrap_or(Default::default());
+
+    // calculate member reward until current block or end of distribution
+    update_global_reward_index(deps.storage, &cfg, &mut state, env.block.time.seconds())?;
+    update_member_reward(deps.storage, &cfg, &state, &mut member_info)?;
+
+    // rewards of the held positions are withdrawn together
+    let mut positions = held_positions(deps.storage, &info.sender)?;
+    for (_, position) in positions.iter_mut() {
+        update_member_reward(deps.storage, &cfg, &state, &mut position.info)?;
+    }
+
+    let assets = assets.unwrap_or_else(|| {
+        withdrawable_assets(&cfg).map(|a| a.id().to_string()).collect()
+    });
+
+    let mut messages = vec![];
+    let mut tokens = vec![];
+    for asset in assets {
+        let asset_info = withdrawable_assets(&cfg)
+            .find(|a| a.id() == asset)
+            .ok_or_else(|| ContractError::UnknownRewardAsset(asset.clone()))?;
+        let mut amount = take_reward(&mut state, &mut member_info, &asset)?;
+        for (_, position) in positions.iter_mut() {
+            amount += take_reward(&mut state, &mut position.info, &asset)?;
+        }
+        if amount.is_zero() {
+            continue;
+        }
+
+        messages.push(transfer_asset_msg(asset_info, &info.sender, amount)?);
+        tokens.push(coin_to_string(amount, &asset));
+        record_history(deps.storage, &env, &cfg, &info.sender, HistoryAction::Withdraw, &asset, amount)?;
+    }
+
+    if messages.is_empty() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+
+    if is_member {
+        MEMBERS.save(deps.storage, &info.sender, &member_info)?;
+    }
+    for (token_id, position) in positions {
+        POSITIONS.save(deps.storage, &token_id, &position)?;
+    }
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "withdraw")
+        .add_attribute("tokens", tokens.join(", "))
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_compound(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    assert_not_emergency(&cfg)?;
+
+    // sender has to pay fee to compound
+    must_pay_fee(&info, &cfg, "compound".to_string())?;
+
+    let asset = compound_asset(&cfg)?;
+
+    let mut state = STATE.load(deps.storage)?;
+    let mut member_info = MEMBERS.may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::MemberNotFound {})?;
 
-    // update state with new stake and global_reward_index
-    state.total_stake -= amount;
-    state.last_updated = env.block.time.seconds();
-    state.global_reward_index = member_info.reward_index;
+    let time = env.block.time.seconds();
+    update_global_reward_index(deps.storage, &cfg, &mut state, time)?;
+    let amount = compound_member_reward(
+        deps.storage, &cfg, &mut state, &mut member_info, &info.sender, &asset, time)?;
+    if amount.is_zero() {
+        // reward the stake caps have no room for
+        let reward = unwithdrawn_reward(&member_info, &asset);
+        assert_bond_allowed(deps.storage, &cfg, &state, &info.sender, reward, time)?;
+        return Err(ContractError::NothingToCompound {});
+    }
 
-    // save new member info and state in storage
     MEMBERS.save(deps.storage, &info.sender, &member_info)?;
     STATE.save(deps.storage, &state)?;
+    record_history(deps.storage, &env, &cfg, &info.sender, HistoryAction::Compound, &asset, amount)?;
+
+    let diffs = update_member_weight(deps.storage, env.block.height, &info.sender)?;
 
     Ok(Response::new()
-        .add_attribute("action", "unbond")
+        .add_submessages(member_changed_hook_msgs(deps.storage, diffs)?)
+        .add_attribute("action", "compound")
         .add_attribute("amount", amount)
+        .add_attribute("stake", member_info.stake)
         .add_attribute("sender", info.sender))
 }
 
-pub fn execute_claim(
+pub fn execute_set_auto_compound(
     deps: DepsMut,
-    env: Env,
     info: MessageInfo,
+    enabled: bool,
 ) -> Result<Response, ContractError> {
-    let cfg = CONFIG.load(deps.storage)?;
-
-    // sender has to pay fee to claim
-    must_pay_fee(&info, &cfg, "claim".to_string())?;
-
-    // get amount of tokens to release
-    let release = CLAIMS.claim_tokens(deps.storage, &info.sender, &env.block, None)?;
-    if release.is_zero() {
-        return Err(ContractError::NothingToClaim {});
+    if !MEMBERS.has(deps.storage, &info.sender) {
+        return Err(ContractError::MemberNotFound {});
     }
 
-    // create message to transfer staking tokens
-    let message = SubMsg::new(WasmMsg::Execute {
-        contract_addr: cfg.staking_token.clone().into(),
-        msg: to_binary(&Cw20ExecuteMsg::Transfer {
-            recipient: info.sender.clone().into(),
-            amount: release,
-        })?,
-        funds: vec![],
-    });
+    if enabled {
+        AUTO_COMPOUND.save(deps.storage, &info.sender, &true)?;
+    } else {
+        AUTO_COMPOUND.remove(deps.storage, &info.sender);
+    }
 
     Ok(Response::new()
-        .add_submessage(message)
-        .add_attribute("action", "claim")
-        .add_attribute("tokens", coin_to_string(release, cfg.staking_token.as_str()))
+        .add_attribute("action", "set_auto_compound")
+        .add_attribute("enabled", enabled.to_string())
         .add_attribute("sender", info.sender))
 }
 
-pub fn execute_instant_claim(
+pub fn execute_harvest(
     deps: DepsMut,
     env: Env,
-    info: MessageInfo,
+    limit: Option<u32>,
 ) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
+    assert_not_emergency(&cfg)?;
+    let asset = compound_asset(&cfg)?;
 
-    // sender has to pay fee to instant claim
-    must_pay_fee(&info, &cfg, "instant_claim".to_string())?;
-
-    let config = CONFIG.load(deps.storage)?;
-
-    // Create block after unbonding_period to be able to release all claims
-    let mut block = env.block.clone();
-    block.time = block.time.plus_seconds(YEAR_IN_SEC);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = HARVEST_CURSOR.may_load(deps.storage)?
+        .map(|addr| Bound::exclusive(addr.as_str()));
+    let members: StdResult<Vec<_>> = AUTO_COMPOUND
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|key| Ok(Addr::unchecked(String::from_utf8(key)?)))
+        .collect();
+    let members = members?;
 
-    // get amount of tokens to release
-    let mut release = CLAIMS.claim_tokens(deps.storage, &info.sender, &block, None)?;
-    if release.is_zero() {
-        return Err(ContractError::NothingToClaim {});
+    let mut state = STATE.load(deps.storage)?;
+    update_global_reward_index(deps.storage, &cfg, &mut state, env.block.time.seconds())?;
+
+    let mut total = Uint128::zero();
+    let mut diffs = vec![];
+    for addr in members.iter() {
+        let mut member_info = MEMBERS.load(deps.storage, addr)?;
+        let amount = compound_member_reward(
+            deps.storage, &cfg, &mut state, &mut member_info, addr, &asset, env.block.time.seconds())?;
+        total += amount;
+        MEMBERS.save(deps.storage, addr, &member_info)?;
+        record_history(deps.storage, &env, &cfg, addr, HistoryAction::Compound, &asset, amount)?;
+        diffs.extend(update_member_weight(deps.storage, env.block.height, addr)?);
     }
+    STATE.save(deps.storage, &state)?;
 
-    // calculate fee for instant claim
-    let fee = release
-        .checked_mul(Uint128::from(config.instant_claim_percentage_loss))
-        .map_err(StdError::overflow)?
-        .div(Uint128::new(100));
-    release = release.checked_sub(fee)
-        .map_err(StdError::overflow)?;
-
-    // create message to release staking tokens to owner
-    let message1 = SubMsg::new(WasmMsg::Execute {
-        contract_addr: config.staking_token.clone().into(),
-        msg: to_binary(&Cw20ExecuteMsg::Transfer {
-            recipient: info.sender.clone().into(),
-            amount: release,
-        })?,
-        funds: vec![],
-    });
-
-    // create message to transfer fee to burn address
-    let message2 = SubMsg::new(WasmMsg::Execute {
-        contract_addr: config.staking_token.clone().into(),
-        msg: to_binary(&Cw20ExecuteMsg::Transfer {
-            recipient: config.burn_address.clone().into(),
-            amount: fee,
-        })?,
-        funds: vec![],
-    });
+    // next harvest starts from the beginning after reaching the last member
+    match members.last() {
+        Some(last) if members.len() == limit => HARVEST_CURSOR.save(deps.storage, last)?,
+        _ => HARVEST_CURSOR.remove(deps.storage),
+    }
 
     Ok(Response::new()
-        .add_submessages([message1, message2])
-        .add_attribute("action", "instant_claim")
-        .add_attribute("tokens", coin_to_string(release, config.staking_token.as_str()))
-        .add_attribute("fee", coin_to_string(fee, config.staking_token.as_str()))
-        .add_attribute("sender", info.sender))
+        .add_submessages(member_changed_hook_msgs(deps.storage, diffs)?)
+        .add_attribute("action", "harvest")
+        .add_attribute("members", members.len().to_string())
+        .add_attribute("amount", total))
 }
 
-pub fn execute_withdraw(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-) -> Result<Response, ContractError> {
-    let cfg = CONFIG.load(deps.storage)?;
+/// Reward assets followed by the retired ones
+fn withdrawable_assets(cfg: &Config) -> impl Iterator<Item = &AssetInfo> {
+    cfg.reward_assets.iter()
+        .map(|a| &a.info)
+        .chain(cfg.retired_assets.iter())
+}
 
-    // sender has to pay fee to withdraw
-    must_pay_fee(&info, &cfg, "withdraw".to_string())?;
+/// Marks the reward in the asset withdrawn, returns the amount not withdrawn before
+fn take_reward(state: &mut State, member_info: &mut MemberInfo, asset: &str) -> StdResult<Uint128> {
+    take_reward_up_to(state, member_info, asset, Uint128::MAX)
+}
 
-    let state = STATE.load(deps.storage)?;
-    let mut member_info = MEMBERS.may_load(deps.storage, &info.sender)?
-        .unwrap_or(Default::default());
+/// Marks the reward in the asset withdrawn up to the limit, returns the marked amount
+fn take_reward_up_to(
+    state: &mut State,
+    member_info: &mut MemberInfo,
+    asset: &str,
+    limit: Uint128,
+) -> StdResult<Uint128> {
+    let amount = match member_info.rewards.iter_mut().find(|r| r.asset == asset) {
+        Some(reward) => {
+            // amount to withdraw is difference between the reward and the withdraw amount
+            let amount = reward.pending_reward.checked_sub(reward.withdrawn)
+                .map_err(StdError::overflow)?;
+            let amount = cmp::min(amount, limit);
+            reward.withdrawn += amount;
+            amount
+        }
+        None => Uint128::zero(),
+    };
 
-    // calculate member reward until current block or end of distribution
-    update_member_reward(&state, &cfg, env.block.time.seconds(), &mut member_info)?;
+    if let Some(global) = state.global_reward_index.iter_mut().find(|r| r.asset == asset) {
+        global.unwithdrawn = global.unwithdrawn.saturating_sub(amount);
+    }
+    Ok(amount)
+}
 
-    // amount to withdraw is difference between the reward and the withdraw amount
-    let amount = member_info.pending_reward.checked_sub(member_info.withdrawn)
-        .map_err(StdError::overflow)?;
+/// Reward in the asset not withdrawn yet
+fn unwithdrawn_reward(member_info: &MemberInfo, asset: &str) -> Uint128 {
+    member_info.rewards.iter()
+        .find(|r| r.asset == asset)
+        .map(|r| r.pending_reward.saturating_sub(r.withdrawn))
+        .unwrap_or_default()
+}
 
-    if amount.is_zero() {
-        return Err(ContractError::NothingToWithdraw {});
-    }
+/// Staking token, if it is a reward asset
+fn compound_asset(cfg: &Config) -> Result<String, ContractError> {
+    cfg.reward_assets.iter()
+        .find(|a| a.info == cfg.staking_token)
+        .map(|a| a.info.id().to_string())
+        .ok_or(ContractError::CannotCompound {})
+}
 
-    // update withdrawal
-    MEMBERS.update(deps.storage, &info.sender, |member_info| -> StdResult<_> {
-        let mut info = member_info.unwrap();
-        info.withdrawn += amount;
-        Ok(info)
-    })?;
+/// Moves the reward in staking tokens to the member stake, global reward indexes have to be
+/// up to date. The reward over the stake caps stays pending. Returns the compounded amount.
+fn compound_member_reward(
+    storage: &dyn Storage,
+    cfg: &Config,
+    state: &mut State,
+    member_info: &mut MemberInfo,
+    addr: &Addr,
+    asset: &str,
+    time: u64,
+) -> StdResult<Uint128> {
+    update_member_reward(storage, cfg, state, member_info)?;
+
+    let weight = member_info.weight();
+
+    let limit = compound_limit(storage, cfg, state, addr, time)?;
+    let amount = take_reward_up_to(state, member_info, asset, limit)?;
+
+    // compounded tokens are not locked
+    member_info.stake += amount;
+    state.total_stake += amount;
+    state.total_weight = state.total_weight - weight + member_info.weight();
 
-    // create message to transfer reward in terraland tokens
-    let config = CONFIG.load(deps.storage)?;
-    let message = SubMsg::new(WasmMsg::Execute {
-        contract_addr: config.terraland_token.to_string(),
-        msg: to_binary(&Cw20ExecuteMsg::Transfer {
-            recipient: info.sender.clone().into(),
-            amount,
-        })?,
-        funds: vec![],
-    });
+    Ok(amount)
+}
 
-    Ok(Response::new()
-        .add_submessage(message)
-        .add_attribute("action", "withdraw")
-        .add_attribute("tokens", coin_to_string(amount, config.terraland_token.as_str()))
-        .add_attribute("sender", info.sender))
+fn transfer_asset_msg(asset: &AssetInfo, recipient: &Addr, amount: Uint128) -> StdResult<SubMsg> {
+    let message = match asset {
+        AssetInfo::Token { contract_addr } => SubMsg::new(WasmMsg::Execute {
+            contract_addr: contract_addr.clone(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.into(),
+                amount,
+            })?,
+            funds: vec![],
+        }),
+        AssetInfo::NativeToken { denom } => SubMsg::new(BankMsg::Send {
+            to_address: recipient.into(),
+            amount: vec![Coin { denom: denom.clone(), amount }],
+        }),
+    };
+    Ok(message)
 }
 
 pub fn execute_ust_withdraw(
@@ -469,9 +2470,9 @@ pub fn execute_ust_withdraw(
     recipient: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized treasurer
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Treasurer, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -494,9 +2495,9 @@ pub fn execute_token_withdraw(
     token: String,
     recipient: String,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized treasurer
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Treasurer, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -559,19 +2560,142 @@ fn coin_to_string(amount: Uint128, denom: &str) -> String {
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps.storage)?),
+        QueryMsg::RoleMembers { role, start_after, limit } =>
+            to_binary(&query_role_members(deps, role, start_after, limit)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
-        QueryMsg::Member { address } => to_binary(&query_member(deps, env, address)?),
-        QueryMsg::ListMembers { start_after, limit } =>
+        QueryMsg::Staker { address } => to_binary(&query_member(deps, env, address)?),
+        QueryMsg::RewardRate { at_time } => to_binary(&query_reward_rate(deps, env, at_time)?),
+        QueryMsg::Apr {} => to_binary(&query_apr(deps, env)?),
+        QueryMsg::ProjectedReward { address, until } =>
+            to_binary(&query_projected_reward(deps, env, address, until)?),
+        QueryMsg::RemainingRewards {} => to_binary(&query_remaining_rewards(deps, env)?),
+        QueryMsg::SimulateInstantClaim { address, amount, claim_ids } =>
+            to_binary(&query_simulate_instant_claim(deps, env, address, amount, claim_ids)?),
+        QueryMsg::ListStakers { start_after, limit } =>
             to_binary(&query_member_list(deps, env, start_after, limit)?),
+        QueryMsg::Member { addr, at_height } => to_binary(&query_member_weight(deps, addr, at_height)?),
+        QueryMsg::ListMembers { start_after, limit } =>
+            to_binary(&query_member_weight_list(deps, start_after, limit)?),
+        QueryMsg::TotalWeight {} => to_binary(&query_total_weight(deps)?),
+        QueryMsg::Hooks {} => to_binary(&query_hooks(deps)?),
+        QueryMsg::Capacity {} => to_binary(&query_capacity(deps)?),
+        QueryMsg::Whitelist { start_after, limit } =>
+            to_binary(&query_whitelist(deps, start_after, limit)?),
+        QueryMsg::MemberHistory { address, start_after, limit } =>
+            to_binary(&query_member_history(deps, address, start_after, limit)?),
+        QueryMsg::Slashes { address, start_after, limit } =>
+            to_binary(&query_slashes(deps, address, start_after, limit)?),
+        QueryMsg::OwnerOf { token_id, include_expired } =>
+            to_binary(&query_owner_of(deps, env, token_id, include_expired.unwrap_or(false))?),
+        QueryMsg::NftInfo { token_id } => to_binary(&query_nft_info(deps, env, token_id)?),
+        QueryMsg::AllNftInfo { token_id, include_expired } =>
+            to_binary(&query_all_nft_info(deps, env, token_id, include_expired.unwrap_or(false))?),
+        QueryMsg::Tokens { owner, start_after, limit } =>
+            to_binary(&query_tokens(deps, owner, start_after, limit)?),
+        QueryMsg::AllTokens { start_after, limit } =>
+            to_binary(&query_all_tokens(deps, start_after, limit)?),
+        QueryMsg::NumTokens {} => to_binary(&query_num_tokens(deps)?),
+        QueryMsg::ContractInfo {} => to_binary(&query_contract_info()),
     }
 }
 
 pub fn query_config(deps: Deps) -> StdResult<Config> {
-    Ok(CONFIG.load(deps.storage)?)
+    CONFIG.load(deps.storage)
 }
 
 fn query_state(deps: Deps) -> StdResult<State> {
-    Ok(STATE.load(deps.storage)?)
+    STATE.load(deps.storage)
+}
+
+fn query_reward_rate(deps: Deps, env: Env, at_time: Option<u64>) -> StdResult<RewardRateResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let time = at_time.unwrap_or_else(|| env.block.time.seconds());
+
+    Ok(RewardRateResponse {
+        rates: cfg.reward_assets.iter()
+            .map(|a| AssetAmount {
+                asset: a.info.id().to_string(),
+                amount: reward_rate(&a.distribution_schedule, time),
+            })
+            .collect(),
+    })
+}
+
+fn query_apr(deps: Deps, env: Env) -> StdResult<AprResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let state = STATE.load(deps.storage)?;
+    let time = env.block.time.seconds();
+
+    Ok(AprResponse {
+        aprs: cfg.reward_assets.iter()
+            .map(|a| {
+                // rewards are distributed by weight, unlocked token weighs 1
+                let yearly = yearly_reward(&a.distribution_schedule, time);
+                let apr = if state.total_weight.is_zero() {
+                    Decimal::zero()
+                } else {
+                    Decimal::from_ratio(yearly, state.total_weight)
+                };
+                AssetApr {
+                    asset: a.info.id().to_string(),
+                    apr,
+                }
+            })
+            .collect(),
+    })
+}
+
+fn query_projected_reward(
+    deps: Deps,
+    env: Env,
+    address: String,
+    until: u64,
+) -> StdResult<ProjectedRewardResponse> {
+    if until < env.block.time.seconds() {
+        return Err(StdError::generic_err("Projection cannot end in the past"));
+    }
+
+    let addr = deps.api.addr_validate(&address)?;
+    let mut info = MEMBERS.may_load(deps.storage, &addr)?.unwrap_or_default();
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    accrue_reward_index(deps.storage, &cfg, &mut state, until)?;
+    update_member_reward(deps.storage, &cfg, &state, &mut info)?;
+
+    Ok(ProjectedRewardResponse {
+        rewards: member_response_item(deps, &addr, info)?.rewards,
+    })
+}
+
+fn query_simulate_instant_claim(
+    deps: Deps,
+    env: Env,
+    address: String,
+    amount: Option<Uint128>,
+    claim_ids: Option<Vec<u64>>,
+) -> StdResult<SimulateInstantClaimResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let addr = deps.api.addr_validate(&address)?;
+    let mut claims = CLAIMS.may_load(deps.storage, &addr)?.unwrap_or_default();
+
+    let (release, fee) = take_instant_claims(&cfg, &mut claims, amount, claim_ids, env.block.time.seconds())
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    Ok(SimulateInstantClaimResponse { release, fee })
+}
+
+fn query_remaining_rewards(deps: Deps, env: Env) -> StdResult<RemainingRewardsResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let time = env.block.time.seconds();
+
+    Ok(RemainingRewardsResponse {
+        remaining: cfg.reward_assets.iter()
+            .map(|a| AssetAmount {
+                asset: a.info.id().to_string(),
+                amount: remaining_rewards(&a.distribution_schedule, time),
+            })
+            .collect(),
+    })
 }
 
 fn query_member(deps: Deps, env: Env, addr: String) -> StdResult<MemberResponse> {
@@ -580,26 +2704,35 @@ fn query_member(deps: Deps, env: Env, addr: String) -> StdResult<MemberResponse>
 
     if let Some(mut info) = member_info {
         let cfg = CONFIG.load(deps.storage)?;
-        let state = STATE.load(deps.storage)?;
-        update_member_reward(&state, &cfg, env.block.time.seconds(), &mut info)?;
+        let mut state = STATE.load(deps.storage)?;
+        accrue_reward_index(deps.storage, &cfg, &mut state, env.block.time.seconds())?;
+        update_member_reward(deps.storage, &cfg, &state, &mut info)?;
 
         return Ok(MemberResponse {
-            member: Some(MemberResponseItem {
-                stake: info.stake,
-                reward: info.pending_reward,
-                reward_index: info.reward_index,
-                withdrawn: info.withdrawn,
-                claims: CLAIMS.query_claims(deps, &addr)?.claims,
-            }),
+            member: Some(member_response_item(deps, &addr, info)?),
         });
     }
 
     Ok(MemberResponse { member: None })
 }
 
-// settings for pagination
-const MAX_LIMIT: u32 = 30;
-const DEFAULT_LIMIT: u32 = 10;
+fn member_response_item(deps: Deps, addr: &Addr, info: MemberInfo) -> StdResult<MemberResponseItem> {
+    Ok(MemberResponseItem {
+        stake: info.stake,
+        weight: info.weight(),
+        rewards: reward_response_items(info.rewards),
+        positions: info.positions.into_iter()
+            .map(|p| PositionResponseItem {
+                amount: p.amount,
+                multiplier: p.multiplier,
+                lock_end: p.lock_end,
+                weight: p.weight(),
+            })
+            .collect(),
+        claims: CLAIMS.may_load(deps.storage, addr)?.unwrap_or_default(),
+        auto_compound: AUTO_COMPOUND.has(deps.storage, addr),
+    })
+}
 
 fn query_member_list(
     deps: Deps,
@@ -611,26 +2744,22 @@ fn query_member_list(
     let addr = maybe_addr(deps.api, start_after)?;
     let start = addr.map(|addr| Bound::exclusive(addr.as_ref()));
 
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    accrue_reward_index(deps.storage, &cfg, &mut state, env.block.time.seconds())?;
+
     let members: StdResult<Vec<_>> = MEMBERS
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
             let (key, mut info) = item?;
             let address = deps.api.addr_validate(&String::from_utf8(key)?)?;
-            let cfg = CONFIG.load(deps.storage)?;
-            let state = STATE.load(deps.storage)?;
 
-            update_member_reward(&state, &cfg, env.block.time.seconds(), &mut info)?;
+            update_member_reward(deps.storage, &cfg, &state, &mut info)?;
 
             Ok(MemberListResponseItem {
                 address: address.to_string(),
-                info: MemberResponseItem {
-                    stake: info.stake,
-                    reward: info.pending_reward,
-                    reward_index: info.reward_index,
-                    withdrawn: info.withdrawn,
-                    claims: CLAIMS.query_claims(deps, &address)?.claims,
-                },
+                info: member_response_item(deps, &address, info)?,
             })
         })
         .collect();
@@ -638,11 +2767,259 @@ fn query_member_list(
     Ok(MemberListResponse { members: members? })
 }
 
+fn query_member_weight(deps: Deps, addr: String, height: Option<u64>) -> StdResult<cw4::MemberResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let weight = match height {
+        Some(height) => MEMBER_WEIGHTS.may_load_at_height(deps.storage, &addr, height)?,
+        None => MEMBER_WEIGHTS.may_load(deps.storage, &addr)?,
+    };
+    Ok(cw4::MemberResponse { weight })
+}
+
+fn query_member_weight_list(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<cw4::MemberListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.map(|addr| Bound::exclusive(addr.as_ref()));
+
+    let members: StdResult<Vec<_>> = MEMBER_WEIGHTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, weight) = item?;
+            Ok(Member {
+                addr: String::from_utf8(key)?,
+                weight,
+            })
+        })
+        .collect();
+
+    Ok(cw4::MemberListResponse { members: members? })
+}
+
+fn query_total_weight(deps: Deps) -> StdResult<TotalWeightResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(TotalWeightResponse {
+        weight: to_cw4_weight(state.total_stake)?,
+    })
+}
+
+fn query_hooks(deps: Deps) -> StdResult<HooksResponse> {
+    Ok(HooksResponse {
+        hooks: HOOKS.query_hooks(deps)?.hooks,
+    })
+}
+
+fn query_capacity(deps: Deps) -> StdResult<CapacityResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let state = STATE.load(deps.storage)?;
+    Ok(CapacityResponse {
+        total_stake: state.total_stake,
+        max_total_stake: cfg.max_total_stake,
+        remaining_total_stake: cfg.max_total_stake.map(|max| max.saturating_sub(state.total_stake)),
+        max_stake_per_address: cfg.max_stake_per_address,
+        whitelist_until: cfg.whitelist_until,
+    })
+}
+
+fn query_whitelist(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<WhitelistResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.map(|addr| Bound::exclusive(addr.as_ref()));
+
+    let addresses: StdResult<Vec<_>> = WHITELIST
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|key| String::from_utf8(key).map_err(StdError::from))
+        .collect();
+
+    Ok(WhitelistResponse { addresses: addresses? })
+}
+
+fn query_slashes(
+    deps: Deps,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SlashesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| Bound::exclusive(U64Key::new(id)));
+    let addr = deps.api.addr_validate(&address)?;
+
+    let slashes: StdResult<Vec<_>> = SLASHES
+        .prefix(&addr)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, slash)| slash))
+        .collect();
+
+    Ok(SlashesResponse { slashes: slashes? })
+}
+
+fn query_member_history(
+    deps: Deps,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<MemberHistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| Bound::exclusive(U64Key::new(id)));
+    let addr = deps.api.addr_validate(&address)?;
+
+    let entries: StdResult<Vec<_>> = MEMBER_HISTORY
+        .prefix(&addr)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, entry)| entry))
+        .collect();
+
+    Ok(MemberHistoryResponse { entries: entries? })
+}
+
+fn reward_response_items(rewards: Vec<MemberReward>) -> Vec<RewardResponseItem> {
+    rewards.into_iter()
+        .map(|r| RewardResponseItem {
+            asset: r.asset,
+            reward: r.pending_reward,
+            reward_index: r.reward_index,
+            withdrawn: r.withdrawn,
+        })
+        .collect()
+}
+
+fn query_position(deps: Deps, token_id: &str) -> StdResult<StakingPosition> {
+    POSITIONS.may_load(deps.storage, token_id)?
+        .ok_or_else(|| StdError::not_found(format!("Position {}", token_id)))
+}
+
+fn query_owner_of(
+    deps: Deps,
+    env: Env,
+    token_id: String,
+    include_expired: bool,
+) -> StdResult<OwnerOfResponse> {
+    let position = query_position(deps, &token_id)?;
+    Ok(owner_of_response(&env, &position, include_expired))
+}
+
+fn owner_of_response(env: &Env, position: &StakingPosition, include_expired: bool) -> OwnerOfResponse {
+    OwnerOfResponse {
+        owner: position.owner.to_string(),
+        approvals: position.approvals.iter()
+            .filter(|a| include_expired || !a.expires.is_expired(&env.block))
+            .map(|a| NftApproval {
+                spender: a.spender.to_string(),
+                expires: a.expires,
+            })
+            .collect(),
+    }
+}
+
+fn query_nft_info(deps: Deps, env: Env, token_id: String) -> StdResult<NftInfoResponse> {
+    let position = query_position(deps, &token_id)?;
+    nft_info_response(deps, &env, position.info)
+}
+
+/// Position with rewards accrued until now
+fn nft_info_response(deps: Deps, env: &Env, mut info: MemberInfo) -> StdResult<NftInfoResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    accrue_reward_index(deps.storage, &cfg, &mut state, env.block.time.seconds())?;
+    update_member_reward(deps.storage, &cfg, &state, &mut info)?;
+
+    Ok(NftInfoResponse {
+        token_uri: None,
+        extension: PositionNftExtension {
+            stake: info.stake,
+            weight: info.weight(),
+            lock_end: info.positions.iter()
+                .map(|p| p.lock_end)
+                .filter(|lock_end| *lock_end > env.block.time.seconds())
+                .max(),
+            rewards: reward_response_items(info.rewards),
+        },
+    })
+}
+
+fn query_all_nft_info(
+    deps: Deps,
+    env: Env,
+    token_id: String,
+    include_expired: bool,
+) -> StdResult<AllNftInfoResponse> {
+    let position = query_position(deps, &token_id)?;
+    Ok(AllNftInfoResponse {
+        access: owner_of_response(&env, &position, include_expired),
+        info: nft_info_response(deps, &env, position.info)?,
+    })
+}
+
+fn query_tokens(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let owner = deps.api.addr_validate(&owner)?;
+
+    let tokens: StdResult<Vec<_>> = OWNER_POSITIONS
+        .prefix(&owner)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|key| String::from_utf8(key).map_err(StdError::from))
+        .collect();
+
+    Ok(TokensResponse { tokens: tokens? })
+}
+
+fn query_all_tokens(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let tokens: StdResult<Vec<_>> = POSITIONS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|key| String::from_utf8(key).map_err(StdError::from))
+        .collect();
+
+    Ok(TokensResponse { tokens: tokens? })
+}
+
+fn query_num_tokens(deps: Deps) -> StdResult<NumTokensResponse> {
+    Ok(NumTokensResponse {
+        count: NUM_POSITIONS.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+fn query_contract_info() -> ContractInfoResponse {
+    ContractInfoResponse {
+        name: POSITION_NFT_NAME.to_string(),
+        symbol: POSITION_NFT_SYMBOL.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::{Coin, from_slice};
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use crate::state::{FeeConfig, Schedule};
+    use cosmwasm_std::{attr, Coin, coins, ContractResult, from_binary, from_slice, OwnedDeps, Querier, QuerierResult, QueryRequest, SystemResult};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+    use cw_controllers::HookError;
+    use ownership::{OwnershipError, PendingOwnerResponse};
+    use proptest::prelude::*;
+
+    use crate::state::FeeConfig;
 
     use super::*;
 
@@ -655,6 +3032,45 @@ mod tests {
     const TERRALAND_TOKEN_ADDRESS: &str = "tland1234567890";
     const STAKING_TOKEN_ADDRESS: &str = "staking1234567890";
     const WEEK: u64 = 604800;
+    const DAY: u64 = 86400;
+
+    /// Answers cw20 balance queries with the given balances of the contract
+    struct TokenQuerier {
+        base: MockQuerier,
+        token_balances: Vec<(String, Uint128)>,
+    }
+
+    impl Querier for TokenQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<Empty> = from_slice(bin_request).unwrap();
+            match request {
+                QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                    assert!(matches!(from_binary(&msg).unwrap(), Cw20QueryMsg::Balance { .. }));
+                    let balance = self.token_balances.iter()
+                        .find(|(token, _)| *token == contract_addr)
+                        .map(|(_, balance)| *balance)
+                        .unwrap_or_default();
+                    SystemResult::Ok(ContractResult::Ok(to_binary(&BalanceResponse { balance }).unwrap()))
+                }
+                _ => self.base.raw_query(bin_request),
+            }
+        }
+    }
+
+    fn mock_dependencies_with_tokens(
+        token_balances: &[(&str, u128)],
+    ) -> OwnedDeps<MockStorage, MockApi, TokenQuerier> {
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: TokenQuerier {
+                base: MockQuerier::new(&[]),
+                token_balances: token_balances.iter()
+                    .map(|(token, balance)| (token.to_string(), Uint128::new(*balance)))
+                    .collect(),
+            },
+        }
+    }
 
     fn default_instantiate(
         deps: DepsMut,
@@ -662,23 +3078,11 @@ mod tests {
     ) {
         let msg = InstantiateMsg {
             owner: INIT_ADMIN.into(),
-            staking_token: STAKING_TOKEN_ADDRESS.into(),
-            terraland_token: TERRALAND_TOKEN_ADDRESS.into(),
+            staking_token: AssetInfo::Token { contract_addr: STAKING_TOKEN_ADDRESS.into() },
             unbonding_period: UNBONDING_PERIOD,
             burn_address: BURN_ADDRESS.into(),
             instant_claim_percentage_loss: 0,
-            distribution_schedule: Vec::from([
-                Schedule {
-                    amount: Uint128::new(150_000_000_000),
-                    start_time: env.block.time.seconds(),
-                    end_time: env.block.time.seconds() + WEEK,
-                },
-                Schedule {
-                    amount: Uint128::new(100_000_000_000),
-                    start_time: env.block.time.seconds() + WEEK,
-                    end_time: env.block.time.seconds() + 2 * WEEK,
-                }
-            ]),
+            reward_assets: vec![terraland_reward(&env)],
             fee_config: Vec::from([
                 FeeConfig{
                     fee: Uint128::new(1000000),
@@ -701,11 +3105,41 @@ mod tests {
                     denom: "uusd".to_string()
                 }
             ]),
+            lock_tiers: vec![
+                LockTier { duration: 30 * DAY, multiplier: Decimal::percent(150) },
+                LockTier { duration: 90 * DAY, multiplier: Decimal::percent(300) },
+            ],
+            disable_rebond: false,
+            position_nfts: false,
+            penalty_destination: PenaltyDestination::Burn {},
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: 0,
+            whitelist: vec![],
+            history_retention: None,
         };
         let info = mock_info("creator", &[]);
         instantiate(deps, env, info, msg).unwrap();
     }
 
+    fn terraland_reward(env: &Env) -> RewardAsset {
+        RewardAsset {
+            info: AssetInfo::Token { contract_addr: TERRALAND_TOKEN_ADDRESS.to_string() },
+            distribution_schedule: Vec::from([
+                Schedule {
+                    amount: Uint128::new(150_000_000_000),
+                    start_time: env.block.time.seconds(),
+                    end_time: env.block.time.seconds() + WEEK,
+                },
+                Schedule {
+                    amount: Uint128::new(100_000_000_000),
+                    start_time: env.block.time.seconds() + WEEK,
+                    end_time: env.block.time.seconds() + 2 * WEEK,
+                }
+            ]),
+        }
+    }
+
     #[test]
     fn proper_instantiation() {
         let mut deps = mock_dependencies(&[]);
@@ -714,7 +3148,7 @@ mod tests {
 
         // it worked, let's query the state
         let res = query_config(deps.as_ref()).unwrap();
-        assert_eq!(INIT_ADMIN, res.owner.as_str());
+        assert_eq!(Some(Addr::unchecked(INIT_ADMIN)), res.owner);
 
         let res = query_state(deps.as_ref()).unwrap();
         assert_eq!(0, res.total_stake.u128());
@@ -727,13 +3161,13 @@ mod tests {
         let mut env = mock_env();
         env.block.height += height_delta;
         env.block.time = env.block.time.plus_seconds(height_delta * 6);
-        return env;
+        env
     }
 
     fn get_member(deps: Deps, addr: String) -> Option<MemberResponseItem> {
-        let raw = query(deps, mock_env(), QueryMsg::Member { address: addr }).unwrap();
+        let raw = query(deps, mock_env(), QueryMsg::Staker { address: addr }).unwrap();
         let res: MemberResponse = from_slice(&raw).unwrap();
-        return res.member;
+        res.member
     }
 
     // this tests the member queries
@@ -761,7 +3195,7 @@ mod tests {
                 let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
                     sender: addr.to_string(),
                     amount: Uint128::new(*stake),
-                    msg: to_binary(&ReceiveMsg::Bond {}).unwrap(),
+                    msg: to_binary(&ReceiveMsg::Bond { lock_duration: None }).unwrap(),
                 });
                 let info = mock_info(STAKING_TOKEN_ADDRESS, &[]);
                 execute(deps.branch(), env.clone(), info, msg).unwrap();
@@ -797,17 +3231,75 @@ mod tests {
         assert_eq!(res3.member.unwrap().stake, user3_stake.into());
     }
 
+    fn get_reward(deps: Deps, addr: &str, asset: &str, height_delta: u64) -> u128 {
+        let res = query_member(deps, get_env(height_delta), addr.into()).unwrap();
+        res.member.unwrap_or_default().rewards.iter()
+            .find(|r| r.asset == asset)
+            .map(|r| r.reward.u128())
+            .unwrap_or_default()
+    }
+
     fn assert_rewards(deps: Deps, user1_reward: u128, user2_reward: u128, user3_reward: u128, height_delta: u64) {
-        let env = get_env(height_delta);
+        assert_eq!(get_reward(deps, USER1, TERRALAND_TOKEN_ADDRESS, height_delta), user1_reward);
+        assert_eq!(get_reward(deps, USER2, TERRALAND_TOKEN_ADDRESS, height_delta), user2_reward);
+        assert_eq!(get_reward(deps, USER3, TERRALAND_TOKEN_ADDRESS, height_delta), user3_reward);
+    }
 
-        let res1 = query_member(deps, env.clone(), USER1.into()).unwrap();
-        assert_eq!(res1.member.unwrap_or(Default::default()).reward, user1_reward.into());
+    #[test]
+    fn ownership_transfer() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
 
-        let res2 = query_member(deps, env.clone(), USER2.into()).unwrap();
-        assert_eq!(res2.member.unwrap_or(Default::default()).reward, user2_reward.into());
+        let update_config = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: Some(UNBONDING_PERIOD * 2),
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: None,
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
 
-        let res3 = query_member(deps, env.clone(), USER3.into()).unwrap();
-        assert_eq!(res3.member.unwrap_or(Default::default()).reward, user3_reward.into());
+        let msg = ExecuteMsg::ProposeOwner {
+            new_owner: USER1.to_string(),
+            expires: Some(Expiration::AtHeight(mock_env().block.height + 100)),
+        };
+        let info = mock_info(INIT_ADMIN, &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::PendingOwner {}).unwrap();
+        let pending: PendingOwnerResponse = from_slice(&res).unwrap();
+        assert_eq!(pending.pending_owner, Some(USER1.to_string()));
+
+        // old owner keeps control until the proposal is accepted
+        let info = mock_info(USER1, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, update_config.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info(INIT_ADMIN, &[]);
+        execute(deps.as_mut(), mock_env(), info, update_config.clone()).unwrap();
+
+        let info = mock_info(USER2, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::AcceptOwnership {}).unwrap_err();
+        assert_eq!(err, ContractError::Ownership(OwnershipError::Unauthorized {}));
+
+        let info = mock_info(USER1, &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::AcceptOwnership {}).unwrap();
+
+        let res = query_config(deps.as_ref()).unwrap();
+        assert_eq!(Some(Addr::unchecked(USER1)), res.owner);
+
+        let info = mock_info(INIT_ADMIN, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, update_config.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info(USER1, &[]);
+        execute(deps.as_mut(), mock_env(), info, update_config).unwrap();
     }
 
     #[test]
@@ -822,14 +3314,14 @@ mod tests {
         assert_stake(deps.as_ref(), 12_000, 7_500, 500, 1);
 
         // check rewards after 1 block (6 seconds)
-        assert_rewards(deps.as_ref(), 892_854, 558_033, 37_202, 2);
+        assert_rewards(deps.as_ref(), 892_857, 558_035, 37_202, 2);
 
         bond_cw20(deps.as_mut(), 0, 0, 5_000, 2);
         assert_stake(deps.as_ref(), 12_000, 7_500, 5_500, 2);
-        assert_rewards(deps.as_ref(), 892_854, 558_033, 37_202, 2);
+        assert_rewards(deps.as_ref(), 892_857, 558_035, 37_202, 2);
 
         // check rewards after another 1 block (6 seconds)
-        assert_rewards(deps.as_ref(), 1_607_137, 1_004_460, 364_582, 3);
+        assert_rewards(deps.as_ref(), 1_607_142, 1_004_464, 364_582, 3);
     }
 
     #[test]
@@ -841,7 +3333,7 @@ mod tests {
         bond_cw20(deps.as_mut(), 10, 0, 0, 100799);
 
         // calc reward 1 block after end of first distribution schedule
-        assert_rewards(deps.as_ref(), 2480148, 0, 0, 100801);
+        assert_rewards(deps.as_ref(), 2480159, 0, 0, 100801);
     }
 
     #[test]
@@ -855,8 +3347,8 @@ mod tests {
         assert_rewards(deps.as_ref(), 0, 0, 0, 10);
 
         // rewart is calculated after distribution start
-        assert_rewards(deps.as_ref(), 496030, 496030, 496030, 11);
-        assert_rewards(deps.as_ref(), 992060, 992060, 992060, 12);
+        assert_rewards(deps.as_ref(), 496031, 496031, 496031, 11);
+        assert_rewards(deps.as_ref(), 992063, 992063, 992063, 12);
     }
 
     #[test]
@@ -866,13 +3358,13 @@ mod tests {
 
         // bond 1 block before end of first distribution
         bond_cw20(deps.as_mut(), 10, 10, 10, 201599);
-        assert_rewards(deps.as_ref(), 330686, 330686, 330686, 201600);
+        assert_rewards(deps.as_ref(), 330688, 330688, 330688, 201600);
 
         // bond after the end of distribution - it will not change the rewards
         bond_cw20(deps.as_mut(), 1000, 1000, 1000, 201601);
         assert_stake(deps.as_ref(), 1010, 1010, 1010, 201601);
-        assert_rewards(deps.as_ref(), 330686, 330686, 330686, 201601);
-        assert_rewards(deps.as_ref(), 330686, 330686, 330686, 301800);
+        assert_rewards(deps.as_ref(), 330688, 330688, 330688, 201601);
+        assert_rewards(deps.as_ref(), 330688, 330688, 330688, 301800);
     }
 
     #[test]
@@ -887,7 +3379,7 @@ mod tests {
 
         assert_stake(deps.as_ref(), 12_000, 7_500, 0, 2);
 
-        assert_rewards(deps.as_ref(), 892_854, 558_033, 37_202, 2);
+        assert_rewards(deps.as_ref(), 892_857, 558_035, 37_202, 2);
     }
 
     #[test]
@@ -898,9 +3390,1552 @@ mod tests {
         bond_cw20(deps.as_mut(), 12_000, 7_500, 500, 1);
 
         let env = get_env(2);
-        let msg = ExecuteMsg::Withdraw {};
+        let msg = ExecuteMsg::Withdraw { assets: None };
         let info = mock_info(USER1,
                              &[Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }]);
         execute(deps.as_mut().branch(), env.clone(), info, msg).unwrap();
     }
+
+    #[test]
+    fn multiple_reward_assets() {
+        let mut deps = mock_dependencies(&coins(100 * WEEK as u128, "uusd"));
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = [Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }];
+
+        // add ust reward, 100 uusd per second
+        let ust_reward = RewardAsset {
+            info: AssetInfo::NativeToken { denom: "uusd".to_string() },
+            distribution_schedule: vec![Schedule {
+                amount: Uint128::new(100 * WEEK as u128),
+                start_time: mock_env().block.time.seconds(),
+                end_time: mock_env().block.time.seconds() + WEEK,
+            }],
+        };
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: Some(vec![terraland_reward(&mock_env()), ust_reward.clone()]),
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        // assets cannot be listed twice
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: Some(vec![ust_reward.clone(), ust_reward]),
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        let err = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::DuplicateRewardAsset("uusd".to_string()));
+
+        bond_cw20(deps.as_mut(), 1_000, 0, 0, 1);
+
+        // each asset follows its own schedule
+        assert_eq!(get_reward(deps.as_ref(), USER1, TERRALAND_TOKEN_ADDRESS, 2), 1_488_095);
+        assert_eq!(get_reward(deps.as_ref(), USER1, "uusd", 2), 600);
+
+        // unknown assets cannot be withdrawn
+        let msg = ExecuteMsg::Withdraw { assets: Some(vec!["unknown".to_string()]) };
+        let err = execute(deps.as_mut(), get_env(2), mock_info(USER1, &fee), msg).unwrap_err();
+        assert_eq!(err, ContractError::UnknownRewardAsset("unknown".to_string()));
+
+        // withdraw ust only
+        let msg = ExecuteMsg::Withdraw { assets: Some(vec!["uusd".to_string()]) };
+        let res = execute(deps.as_mut(), get_env(2), mock_info(USER1, &fee), msg).unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(BankMsg::Send {
+            to_address: USER1.to_string(),
+            amount: vec![Coin { denom: "uusd".to_string(), amount: Uint128::new(600) }],
+        })]);
+
+        // the rest goes in terraland tokens
+        let msg = ExecuteMsg::Withdraw { assets: None };
+        let res = execute(deps.as_mut(), get_env(2), mock_info(USER1, &fee), msg).unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: TERRALAND_TOKEN_ADDRESS.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: USER1.to_string(),
+                amount: Uint128::new(1_488_095),
+            }).unwrap(),
+            funds: vec![],
+        })]);
+
+        let msg = ExecuteMsg::Withdraw { assets: None };
+        let err = execute(deps.as_mut(), get_env(2), mock_info(USER1, &fee), msg).unwrap_err();
+        assert_eq!(err, ContractError::NothingToWithdraw {});
+
+        let member = query_member(deps.as_ref(), get_env(2), USER1.to_string()).unwrap().member.unwrap();
+        assert_eq!(member.rewards.len(), 2);
+        assert!(member.rewards.iter().all(|r| r.reward == r.withdrawn));
+
+        // removed asset stops accruing, the reward accrued until then is still withdrawn
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: Some(vec![terraland_reward(&mock_env())]),
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        execute(deps.as_mut(), get_env(3), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        let cfg = query_config(deps.as_ref()).unwrap();
+        assert_eq!(cfg.retired_assets, vec![AssetInfo::NativeToken { denom: "uusd".to_string() }]);
+        assert_eq!(get_reward(deps.as_ref(), USER1, "uusd", 4), 1_200);
+
+        let msg = ExecuteMsg::Withdraw { assets: Some(vec!["uusd".to_string()]) };
+        let res = execute(deps.as_mut(), get_env(4), mock_info(USER1, &fee), msg).unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(BankMsg::Send {
+            to_address: USER1.to_string(),
+            amount: vec![Coin { denom: "uusd".to_string(), amount: Uint128::new(600) }],
+        })]);
+    }
+
+    #[test]
+    fn locked_bond_earns_boosted_reward() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = [Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }];
+
+        let bond_locked = |lock_duration: u64| ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER2.to_string(),
+            amount: Uint128::new(1_000),
+            msg: to_binary(&ReceiveMsg::Bond { lock_duration: Some(lock_duration) }).unwrap(),
+        });
+
+        // only durations of the configured tiers
+        let info = mock_info(STAKING_TOKEN_ADDRESS, &[]);
+        let err = execute(deps.as_mut(), get_env(1), info.clone(), bond_locked(7 * DAY)).unwrap_err();
+        assert_eq!(err, ContractError::LockTierNotFound(7 * DAY));
+
+        bond_cw20(deps.as_mut(), 1_000, 0, 0, 1);
+        execute(deps.as_mut(), get_env(1), info, bond_locked(90 * DAY)).unwrap();
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_weight, Uint128::new(4_000));
+
+        let member = query_member(deps.as_ref(), get_env(1), USER2.to_string())
+            .unwrap().member.unwrap();
+        assert_eq!(member.stake, Uint128::new(1_000));
+        assert_eq!(member.weight, Uint128::new(3_000));
+        let lock_end = get_env(1).block.time.seconds() + 90 * DAY;
+        assert_eq!(member.positions, vec![PositionResponseItem {
+            amount: Uint128::new(1_000),
+            multiplier: Decimal::percent(300),
+            lock_end,
+            weight: Uint128::new(3_000),
+        }]);
+
+        // rewards are distributed by weight
+        assert_eq!(get_reward(deps.as_ref(), USER1, TERRALAND_TOKEN_ADDRESS, 2), 372_023);
+        assert_eq!(get_reward(deps.as_ref(), USER2, TERRALAND_TOKEN_ADDRESS, 2), 1_116_071);
+
+        // locked tokens cannot be unbonded
+        let msg = ExecuteMsg::Unbond { tokens: Uint128::new(1) };
+        let err = execute(deps.as_mut(), get_env(2), mock_info(USER2, &fee), msg).unwrap_err();
+        assert_eq!(err, ContractError::StakeLocked {});
+
+        // but can after the lock ends
+        let after_lock = (lock_end - mock_env().block.time.seconds()) / 6 + 1;
+        unbond(deps.as_mut(), 0, 1_000, 0, after_lock, &fee);
+        let member = query_member(deps.as_ref(), get_env(after_lock), USER2.to_string())
+            .unwrap().member.unwrap();
+        assert_eq!(member.stake, Uint128::zero());
+        assert_eq!(member.weight, Uint128::zero());
+        assert_eq!(member.positions, vec![]);
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_weight, Uint128::new(1_000));
+    }
+
+    #[test]
+    fn lock_boost_ends_at_lock_end() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+
+        let update_config = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: None,
+            fee_config: None,
+            lock_tiers: Some(vec![LockTier { duration: DAY, multiplier: Decimal::percent(300) }]),
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), update_config).unwrap();
+
+        bond_cw20(deps.as_mut(), 1_000, 0, 0, 1);
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER2.to_string(),
+            amount: Uint128::new(1_000),
+            msg: to_binary(&ReceiveMsg::Bond { lock_duration: Some(DAY) }).unwrap(),
+        });
+        execute(deps.as_mut(), get_env(1), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg).unwrap();
+
+        // nobody interacts with the contract after the lock ends
+        let lock_end = DAY / 6 + 1;
+        assert_rewards(deps.as_ref(), 5_357_142_857, 16_071_428_571, 0, lock_end);
+
+        // the boost ends at the lock end, both members earn the same afterwards
+        let later = lock_end + DAY / 6;
+        assert_rewards(deps.as_ref(), 5_357_142_857 + 10_714_285_714, 16_071_428_571 + 10_714_285_714, 0, later);
+        let member = query_member(deps.as_ref(), get_env(later), USER2.to_string())
+            .unwrap().member.unwrap();
+        assert_eq!(member.weight, Uint128::new(1_000));
+        assert_eq!(member.positions, vec![]);
+
+        // the next update charges the boost until the lock end as well
+        bond_cw20(deps.as_mut(), 0, 0, 1_000, later);
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_weight, Uint128::new(3_000));
+        assert_rewards(deps.as_ref(), 16_071_428_571, 26_785_714_285, 0, later);
+    }
+
+    #[test]
+    fn lock_tiers_need_distinct_durations() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+
+        let update_config = |lock_tiers: Vec<LockTier>| ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: None,
+            fee_config: None,
+            lock_tiers: Some(lock_tiers),
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        let admin = mock_info(INIT_ADMIN, &[]);
+
+        let msg = update_config(vec![LockTier { duration: 0, multiplier: Decimal::percent(300) }]);
+        let err = execute(deps.as_mut(), mock_env(), admin.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::ZeroLockDuration {});
+        let msg = update_config(vec![
+            LockTier { duration: DAY, multiplier: Decimal::percent(150) },
+            LockTier { duration: DAY, multiplier: Decimal::percent(300) },
+        ]);
+        let err = execute(deps.as_mut(), mock_env(), admin, msg).unwrap_err();
+        assert_eq!(err, ContractError::DuplicateLockTier(DAY));
+
+        // a bond without a lock end does not keep extra weight
+        bond_cw20(deps.as_mut(), 1_000, 0, 0, 1);
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER2.to_string(),
+            amount: Uint128::new(1_000),
+            msg: to_binary(&ReceiveMsg::Bond { lock_duration: Some(0) }).unwrap(),
+        });
+        let err = execute(deps.as_mut(), get_env(1), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::LockTierNotFound(0));
+        bond_cw20(deps.as_mut(), 0, 1_000, 0, 2);
+
+        let weights: Uint128 = [USER1, USER2].iter()
+            .map(|addr| query_member(deps.as_ref(), get_env(3), addr.to_string()).unwrap().member.unwrap().weight)
+            .sum();
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_weight, weights);
+    }
+
+    #[test]
+    fn compound_reward() {
+        let mut deps = mock_dependencies_with_tokens(&[(STAKING_TOKEN_ADDRESS, 2_000 + 100 * WEEK as u128)]);
+        default_instantiate(deps.as_mut(), mock_env());
+        bond_cw20(deps.as_mut(), 1_000, 1_000, 0, 1);
+
+        let err = execute(deps.as_mut(), get_env(2), mock_info(USER1, &[]), ExecuteMsg::Compound {})
+            .unwrap_err();
+        assert_eq!(err, ContractError::CannotCompound {});
+
+        // staking tokens become a reward, 100 per second
+        let staking_reward = RewardAsset {
+            info: AssetInfo::Token { contract_addr: STAKING_TOKEN_ADDRESS.to_string() },
+            distribution_schedule: vec![Schedule {
+                amount: Uint128::new(100 * WEEK as u128),
+                start_time: get_env(1).block.time.seconds(),
+                end_time: get_env(1).block.time.seconds() + WEEK,
+            }],
+        };
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: Some(vec![terraland_reward(&mock_env()), staking_reward]),
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        execute(deps.as_mut(), get_env(1), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::SetAutoCompound { enabled: true };
+        let err = execute(deps.as_mut(), get_env(1), mock_info(USER3, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::MemberNotFound {});
+        execute(deps.as_mut(), get_env(1), mock_info(USER2, &[]), msg).unwrap();
+
+        // 6 seconds later both have 300 tokens of reward
+        let res = execute(deps.as_mut(), get_env(2), mock_info(USER1, &[]), ExecuteMsg::Compound {})
+            .unwrap();
+        assert_eq!(res.messages, vec![]);
+        assert_eq!(res.attributes[1], attr("amount", "300"));
+
+        let err = execute(deps.as_mut(), get_env(2), mock_info(USER1, &[]), ExecuteMsg::Compound {})
+            .unwrap_err();
+        assert_eq!(err, ContractError::NothingToCompound {});
+
+        // anybody can harvest for members with auto compound
+        let msg = ExecuteMsg::Harvest { limit: Some(1) };
+        let res = execute(deps.as_mut(), get_env(2), mock_info("keeper", &[]), msg.clone()).unwrap();
+        assert_eq!(res.attributes, vec![
+            attr("action", "harvest"),
+            attr("members", "1"),
+            attr("amount", "300"),
+        ]);
+        assert_eq!(HARVEST_CURSOR.load(deps.as_ref().storage).unwrap(), Addr::unchecked(USER2));
+
+        // nobody left after the cursor, the next harvest starts over
+        let res = execute(deps.as_mut(), get_env(2), mock_info("keeper", &[]), msg).unwrap();
+        assert_eq!(res.attributes[1], attr("members", "0"));
+        assert_eq!(HARVEST_CURSOR.may_load(deps.as_ref().storage).unwrap(), None);
+
+        for addr in [USER1, USER2] {
+            let member = query_member(deps.as_ref(), get_env(2), addr.to_string()).unwrap();
+            assert_eq!(member.member.unwrap().stake, Uint128::new(1_300));
+        }
+        let state = query_state(deps.as_ref()).unwrap();
+        assert_eq!(state.total_stake, Uint128::new(2_600));
+        assert_eq!(state.total_weight, Uint128::new(2_600));
+
+        let member = query_member(deps.as_ref(), get_env(2), USER2.to_string()).unwrap().member.unwrap();
+        assert!(member.auto_compound);
+        let reward = member.rewards.iter().find(|r| r.asset == STAKING_TOKEN_ADDRESS).unwrap();
+        assert_eq!(reward.reward, reward.withdrawn);
+    }
+
+    #[test]
+    fn schedule_operations() {
+        let mut deps = mock_dependencies(&coins(1_000_000, "uusd"));
+        default_instantiate(deps.as_mut(), mock_env());
+        let admin = mock_info(INIT_ADMIN, &[]);
+        let t1 = get_env(1).block.time.seconds();
+
+        let ust_reward = RewardAsset {
+            info: AssetInfo::NativeToken { denom: "uusd".to_string() },
+            distribution_schedule: vec![],
+        };
+        let update_reward_assets = |ust_reward: &RewardAsset| ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: Some(vec![terraland_reward(&mock_env()), ust_reward.clone()]),
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        execute(deps.as_mut(), get_env(1), admin.clone(), update_reward_assets(&ust_reward)).unwrap();
+        bond_cw20(deps.as_mut(), 1_000, 0, 0, 1);
+
+        let add_schedule = |amount: u128, start_time: u64, end_time: u64| ExecuteMsg::AddSchedule {
+            asset: "uusd".to_string(),
+            schedule: Schedule { amount: Uint128::new(amount), start_time, end_time },
+        };
+
+        // only admin
+        let err = execute(deps.as_mut(), get_env(1), mock_info(USER1, &[]), add_schedule(6_000, t1, t1 + 60))
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // 100 uusd per second
+        execute(deps.as_mut(), get_env(1), admin.clone(), add_schedule(6_000, t1, t1 + 60)).unwrap();
+
+        let invalid = |reason: &str| ContractError::InvalidSchedule { reason: reason.to_string() };
+        let err = execute(deps.as_mut(), get_env(1), admin.clone(), add_schedule(6_000, t1 + 30, t1 + 90))
+            .unwrap_err();
+        assert_eq!(err, invalid("schedules have to be sorted and cannot overlap"));
+        let err = execute(deps.as_mut(), get_env(1), admin.clone(), add_schedule(6_000, t1 - 6, t1 + 90))
+            .unwrap_err();
+        assert_eq!(err, invalid("schedule cannot start in the past"));
+        let err = execute(deps.as_mut(), get_env(1), admin.clone(), add_schedule(6_000, t1 + 100, t1 + 100))
+            .unwrap_err();
+        assert_eq!(err, invalid("schedule has to end after it starts"));
+        let err = execute(deps.as_mut(), get_env(1), admin.clone(), add_schedule(2_000_000, t1 + 100, t1 + 200))
+            .unwrap_err();
+        assert_eq!(err, ContractError::InsufficientRewardFunds {
+            asset: "uusd".to_string(),
+            balance: Uint128::new(1_000_000),
+            required: Uint128::new(2_006_000),
+        });
+
+        // schedules cannot be rewritten with the config
+        let err = execute(deps.as_mut(), get_env(1), admin.clone(), update_reward_assets(&ust_reward))
+            .unwrap_err();
+        assert_eq!(err, ContractError::ScheduleChanged("uusd".to_string()));
+
+        // extending the running schedule keeps the elapsed part
+        let msg = ExecuteMsg::ExtendSchedule {
+            asset: "uusd".to_string(),
+            end_time: t1 + 120,
+            amount: Uint128::new(6_000),
+        };
+        execute(deps.as_mut(), get_env(2), admin.clone(), msg).unwrap();
+        let cfg = query_config(deps.as_ref()).unwrap();
+        assert_eq!(cfg.reward_assets[1].distribution_schedule, vec![
+            Schedule { amount: Uint128::new(600), start_time: t1, end_time: t1 + 6 },
+            Schedule { amount: Uint128::new(11_400), start_time: t1 + 6, end_time: t1 + 120 },
+        ]);
+        assert_eq!(get_reward(deps.as_ref(), USER1, "uusd", 2), 600);
+        assert_eq!(get_reward(deps.as_ref(), USER1, "uusd", 3), 1_200);
+
+        // only schedules which have not started can be cancelled
+        execute(deps.as_mut(), get_env(3), admin.clone(), add_schedule(1_000, t1 + 200, t1 + 300)).unwrap();
+        let cancel = |start_time: u64| ExecuteMsg::CancelFutureSchedule {
+            asset: "uusd".to_string(),
+            start_time,
+        };
+        let err = execute(deps.as_mut(), get_env(3), admin.clone(), cancel(t1 + 6)).unwrap_err();
+        assert_eq!(err, invalid("schedule has already started"));
+        let err = execute(deps.as_mut(), get_env(3), admin.clone(), cancel(t1 + 150)).unwrap_err();
+        assert_eq!(err, ContractError::ScheduleNotFound {});
+        execute(deps.as_mut(), get_env(3), admin, cancel(t1 + 200)).unwrap();
+        let cfg = query_config(deps.as_ref()).unwrap();
+        assert_eq!(cfg.reward_assets[1].distribution_schedule.len(), 2);
+    }
+
+    #[test]
+    fn schedule_funding_excludes_owed_tokens() {
+        let mut deps = mock_dependencies_with_tokens(&[(STAKING_TOKEN_ADDRESS, 7_000)]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let admin = mock_info(INIT_ADMIN, &[]);
+        let t1 = get_env(1).block.time.seconds();
+
+        let update_reward_assets = |reward_asset: RewardAsset| ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: Some(vec![terraland_reward(&mock_env()), reward_asset]),
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+
+        // new assets cannot skip the funding check
+        let ust_reward = RewardAsset {
+            info: AssetInfo::NativeToken { denom: "uusd".to_string() },
+            distribution_schedule: vec![Schedule { amount: Uint128::new(6_000), start_time: t1, end_time: t1 + 60 }],
+        };
+        let err = execute(deps.as_mut(), get_env(1), admin.clone(), update_reward_assets(ust_reward)).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientRewardFunds {
+            asset: "uusd".to_string(),
+            balance: Uint128::zero(),
+            required: Uint128::new(6_000),
+        });
+
+        let staking_reward = RewardAsset {
+            info: AssetInfo::Token { contract_addr: STAKING_TOKEN_ADDRESS.to_string() },
+            distribution_schedule: vec![],
+        };
+        execute(deps.as_mut(), get_env(1), admin.clone(), update_reward_assets(staking_reward)).unwrap();
+
+        // 600 staked and 400 in the claim are not available
+        bond_cw20(deps.as_mut(), 1_000, 0, 0, 1);
+        unbond(deps.as_mut(), 400, 0, 0, 1, &coins(1_000_000, "uusd"));
+        let add_schedule = |amount: u128| ExecuteMsg::AddSchedule {
+            asset: STAKING_TOKEN_ADDRESS.to_string(),
+            schedule: Schedule { amount: Uint128::new(amount), start_time: t1, end_time: t1 + 60 },
+        };
+        let err = execute(deps.as_mut(), get_env(1), admin.clone(), add_schedule(6_001)).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientRewardFunds {
+            asset: STAKING_TOKEN_ADDRESS.to_string(),
+            balance: Uint128::new(6_000),
+            required: Uint128::new(6_001),
+        });
+        execute(deps.as_mut(), get_env(1), admin.clone(), add_schedule(6_000)).unwrap();
+
+        // nor is the reward distributed and not withdrawn yet
+        let msg = ExecuteMsg::ExtendSchedule {
+            asset: STAKING_TOKEN_ADDRESS.to_string(),
+            end_time: t1 + 120,
+            amount: Uint128::new(1),
+        };
+        let err = execute(deps.as_mut(), get_env(3), admin, msg).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientRewardFunds {
+            asset: STAKING_TOKEN_ADDRESS.to_string(),
+            balance: Uint128::new(4_800),
+            required: Uint128::new(4_801),
+        });
+    }
+
+    #[test]
+    fn migrate_single_reward_stream() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        default_instantiate(deps.as_mut(), env.clone());
+
+        // storage layout of the previous version
+        let terraland = terraland_reward(&env);
+        legacy::CONFIG.save(deps.as_mut().storage, &legacy::Config {
+            owner: Some(Addr::unchecked(INIT_ADMIN)),
+            staking_token: Addr::unchecked(STAKING_TOKEN_ADDRESS),
+            terraland_token: Addr::unchecked(TERRALAND_TOKEN_ADDRESS),
+            unbonding_period: UNBONDING_PERIOD,
+            burn_address: Addr::unchecked(BURN_ADDRESS),
+            instant_claim_percentage_loss: 0,
+            distribution_schedule: terraland.distribution_schedule.clone(),
+            fee_config: vec![],
+        }).unwrap();
+        legacy::STATE.save(deps.as_mut().storage, &legacy::State {
+            total_stake: Uint128::new(1_000),
+            last_updated: env.block.time.seconds(),
+            global_reward_index: Decimal::percent(50),
+            num_of_members: 1,
+        }).unwrap();
+        legacy::MEMBERS.save(deps.as_mut().storage, &Addr::unchecked(USER1), &legacy::MemberInfo {
+            stake: Uint128::new(1_000),
+            pending_reward: Uint128::new(700),
+            reward_index: Decimal::percent(50),
+            withdrawn: Uint128::new(200),
+        }).unwrap();
+        let legacy_claims = cw_controllers::Claims::new("claims");
+        for amount in [100u128, 200] {
+            legacy_claims.create_claim(
+                deps.as_mut().storage, &Addr::unchecked(USER1), Uint128::new(amount), Expiration::Never {},
+            ).unwrap();
+        }
+
+        migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+
+        let cfg = query_config(deps.as_ref()).unwrap();
+        assert_eq!(cfg.reward_assets, vec![terraland]);
+        assert_eq!(cfg.staking_token, AssetInfo::Token { contract_addr: STAKING_TOKEN_ADDRESS.to_string() });
+        assert_eq!(cfg.owner, Some(Addr::unchecked(INIT_ADMIN)));
+
+        let state = query_state(deps.as_ref()).unwrap();
+        assert_eq!(state.global_reward_index, vec![RewardIndex {
+            asset: TERRALAND_TOKEN_ADDRESS.to_string(),
+            index: Decimal256::percent(50),
+            remainder: Uint128::zero(),
+            unwithdrawn: Uint128::new(500),
+        }]);
+
+        let member = MEMBERS.load(deps.as_ref().storage, &Addr::unchecked(USER1)).unwrap();
+        assert_eq!(member, MemberInfo {
+            stake: Uint128::new(1_000),
+            rewards: vec![MemberReward {
+                asset: TERRALAND_TOKEN_ADDRESS.to_string(),
+                pending_reward: Uint128::new(700),
+                reward_index: Decimal256::percent(50),
+                withdrawn: Uint128::new(200),
+            }],
+            positions: vec![],
+        });
+
+        // the reward keeps accruing, 6 seconds of the first schedule
+        assert_eq!(get_reward(deps.as_ref(), USER1, TERRALAND_TOKEN_ADDRESS, 1), 700 + 1_488_095);
+
+        // claims get ids
+        let claims = CLAIMS.load(deps.as_ref().storage, &Addr::unchecked(USER1)).unwrap();
+        assert_eq!(claims.iter().map(|c| (c.id, c.amount.u128())).collect::<Vec<_>>(), vec![(1, 100), (2, 200)]);
+        assert_eq!(TOTAL_CLAIMS.load(deps.as_ref().storage).unwrap(), Uint128::new(300));
+
+        // migrating again does not change anything
+        migrate(deps.as_mut(), env, MigrateMsg {}).unwrap();
+        assert_eq!(query_config(deps.as_ref()).unwrap(), cfg);
+    }
+
+    #[test]
+    fn reward_rate_and_projection() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        bond_cw20(deps.as_mut(), 1_000, 3_000, 0, 1);
+        let start = mock_env().block.time.seconds();
+
+        let rate = |at_time: Option<u64>| {
+            query_reward_rate(deps.as_ref(), get_env(1), at_time).unwrap().rates[0].amount.u128()
+        };
+        // 150_000_000_000 over a week, then 100_000_000_000 over a week, as accrued in the second
+        let week = WEEK as u128;
+        assert_eq!(rate(None), 150_000_000_000 * 7 / week - 150_000_000_000 * 6 / week);
+        assert_eq!(rate(None), 248_016);
+        assert_eq!(rate(Some(start + WEEK)), 165_343);
+        assert_eq!(rate(Some(start + 2 * WEEK)), 0);
+
+        // members accrue exactly the rate in the second
+        let accrued = |seconds: u64| -> u128 {
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(seconds);
+            [USER1, USER2].iter()
+                .map(|addr| query_member(deps.as_ref(), env.clone(), addr.to_string()).unwrap().member.unwrap())
+                .map(|m| m.rewards[0].reward.u128())
+                .sum()
+        };
+        assert_eq!(accrued(7) - accrued(6), rate(None));
+
+        // yearly rewards per unit of weight
+        let res = query_apr(deps.as_ref(), get_env(1)).unwrap();
+        assert_eq!(res.aprs, vec![AssetApr {
+            asset: TERRALAND_TOKEN_ADDRESS.to_string(),
+            apr: Decimal::from_ratio(150_000_000_000 * YEAR_IN_SEC as u128 / week, 4_000u128),
+        }]);
+
+        let res = query_remaining_rewards(deps.as_ref(), get_env(1)).unwrap();
+        assert_eq!(res.remaining, vec![AssetAmount {
+            asset: TERRALAND_TOKEN_ADDRESS.to_string(),
+            amount: Uint128::new(250_000_000_000 - 150_000_000_000 * 6 / WEEK as u128),
+        }]);
+
+        // projection matches the reward accrued by then
+        let until = get_env(11).block.time.seconds();
+        let res = query_projected_reward(deps.as_ref(), get_env(1), USER1.to_string(), until).unwrap();
+        assert_eq!(res.rewards[0].reward.u128(), get_reward(deps.as_ref(), USER1, TERRALAND_TOKEN_ADDRESS, 11));
+        assert_eq!(res.rewards[0].reward.u128(), 150_000_000_000 * 60 / WEEK as u128 / 4);
+
+        // also across schedules
+        let until = start + WEEK + 60;
+        let res = query_projected_reward(deps.as_ref(), get_env(1), USER2.to_string(), until).unwrap();
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(WEEK + 60);
+        let member = query_member(deps.as_ref(), env, USER2.to_string()).unwrap().member.unwrap();
+        assert_eq!(res.rewards, member.rewards);
+
+        // cannot project into the past
+        query_projected_reward(deps.as_ref(), get_env(2), USER1.to_string(), start).unwrap_err();
+    }
+
+    #[test]
+    fn rebond_unbonded_tokens() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = coins(1_000_000, "uusd");
+
+        bond_cw20(deps.as_mut(), 10_000, 0, 0, 1);
+        unbond(deps.as_mut(), 1_000, 0, 0, 1, &fee);
+        unbond(deps.as_mut(), 2_000, 0, 0, 51, &fee);
+
+        // the first claim is released, the second one is not yet
+        let reward = get_reward(deps.as_ref(), USER1, TERRALAND_TOKEN_ADDRESS, 101);
+        let msg = ExecuteMsg::Rebond { amount: Uint128::new(2_500) };
+        let res = execute(deps.as_mut(), get_env(101), mock_info(USER1, &[]), msg).unwrap();
+        assert_eq!(res.attributes, vec![
+            attr("action", "rebond"),
+            attr("amount", "2500"),
+            attr("sender", USER1),
+        ]);
+
+        // the newest claim is taken whole, the released one in part
+        let member = query_member(deps.as_ref(), get_env(101), USER1.to_string()).unwrap().member.unwrap();
+        assert_eq!(member.stake, Uint128::new(9_500));
+        assert_eq!(member.claims.iter().map(|c| (c.id, c.amount.u128())).collect::<Vec<_>>(), vec![(1, 500)]);
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_stake, Uint128::new(9_500));
+
+        // reward accrued before rebond is kept, the rebonded tokens earn from now on
+        assert_eq!(get_reward(deps.as_ref(), USER1, TERRALAND_TOKEN_ADDRESS, 101), reward);
+        assert_eq!(member.rewards[0].reward_index, query_state(deps.as_ref()).unwrap().global_reward_index[0].index);
+
+        let msg = ExecuteMsg::Rebond { amount: Uint128::new(600) };
+        let err = execute(deps.as_mut(), get_env(101), mock_info(USER1, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::RebondExceedsClaims { available: Uint128::new(500) });
+
+        // the rest can still be claimed
+        let msg = ExecuteMsg::Claim { amount: None };
+        let res = execute(deps.as_mut(), get_env(101), mock_info(USER1, &fee), msg).unwrap();
+        assert_eq!(res.attributes[1], attr("tokens", format!("500 {}", STAKING_TOKEN_ADDRESS)));
+
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: None,
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: Some(true),
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        execute(deps.as_mut(), get_env(101), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        unbond(deps.as_mut(), 1_000, 0, 0, 102, &fee);
+        let msg = ExecuteMsg::Rebond { amount: Uint128::new(1_000) };
+        let err = execute(deps.as_mut(), get_env(102), mock_info(USER1, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::RebondDisabled {});
+    }
+
+    #[test]
+    fn native_staking_token() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = coins(1_000_000, "uusd");
+
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: Some(AssetInfo::NativeToken { denom: "uluna".to_string() }),
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: None,
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond { lock_duration: None };
+        let res = execute(deps.as_mut(), get_env(1), mock_info(USER1, &coins(1_000, "uluna")), bond.clone()).unwrap();
+        assert_eq!(res.attributes, vec![
+            attr("action", "bond"),
+            attr("amount", "1000"),
+            attr("sender", USER1),
+        ]);
+        let member = query_member(deps.as_ref(), get_env(1), USER1.to_string()).unwrap().member.unwrap();
+        assert_eq!(member.stake, Uint128::new(1_000));
+
+        // only the staking coin is accepted
+        let err = execute(deps.as_mut(), get_env(1), mock_info(USER1, &coins(1_000, "uusd")), bond.clone()).unwrap_err();
+        assert_eq!(err, ContractError::InvalidToken("uluna".to_string()));
+        let err = execute(deps.as_mut(), get_env(1), mock_info(USER1, &[]), bond).unwrap_err();
+        assert_eq!(err, ContractError::MissedToken {});
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER1.to_string(),
+            amount: Uint128::new(1_000),
+            msg: to_binary(&ReceiveMsg::Bond { lock_duration: None }).unwrap(),
+        });
+        let err = execute(deps.as_mut(), get_env(1), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::MissedToken {});
+
+        // claims are paid with bank transfers
+        unbond(deps.as_mut(), 400, 0, 0, 1, &fee);
+        let msg = ExecuteMsg::Claim { amount: None };
+        let res = execute(deps.as_mut(), get_env(101), mock_info(USER1, &fee), msg).unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(BankMsg::Send {
+            to_address: USER1.to_string(),
+            amount: coins(400, "uluna"),
+        })]);
+        assert_eq!(res.attributes[1], attr("tokens", "400 uluna"));
+
+        unbond(deps.as_mut(), 100, 0, 0, 101, &fee);
+        let msg = ExecuteMsg::InstantClaim { amount: None, claim_ids: None };
+        let res = execute(deps.as_mut(), get_env(101), mock_info(USER1, &fee), msg).unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(BankMsg::Send {
+            to_address: USER1.to_string(),
+            amount: coins(100, "uluna"),
+        })]);
+
+        // the staking token cannot change until the stake and the claims are paid out
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: Some(AssetInfo::Token { contract_addr: STAKING_TOKEN_ADDRESS.to_string() }),
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: None,
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        let err = execute(deps.as_mut(), get_env(101), mock_info(INIT_ADMIN, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::StakingTokenInUse {});
+        unbond(deps.as_mut(), 500, 0, 0, 102, &fee);
+        let err = execute(deps.as_mut(), get_env(102), mock_info(INIT_ADMIN, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::StakingTokenInUse {});
+        let claim = ExecuteMsg::Claim { amount: None };
+        execute(deps.as_mut(), get_env(202), mock_info(USER1, &fee), claim).unwrap();
+        execute(deps.as_mut(), get_env(202), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn transferable_position_nfts() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = coins(1_000_000, "uusd");
+
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: None,
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: Some(true),
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        // bond mints a position instead of adding to the member stake
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER1.to_string(),
+            amount: Uint128::new(1_000),
+            msg: to_binary(&ReceiveMsg::Bond { lock_duration: None }).unwrap(),
+        });
+        let res = execute(deps.as_mut(), get_env(1), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg).unwrap();
+        assert_eq!(res.attributes, vec![
+            attr("action", "bond"),
+            attr("amount", "1000"),
+            attr("sender", USER1),
+            attr("token_id", "1"),
+        ]);
+        assert_eq!(query_member(deps.as_ref(), get_env(1), USER1.to_string()).unwrap().member, None);
+        assert_eq!(query_owner_of(deps.as_ref(), get_env(1), "1".to_string(), false).unwrap().owner, USER1);
+        assert_eq!(query_tokens(deps.as_ref(), USER1.to_string(), None, None).unwrap().tokens, vec!["1"]);
+        assert_eq!(query_num_tokens(deps.as_ref()).unwrap().count, 1);
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_stake, Uint128::new(1_000));
+        assert_eq!(query_member_weight(deps.as_ref(), USER1.to_string(), None).unwrap().weight, Some(1_000));
+
+        // only the holder or an approved spender can transfer
+        let msg = ExecuteMsg::TransferNft { recipient: USER3.to_string(), token_id: "1".to_string() };
+        let err = execute(deps.as_mut(), get_env(11), mock_info(USER2, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let approve = ExecuteMsg::Approve { spender: USER2.to_string(), token_id: "1".to_string(), expires: None };
+        execute(deps.as_mut(), get_env(11), mock_info(USER1, &[]), approve).unwrap();
+
+        // rewards accrued until the transfer are paid to the previous holder
+        let info = query_nft_info(deps.as_ref(), get_env(11), "1".to_string()).unwrap().extension;
+        let accrued = info.rewards[0].reward;
+        assert!(!accrued.is_zero());
+        let res = execute(deps.as_mut(), get_env(11), mock_info(USER2, &[]), msg).unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: TERRALAND_TOKEN_ADDRESS.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient: USER1.to_string(), amount: accrued }).unwrap(),
+            funds: vec![],
+        })]);
+
+        let owner = query_owner_of(deps.as_ref(), get_env(11), "1".to_string(), true).unwrap();
+        assert_eq!(owner, OwnerOfResponse { owner: USER3.to_string(), approvals: vec![] });
+        assert_eq!(query_member_weight(deps.as_ref(), USER1.to_string(), None).unwrap().weight, None);
+        assert_eq!(query_member_weight(deps.as_ref(), USER3.to_string(), None).unwrap().weight, Some(1_000));
+
+        // future rewards go to the new holder
+        let info = query_nft_info(deps.as_ref(), get_env(21), "1".to_string()).unwrap().extension;
+        assert_eq!(info.stake, Uint128::new(1_000));
+        assert_eq!(info.rewards[0].withdrawn, accrued);
+        let since_transfer = info.rewards[0].reward - accrued;
+        let msg = ExecuteMsg::Withdraw { assets: None };
+        let res = execute(deps.as_mut(), get_env(21), mock_info(USER3, &fee), msg).unwrap();
+        assert_eq!(res.attributes[1], attr("tokens", format!("{} {}", since_transfer, TERRALAND_TOKEN_ADDRESS)));
+
+        // unbonding everything burns the position
+        let msg = ExecuteMsg::UnbondPosition { token_id: "1".to_string(), tokens: Uint128::new(1_000) };
+        let err = execute(deps.as_mut(), get_env(21), mock_info(USER1, &fee), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), get_env(21), mock_info(USER3, &fee), msg.clone()).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(query_num_tokens(deps.as_ref()).unwrap().count, 0);
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_stake, Uint128::zero());
+        let member = query_member(deps.as_ref(), get_env(21), USER3.to_string()).unwrap().member;
+        assert_eq!(member, None);
+        assert_eq!(CLAIMS.load(deps.as_ref().storage, &Addr::unchecked(USER3)).unwrap()[0].amount, Uint128::new(1_000));
+        let err = execute(deps.as_mut(), get_env(21), mock_info(USER3, &fee), msg).unwrap_err();
+        assert_eq!(err, ContractError::PositionNotFound("1".to_string()));
+    }
+
+    #[test]
+    fn emergency_withdraw() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = coins(1_000_000, "uusd");
+
+        bond_cw20(deps.as_mut(), 10_000, 5_000, 0, 1);
+        unbond(deps.as_mut(), 1_000, 0, 0, 2, &fee);
+        unbond(deps.as_mut(), 2_000, 0, 0, 200, &fee);
+
+        // a position held by the second user
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: None,
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: Some(true),
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        execute(deps.as_mut(), get_env(200), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        bond_cw20(deps.as_mut(), 0, 1_000, 0, 200);
+
+        let msg = ExecuteMsg::EmergencyWithdraw {};
+        let err = execute(deps.as_mut(), get_env(201), mock_info(USER1, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::EmergencyNotActive {});
+
+        // only a pauser turns the emergency on
+        let err = execute(deps.as_mut(), get_env(201), mock_info(USER1, &[]), ExecuteMsg::EnableEmergency {})
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let msg_grant = ExecuteMsg::GrantRole { role: Role::Pauser, address: "pauser".to_string() };
+        execute(deps.as_mut(), get_env(201), mock_info(INIT_ADMIN, &[]), msg_grant).unwrap();
+        execute(deps.as_mut(), get_env(201), mock_info("pauser", &[]), ExecuteMsg::EnableEmergency {}).unwrap();
+
+        // bonding and rewards are disabled
+        let msg_bond = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER1.to_string(),
+            amount: Uint128::new(1_000),
+            msg: to_binary(&ReceiveMsg::Bond { lock_duration: None }).unwrap(),
+        });
+        let err = execute(deps.as_mut(), get_env(201), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg_bond).unwrap_err();
+        assert_eq!(err, ContractError::EmergencyActive {});
+        let msg_withdraw = ExecuteMsg::Withdraw { assets: None };
+        let err = execute(deps.as_mut(), get_env(201), mock_info(USER1, &fee), msg_withdraw).unwrap_err();
+        assert_eq!(err, ContractError::EmergencyActive {});
+
+        // unbonds and instant claims do not accrue rewards either, the emergency withdraw
+        // returns the stake and the claims
+        let msg_unbond = ExecuteMsg::Unbond { tokens: Uint128::new(1_000) };
+        let err = execute(deps.as_mut(), get_env(201), mock_info(USER1, &fee), msg_unbond).unwrap_err();
+        assert_eq!(err, ContractError::EmergencyActive {});
+        let msg_instant_claim = ExecuteMsg::InstantClaim { amount: None, claim_ids: None };
+        let err = execute(deps.as_mut(), get_env(201), mock_info(USER1, &fee), msg_instant_claim).unwrap_err();
+        assert_eq!(err, ContractError::EmergencyActive {});
+
+        // position rewards cannot be paid out with its transfer or unbond
+        let msg_transfer = ExecuteMsg::TransferNft { recipient: USER2.to_string(), token_id: "1".to_string() };
+        let err = execute(deps.as_mut(), get_env(201), mock_info(USER2, &[]), msg_transfer).unwrap_err();
+        assert_eq!(err, ContractError::EmergencyActive {});
+        let msg_unbond = ExecuteMsg::UnbondPosition { token_id: "1".to_string(), tokens: Uint128::new(1_000) };
+        let err = execute(deps.as_mut(), get_env(201), mock_info(USER2, &fee), msg_unbond).unwrap_err();
+        assert_eq!(err, ContractError::EmergencyActive {});
+
+        // stake and both claims, released or not, are returned at once
+        let res = execute(deps.as_mut(), get_env(201), mock_info(USER1, &[]), msg.clone()).unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: STAKING_TOKEN_ADDRESS.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient: USER1.to_string(), amount: Uint128::new(10_000) })
+                .unwrap(),
+            funds: vec![],
+        })]);
+        assert_eq!(res.attributes[1], attr("amount", "10000"));
+        assert_eq!(query_member(deps.as_ref(), get_env(201), USER1.to_string()).unwrap().member, None);
+        let err = execute(deps.as_mut(), get_env(201), mock_info(USER1, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+
+        // the held position is returned with the stake
+        let res = execute(deps.as_mut(), get_env(201), mock_info(USER2, &[]), msg).unwrap();
+        assert_eq!(res.attributes[1], attr("amount", "6000"));
+        assert_eq!(query_num_tokens(deps.as_ref()).unwrap().count, 0);
+        let state = query_state(deps.as_ref()).unwrap();
+        assert_eq!((state.total_stake, state.total_weight, state.num_of_members), (Uint128::zero(), Uint128::zero(), 0));
+    }
+
+    #[test]
+    fn slashing_and_penalty_destinations() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = coins(1_000_000, "uusd");
+        let oracle = "oracle";
+
+        bond_cw20(deps.as_mut(), 10_000, 10_000, 0, 1);
+        unbond(deps.as_mut(), 2_000, 0, 0, 2, &fee);
+
+        let slash = |percentage: Decimal| ExecuteMsg::Slash {
+            address: USER1.to_string(),
+            percentage,
+            reason: "abuse".to_string(),
+        };
+        let err = execute(deps.as_mut(), get_env(3), mock_info(oracle, &[]), slash(Decimal::percent(10))).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let msg = ExecuteMsg::GrantRole { role: Role::Oracle, address: oracle.to_string() };
+        execute(deps.as_mut(), get_env(3), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        for percentage in [Decimal::zero(), Decimal::percent(150)] {
+            let err = execute(deps.as_mut(), get_env(3), mock_info(oracle, &[]), slash(percentage)).unwrap_err();
+            assert_eq!(err, ContractError::InvalidSlashPercentage {});
+        }
+
+        // stake and claims are slashed, burnt by default
+        let res = execute(deps.as_mut(), get_env(3), mock_info(oracle, &[]), slash(Decimal::percent(10))).unwrap();
+        let transfer = |recipient: &str, amount: u128| SubMsg::new(WasmMsg::Execute {
+            contract_addr: STAKING_TOKEN_ADDRESS.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount: Uint128::new(amount),
+            }).unwrap(),
+            funds: vec![],
+        });
+        assert_eq!(res.messages, vec![transfer(BURN_ADDRESS, 1_000)]);
+        let member = query_member(deps.as_ref(), get_env(3), USER1.to_string()).unwrap().member.unwrap();
+        assert_eq!(member.stake, Uint128::new(7_200));
+        assert_eq!(member.claims[0].amount, Uint128::new(1_800));
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_stake, Uint128::new(17_200));
+
+        let res = query_slashes(deps.as_ref(), USER1.to_string(), None, None).unwrap();
+        assert_eq!(res.slashes, vec![SlashRecord {
+            id: 1,
+            percentage: Decimal::percent(10),
+            reason: "abuse".to_string(),
+            amount: Uint128::new(1_000),
+            time: get_env(3).block.time.seconds(),
+        }]);
+
+        let new_config = |reward_assets: Option<Vec<RewardAsset>>, penalty_destination: PenaltyDestination| {
+            ExecuteMsg::UpdateConfig(NewConfig {
+                staking_token: None,
+                unbonding_period: None,
+                burn_address: None,
+                instant_claim_percentage_loss: None,
+                reward_assets,
+                fee_config: None,
+                lock_tiers: None,
+                disable_rebond: None,
+                position_nfts: None,
+                penalty_destination: Some(penalty_destination),
+                max_total_stake: None,
+                max_stake_per_address: None,
+                whitelist_until: None,
+                history_retention: None,
+            })
+        };
+        let admin = mock_info(INIT_ADMIN, &[]);
+        let msg = new_config(None, PenaltyDestination::Treasury { address: "treasury".to_string() });
+        execute(deps.as_mut(), get_env(3), admin.clone(), msg).unwrap();
+        let res = execute(deps.as_mut(), get_env(4), mock_info(oracle, &[]), slash(Decimal::percent(50))).unwrap();
+        assert_eq!(res.messages, vec![transfer("treasury", 4_500)]);
+
+        // redistributed penalty is a reward in staking tokens
+        let msg = new_config(None, PenaltyDestination::Redistribute {});
+        let err = execute(deps.as_mut(), get_env(4), admin.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::CannotRedistribute {});
+        let staking_reward = RewardAsset {
+            info: AssetInfo::Token { contract_addr: STAKING_TOKEN_ADDRESS.to_string() },
+            distribution_schedule: vec![],
+        };
+        let reward_assets = vec![terraland_reward(&mock_env()), staking_reward];
+        let msg = new_config(Some(reward_assets), PenaltyDestination::Redistribute {});
+        execute(deps.as_mut(), get_env(4), admin, msg).unwrap();
+
+        // the slashed member has nothing left, the other one gets it all
+        let res = execute(deps.as_mut(), get_env(5), mock_info(oracle, &[]), slash(Decimal::one())).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(res.attributes[4], attr("amount", "4500"));
+        assert_eq!(get_reward(deps.as_ref(), USER2, STAKING_TOKEN_ADDRESS, 5), 4_500);
+        assert_eq!(get_reward(deps.as_ref(), USER1, STAKING_TOKEN_ADDRESS, 5), 0);
+        assert_eq!(query_slashes(deps.as_ref(), USER1.to_string(), Some(1), None).unwrap().slashes.len(), 2);
+
+        let err = execute(deps.as_mut(), get_env(5), mock_info(oracle, &[]), slash(Decimal::one())).unwrap_err();
+        assert_eq!(err, ContractError::NothingToSlash {});
+    }
+
+    #[test]
+    fn stake_caps_and_whitelist() {
+        let mut deps = mock_dependencies_with_tokens(&[(STAKING_TOKEN_ADDRESS, 100 * WEEK as u128)]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let admin = mock_info(INIT_ADMIN, &[]);
+
+        // staking tokens are a reward too, 100 per second
+        let staking_reward = RewardAsset {
+            info: AssetInfo::Token { contract_addr: STAKING_TOKEN_ADDRESS.to_string() },
+            distribution_schedule: vec![Schedule {
+                amount: Uint128::new(100 * WEEK as u128),
+                start_time: mock_env().block.time.seconds(),
+                end_time: mock_env().block.time.seconds() + WEEK,
+            }],
+        };
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: Some(vec![terraland_reward(&mock_env()), staking_reward]),
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: Some(Uint128::new(10_000)),
+            max_stake_per_address: Some(Uint128::new(6_000)),
+            whitelist_until: Some(get_env(10).block.time.seconds()),
+            history_retention: None,
+        });
+        execute(deps.as_mut(), mock_env(), admin.clone(), msg).unwrap();
+        let msg = ExecuteMsg::UpdateWhitelist { add: vec![USER1.to_string(), USER2.to_string()], remove: vec![] };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), admin, msg).unwrap();
+        let res = query_whitelist(deps.as_ref(), None, None).unwrap();
+        assert_eq!(res.addresses, vec![USER2.to_string(), USER1.to_string()]);
+
+        let bond = |deps: DepsMut, user: &str, amount: u128, height_delta: u64| {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: user.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::Bond { lock_duration: None }).unwrap(),
+            });
+            execute(deps, get_env(height_delta), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg)
+        };
+
+        // only whitelisted addresses bond during the window
+        let err = bond(deps.as_mut(), USER3, 1_000, 1).unwrap_err();
+        assert_eq!(err, ContractError::NotWhitelisted {});
+        bond(deps.as_mut(), USER1, 5_000, 1).unwrap();
+        let err = bond(deps.as_mut(), USER1, 1_001, 2).unwrap_err();
+        assert_eq!(err, ContractError::AddressStakeCapExceeded { remaining: Uint128::new(1_000) });
+        bond(deps.as_mut(), USER2, 4_000, 2).unwrap();
+
+        let res = query_capacity(deps.as_ref()).unwrap();
+        assert_eq!(res, CapacityResponse {
+            total_stake: Uint128::new(9_000),
+            max_total_stake: Some(Uint128::new(10_000)),
+            remaining_total_stake: Some(Uint128::new(1_000)),
+            max_stake_per_address: Some(Uint128::new(6_000)),
+            whitelist_until: get_env(10).block.time.seconds(),
+        });
+
+        // anybody bonds after the window, up to the total cap
+        let err = bond(deps.as_mut(), USER3, 1_001, 10).unwrap_err();
+        assert_eq!(err, ContractError::TotalStakeCapExceeded { remaining: Uint128::new(1_000) });
+        bond(deps.as_mut(), USER3, 1_000, 10).unwrap();
+        assert_eq!(query_capacity(deps.as_ref()).unwrap().remaining_total_stake, Some(Uint128::zero()));
+
+        // compounding cannot exceed the caps either
+        let err = execute(deps.as_mut(), get_env(11), mock_info(USER1, &[]), ExecuteMsg::Compound {})
+            .unwrap_err();
+        assert_eq!(err, ContractError::TotalStakeCapExceeded { remaining: Uint128::zero() });
+
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_assets: None,
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: Some(true),
+            penalty_destination: None,
+            max_total_stake: Some(Uint128::new(20_000)),
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        execute(deps.as_mut(), get_env(11), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        // only the reward up to the cap per address is compounded, the rest stays pending
+        let res = execute(deps.as_mut(), get_env(11), mock_info(USER1, &[]), ExecuteMsg::Compound {})
+            .unwrap();
+        assert_eq!(res.attributes[1], attr("amount", "1000"));
+        let member = query_member(deps.as_ref(), get_env(11), USER1.to_string()).unwrap().member.unwrap();
+        assert_eq!(member.stake, Uint128::new(6_000));
+        let reward = member.rewards.iter().find(|r| r.asset == STAKING_TOKEN_ADDRESS).unwrap();
+        assert!(reward.reward > reward.withdrawn);
+        let err = execute(deps.as_mut(), get_env(11), mock_info(USER1, &[]), ExecuteMsg::Compound {})
+            .unwrap_err();
+        assert_eq!(err, ContractError::AddressStakeCapExceeded { remaining: Uint128::zero() });
+
+        // harvest compounds up to the caps as well
+        let msg = ExecuteMsg::SetAutoCompound { enabled: true };
+        execute(deps.as_mut(), get_env(11), mock_info(USER2, &[]), msg).unwrap();
+        let msg = ExecuteMsg::Harvest { limit: None };
+        let res = execute(deps.as_mut(), get_env(11), mock_info("keeper", &[]), msg).unwrap();
+        assert_eq!(res.attributes[2], attr("amount", "2000"));
+        assert_eq!(address_stake(deps.as_ref().storage, &Addr::unchecked(USER2)).unwrap(), Uint128::new(6_000));
+
+        // positions cannot be transferred over the cap per address of the recipient
+        bond(deps.as_mut(), USER3, 1_000, 11).unwrap();
+        let msg = ExecuteMsg::TransferNft { recipient: USER1.to_string(), token_id: "1".to_string() };
+        let err = execute(deps.as_mut(), get_env(11), mock_info(USER3, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::AddressStakeCapExceeded { remaining: Uint128::zero() });
+        let msg = ExecuteMsg::TransferNft { recipient: "user4".to_string(), token_id: "1".to_string() };
+        execute(deps.as_mut(), get_env(11), mock_info(USER3, &[]), msg).unwrap();
+        assert_eq!(address_stake(deps.as_ref().storage, &Addr::unchecked("user4")).unwrap(), Uint128::new(1_000));
+    }
+
+    #[test]
+    fn member_history() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = coins(1_000_000, "uusd");
+
+        // 10% loss for just unbonded tokens, entries kept for 100 seconds
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: Some(10),
+            reward_assets: None,
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: Some(100),
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        bond_cw20(deps.as_mut(), 1_000, 0, 0, 1);
+        unbond(deps.as_mut(), 400, 0, 0, 2, &fee);
+        let msg = ExecuteMsg::InstantClaim { amount: None, claim_ids: None };
+        execute(deps.as_mut(), get_env(3), mock_info(USER1, &fee), msg).unwrap();
+        let reward = get_reward(deps.as_ref(), USER1, TERRALAND_TOKEN_ADDRESS, 4);
+        let msg = ExecuteMsg::Withdraw { assets: None };
+        execute(deps.as_mut(), get_env(4), mock_info(USER1, &fee), msg).unwrap();
+
+        let entry = |id: u64, action: HistoryAction, asset: &str, amount: u128, height_delta: u64| {
+            let env = get_env(height_delta);
+            HistoryEntry {
+                id,
+                action,
+                asset: asset.to_string(),
+                amount: Uint128::new(amount),
+                time: env.block.time.seconds(),
+                height: env.block.height,
+            }
+        };
+        let res = query_member_history(deps.as_ref(), USER1.to_string(), None, None).unwrap();
+        assert_eq!(res.entries, vec![
+            entry(1, HistoryAction::Bond, STAKING_TOKEN_ADDRESS, 1_000, 1),
+            entry(2, HistoryAction::Unbond, STAKING_TOKEN_ADDRESS, 400, 2),
+            entry(3, HistoryAction::InstantClaim, STAKING_TOKEN_ADDRESS, 361, 3),
+            entry(4, HistoryAction::InstantClaimFee, STAKING_TOKEN_ADDRESS, 39, 3),
+            entry(5, HistoryAction::Withdraw, TERRALAND_TOKEN_ADDRESS, reward, 4),
+        ]);
+
+        let res = query_member_history(deps.as_ref(), USER1.to_string(), Some(2), Some(2)).unwrap();
+        assert_eq!(res.entries.iter().map(|e| e.id).collect::<Vec<_>>(), vec![3, 4]);
+        let res = query_member_history(deps.as_ref(), USER2.to_string(), None, None).unwrap();
+        assert!(res.entries.is_empty());
+
+        // entries past the retention are pruned by the next one
+        bond_cw20(deps.as_mut(), 500, 0, 0, 30);
+        let res = query_member_history(deps.as_ref(), USER1.to_string(), None, None).unwrap();
+        assert_eq!(res.entries, vec![entry(6, HistoryAction::Bond, STAKING_TOKEN_ADDRESS, 500, 30)]);
+    }
+
+    #[test]
+    fn cw4_weights_and_hooks() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let admin = mock_info(INIT_ADMIN, &[]);
+        let hook = "governance";
+
+        let msg = ExecuteMsg::AddHook { addr: hook.to_string() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), admin.clone(), msg.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), admin.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::Hook(HookError::HookAlreadyRegistered {}));
+        assert_eq!(query_hooks(deps.as_ref()).unwrap().hooks, vec![hook.to_string()]);
+
+        let bond = |amount: u128| ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER1.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_binary(&ReceiveMsg::Bond { lock_duration: None }).unwrap(),
+        });
+        let hook_msg = |old: Option<u64>, new: Option<u64>| {
+            SubMsg::new(MemberChangedHookMsg::one(MemberDiff::new(USER1, old, new))
+                .into_cosmos_msg(hook)
+                .unwrap())
+        };
+
+        // bond notifies the hook
+        let res = execute(deps.as_mut(), get_env(1), mock_info(STAKING_TOKEN_ADDRESS, &[]), bond(1_000)).unwrap();
+        assert_eq!(res.messages, vec![hook_msg(None, Some(1_000))]);
+        bond_cw20(deps.as_mut(), 0, 500, 0, 1);
+
+        let res = execute(deps.as_mut(), get_env(2), mock_info(STAKING_TOKEN_ADDRESS, &[]), bond(500)).unwrap();
+        assert_eq!(res.messages, vec![hook_msg(Some(1_000), Some(1_500))]);
+
+        // unbonding everything removes the member
+        let msg = ExecuteMsg::Unbond { tokens: Uint128::new(1_500) };
+        let res = execute(deps.as_mut(), get_env(3), mock_info(USER1, &coins(1_000_000, "uusd")), msg).unwrap();
+        assert_eq!(res.messages, vec![hook_msg(Some(1_500), None)]);
+
+        // weights at the beginning of the block
+        let weight = |height: Option<u64>| {
+            query_member_weight(deps.as_ref(), USER1.to_string(), height).unwrap().weight
+        };
+        assert_eq!(weight(Some(get_env(1).block.height)), None);
+        assert_eq!(weight(Some(get_env(2).block.height)), Some(1_000));
+        assert_eq!(weight(Some(get_env(3).block.height)), Some(1_500));
+        assert_eq!(weight(None), None);
+
+        let res = query_member_weight_list(deps.as_ref(), None, None).unwrap();
+        assert_eq!(res.members, vec![Member { addr: USER2.to_string(), weight: 500 }]);
+        assert_eq!(query_total_weight(deps.as_ref()).unwrap().weight, 500);
+
+        // removed hook is not notified
+        let msg = ExecuteMsg::RemoveHook { addr: hook.to_string() };
+        execute(deps.as_mut(), mock_env(), admin, msg).unwrap();
+        let res = execute(deps.as_mut(), get_env(4), mock_info(STAKING_TOKEN_ADDRESS, &[]), bond(100)).unwrap();
+        assert!(res.messages.is_empty());
+    }
+
+    #[test]
+    fn partial_and_instant_claims() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = coins(1_000_000, "uusd");
+
+        // 10% loss for just unbonded tokens
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: Some(10),
+            reward_assets: None,
+            fee_config: None,
+            lock_tiers: None,
+            disable_rebond: None,
+            position_nfts: None,
+            penalty_destination: None,
+            max_total_stake: None,
+            max_stake_per_address: None,
+            whitelist_until: None,
+            history_retention: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        bond_cw20(deps.as_mut(), 10_000, 0, 0, 1);
+        unbond(deps.as_mut(), 1_000, 0, 0, 1, &fee);
+        unbond(deps.as_mut(), 2_000, 0, 0, 51, &fee);
+
+        let claim = |deps: DepsMut, amount: Option<u128>| {
+            let msg = ExecuteMsg::Claim { amount: amount.map(Uint128::new) };
+            execute(deps, get_env(101), mock_info(USER1, &fee), msg)
+        };
+        let transfer = |recipient: &str, amount: u128| SubMsg::new(WasmMsg::Execute {
+            contract_addr: STAKING_TOKEN_ADDRESS.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount: Uint128::new(amount),
+            }).unwrap(),
+            funds: vec![],
+        });
+
+        // only the first claim is released, it can be claimed in parts
+        let res = claim(deps.as_mut(), Some(400)).unwrap();
+        assert_eq!(res.messages, vec![transfer(USER1, 400)]);
+        let res = claim(deps.as_mut(), None).unwrap();
+        assert_eq!(res.messages, vec![transfer(USER1, 600)]);
+        let err = claim(deps.as_mut(), None).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+
+        // half of the unbonding period left, so the loss is 5%
+        let simulate = |amount: Option<u128>, claim_ids: Option<Vec<u64>>| {
+            query_simulate_instant_claim(
+                deps.as_ref(), get_env(101), USER1.to_string(), amount.map(Uint128::new), claim_ids)
+        };
+        assert_eq!(simulate(None, None).unwrap(), SimulateInstantClaimResponse {
+            release: Uint128::new(1_900),
+            fee: Uint128::new(100),
+        });
+        assert_eq!(simulate(Some(1_000), Some(vec![2])).unwrap(), SimulateInstantClaimResponse {
+            release: Uint128::new(950),
+            fee: Uint128::new(50),
+        });
+        simulate(None, Some(vec![1])).unwrap_err();
+
+        let instant_claim = |amount: Option<u128>, claim_ids: Option<Vec<u64>>| ExecuteMsg::InstantClaim {
+            amount: amount.map(Uint128::new),
+            claim_ids,
+        };
+        let err = execute(deps.as_mut(), get_env(101), mock_info(USER1, &fee), instant_claim(None, Some(vec![7])))
+            .unwrap_err();
+        assert_eq!(err, ContractError::ClaimNotFound(7));
+
+        let res = execute(deps.as_mut(), get_env(101), mock_info(USER1, &fee), instant_claim(Some(1_000), Some(vec![2])))
+            .unwrap();
+        assert_eq!(res.messages, vec![transfer(USER1, 950), transfer(BURN_ADDRESS, 50)]);
+
+        let member = query_member(deps.as_ref(), get_env(101), USER1.to_string()).unwrap().member.unwrap();
+        assert_eq!(member.claims, vec![Claim {
+            id: 2,
+            amount: Uint128::new(1_000),
+            release_at: Expiration::AtTime(get_env(1).block.time.plus_seconds(900)),
+        }]);
+
+        // no loss once released
+        let res = execute(deps.as_mut(), get_env(151), mock_info(USER1, &fee), instant_claim(None, None)).unwrap();
+        assert_eq!(res.messages, vec![transfer(USER1, 1_000)]);
+    }
+
+    #[derive(Clone, Debug)]
+    enum Action {
+        Bond { user: usize, amount: u128, locked: bool },
+        Unbond { user: usize, amount: u128 },
+        Withdraw { user: usize },
+    }
+
+    fn execute_action(deps: DepsMut, env: Env, action: Action) -> Result<Response, ContractError> {
+        let fee = coins(1_000_000, "uusd");
+        let users = [USER1, USER2, USER3];
+        match action {
+            Action::Bond { user, amount, locked } => {
+                let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                    sender: users[user].to_string(),
+                    amount: Uint128::new(amount),
+                    msg: to_binary(&ReceiveMsg::Bond {
+                        lock_duration: if locked { Some(30 * DAY) } else { None },
+                    }).unwrap(),
+                });
+                execute(deps, env, mock_info(STAKING_TOKEN_ADDRESS, &[]), msg)
+            }
+            Action::Unbond { user, amount } => {
+                let msg = ExecuteMsg::Unbond { tokens: Uint128::new(amount) };
+                execute(deps, env, mock_info(users[user], &fee), msg)
+            }
+            Action::Withdraw { user } => {
+                let msg = ExecuteMsg::Withdraw { assets: None };
+                execute(deps, env, mock_info(users[user], &fee), msg)
+            }
+        }
+    }
+
+    fn action() -> impl Strategy<Value = Action> {
+        prop_oneof![
+            (0..3usize, 1..1_000_000_000u128, any::<bool>())
+                .prop_map(|(user, amount, locked)| Action::Bond { user, amount, locked }),
+            (0..3usize, 1..1_000_000_000u128).prop_map(|(user, amount)| Action::Unbond { user, amount }),
+            (0..3usize).prop_map(|user| Action::Withdraw { user }),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        // sum of member rewards never exceeds the amount distributed by the schedules
+        #[test]
+        fn rewards_never_exceed_schedules(
+            actions in prop::collection::vec((action(), 0..2 * DAY), 1..40),
+        ) {
+            let mut deps = mock_dependencies(&[]);
+            default_instantiate(deps.as_mut(), mock_env());
+            let schedules = terraland_reward(&mock_env()).distribution_schedule;
+            let users = [USER1, USER2, USER3];
+            let mut env = mock_env();
+
+            for (action, elapsed) in actions {
+                env.block.height += 1;
+                env.block.time = env.block.time.plus_seconds(elapsed);
+
+                // failing actions are fine, only the accounting matters
+                let _ = execute_action(deps.as_mut(), env.clone(), action);
+
+                let mut accrued = Uint128::zero();
+                for user in users.iter() {
+                    let member = query_member(deps.as_ref(), env.clone(), user.to_string()).unwrap().member;
+                    if let Some(member) = member {
+                        let reward = &member.rewards[0];
+                        prop_assert!(reward.withdrawn <= reward.reward);
+                        accrued += reward.reward;
+                    }
+                }
+                let distributed = distributed_between(&schedules, 0, env.block.time.seconds());
+                prop_assert!(accrued <= distributed, "accrued {} distributed {}", accrued, distributed);
+            }
+        }
+
+        // emergency withdrawals return exactly the total stake and the outstanding claims
+        #[test]
+        fn emergency_withdraw_returns_principal(
+            actions in prop::collection::vec((action(), 0..2 * DAY), 1..40),
+        ) {
+            let mut deps = mock_dependencies(&[]);
+            default_instantiate(deps.as_mut(), mock_env());
+            let users = [USER1, USER2, USER3];
+            let mut env = mock_env();
+
+            for (action, elapsed) in actions {
+                env.block.height += 1;
+                env.block.time = env.block.time.plus_seconds(elapsed);
+                let _ = execute_action(deps.as_mut(), env.clone(), action);
+            }
+
+            let claims: Uint128 = users.iter()
+                .flat_map(|user| CLAIMS.may_load(deps.as_ref().storage, &Addr::unchecked(*user)).unwrap())
+                .flatten()
+                .map(|c| c.amount)
+                .sum();
+            prop_assert_eq!(TOTAL_CLAIMS.may_load(deps.as_ref().storage).unwrap().unwrap_or_default(), claims);
+            let principal = query_state(deps.as_ref()).unwrap().total_stake + claims;
+
+            execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), ExecuteMsg::EnableEmergency {}).unwrap();
+            let mut paid_out = Uint128::zero();
+            for user in users.iter() {
+                if let Ok(res) = execute(deps.as_mut(), env.clone(), mock_info(user, &[]), ExecuteMsg::EmergencyWithdraw {}) {
+                    paid_out += Uint128::from_str(&res.attributes[1].value).unwrap();
+                }
+            }
+
+            prop_assert_eq!(paid_out, principal);
+            let state = query_state(deps.as_ref()).unwrap();
+            prop_assert_eq!(state.total_stake, Uint128::zero());
+            prop_assert_eq!(state.total_weight, Uint128::zero());
+        }
+    }
 }
@@ -1,5 +1,8 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use cw0::PaymentError;
+use cw_controllers::HookError;
+use ownership::OwnershipError;
+use roles::RolesError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -10,9 +13,57 @@ pub enum ContractError {
     #[error("{0}")]
     Payment(#[from] PaymentError),
 
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
+
+    #[error("{0}")]
+    Roles(#[from] RolesError),
+
+    #[error("{0}")]
+    Hook(#[from] HookError),
+
     #[error("No claims that can be released currently")]
     NothingToClaim {},
 
+    #[error("Claim {0} not found")]
+    ClaimNotFound(u64),
+
+    #[error("Position {0} not found")]
+    PositionNotFound(String),
+
+    #[error("Approval has expired")]
+    ApprovalExpired {},
+
+    #[error("Total stake cap exceeded, {remaining} tokens can be bonded")]
+    TotalStakeCapExceeded { remaining: Uint128 },
+
+    #[error("Stake cap per address exceeded, {remaining} tokens can be bonded")]
+    AddressStakeCapExceeded { remaining: Uint128 },
+
+    #[error("Address is not whitelisted to bond yet")]
+    NotWhitelisted {},
+
+    #[error("Slash percentage has to be above 0 and not above 1")]
+    InvalidSlashPercentage {},
+
+    #[error("Nothing to slash")]
+    NothingToSlash {},
+
+    #[error("Penalty can be redistributed only when the staking token is a reward asset")]
+    CannotRedistribute {},
+
+    #[error("Contract is in emergency mode")]
+    EmergencyActive {},
+
+    #[error("Contract is not in emergency mode")]
+    EmergencyNotActive {},
+
+    #[error("Rebond is disabled")]
+    RebondDisabled {},
+
+    #[error("Cannot rebond more than {available} unbonded tokens")]
+    RebondExceedsClaims { available: Uint128 },
+
     #[error("Must send valid address to stake")]
     InvalidToken(String),
 
@@ -31,6 +82,52 @@ pub enum ContractError {
     #[error("No reward to release")]
     NothingToWithdraw {},
 
+    #[error("Unknown reward asset {0}")]
+    UnknownRewardAsset(String),
+
+    #[error("Reward asset {0} is listed more than once")]
+    DuplicateRewardAsset(String),
+
+    #[error("No lock tier with duration {0}")]
+    LockTierNotFound(u64),
+
+    #[error("Staking token cannot change while tokens are staked or claimed")]
+    StakingTokenInUse {},
+
+    #[error("Lock tier multiplier cannot be lower than 1")]
+    InvalidLockTier {},
+
+    #[error("Lock tier duration cannot be zero")]
+    ZeroLockDuration {},
+
+    #[error("Lock tier with duration {0} is listed more than once")]
+    DuplicateLockTier(u64),
+
+    #[error("Cannot unbond locked tokens")]
+    StakeLocked {},
+
+    #[error("Staking token is not a reward asset")]
+    CannotCompound {},
+
+    #[error("No reward to compound")]
+    NothingToCompound {},
+
+    #[error("Invalid schedule: {reason}")]
+    InvalidSchedule { reason: String },
+
+    #[error("Schedule not found")]
+    ScheduleNotFound {},
+
+    #[error("Distribution schedule of {0} can be changed only with schedule messages")]
+    ScheduleChanged(String),
+
+    #[error("Contract holds {balance} {asset}, {required} needed for the remaining schedule")]
+    InsufficientRewardFunds {
+        asset: String,
+        balance: Uint128,
+        required: Uint128,
+    },
+
     #[error("Cannot migrate from different contract type: {previous_contract}")]
     CannotMigrate { previous_contract: String },
 
@@ -1,61 +1,178 @@
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Binary, CosmosMsg, Decimal, Decimal256, StdResult, to_binary, Uint128, WasmMsg};
+use cw0::Expiration;
 use cw20::Cw20ReceiveMsg;
-use cw_controllers::Claim;
-pub use cw_controllers::ClaimsResponse;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use roles::Role;
 
-use crate::state::{FeeConfig, Schedule};
+use crate::state::{AssetInfo, Claim, HistoryEntry, PenaltyDestination, SlashRecord, FeeConfig, LockTier, RewardAsset, Schedule};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub owner: String,
-    pub staking_token: String,
-    pub terraland_token: String,
+    pub staking_token: AssetInfo,
     pub unbonding_period: u64,
     pub burn_address: String,
     pub instant_claim_percentage_loss: u64,
-    pub distribution_schedule: Vec<Schedule>,
+    pub reward_assets: Vec<RewardAsset>,
     pub fee_config: Vec<FeeConfig>,
+    #[serde(default)]
+    pub lock_tiers: Vec<LockTier>,
+    #[serde(default)]
+    pub disable_rebond: bool,
+    #[serde(default)]
+    pub position_nfts: bool,
+    #[serde(default)]
+    pub penalty_destination: PenaltyDestination,
+    #[serde(default)]
+    pub max_total_stake: Option<Uint128>,
+    #[serde(default)]
+    pub max_stake_per_address: Option<Uint128>,
+    #[serde(default)]
+    pub whitelist_until: u64,
+    #[serde(default)]
+    pub whitelist: Vec<String>,
+    #[serde(default)]
+    pub history_retention: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct NewConfig {
-    pub owner: Option<String>,
-    pub staking_token: Option<String>,
+    pub staking_token: Option<AssetInfo>,
     pub unbonding_period: Option<u64>,
     pub burn_address: Option<String>,
     pub instant_claim_percentage_loss: Option<u64>,
-    pub distribution_schedule: Option<Vec<Schedule>>,
+    /// Adds or removes reward assets, schedules of the existing ones are changed with
+    /// the schedule messages. Removed assets stop accruing, rewards accrued before stay
+    /// withdrawable.
+    pub reward_assets: Option<Vec<RewardAsset>>,
     pub fee_config: Option<Vec<FeeConfig>>,
+    pub lock_tiers: Option<Vec<LockTier>>,
+    pub disable_rebond: Option<bool>,
+    pub position_nfts: Option<bool>,
+    pub penalty_destination: Option<PenaltyDestination>,
+    /// Zero lifts the cap
+    pub max_total_stake: Option<Uint128>,
+    /// Zero lifts the cap
+    pub max_stake_per_address: Option<Uint128>,
+    pub whitelist_until: Option<u64>,
+    /// Zero keeps the member history forever
+    pub history_retention: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     /// Update config parameters
     UpdateConfig ( NewConfig ),
+    /// Only admin. Appends a schedule to the reward asset, it cannot start before the
+    /// previous one ends or in the past
+    AddSchedule { asset: String, schedule: Schedule },
+    /// Only admin. Makes the last schedule of the reward asset end later and distribute
+    /// amount more tokens. Running schedule is split at the current time.
+    ExtendSchedule {
+        asset: String,
+        end_time: u64,
+        amount: Uint128,
+    },
+    /// Only admin. Removes a schedule of the reward asset which has not started yet
+    CancelFutureSchedule { asset: String, start_time: u64 },
+    /// Only owner. Proposes a new owner, who has to accept the ownership before it expires.
+    /// The current owner keeps control until then.
+    ProposeOwner {
+        new_owner: String,
+        expires: Option<Expiration>,
+    },
+    /// Only pending owner. Takes over the ownership.
+    AcceptOwnership {},
+    /// Only owner or pending owner. Drops the ownership proposal.
+    CancelOwnershipProposal {},
+    /// Only owner. Leaves the contract without an owner, forever.
+    RenounceOwnership {},
+    /// Only admin. Gives the role to the address.
+    GrantRole { role: Role, address: String },
+    /// Only admin. Takes the role from the address.
+    RevokeRole { role: Role, address: String },
+    /// Only admin. Adds and removes addresses allowed to bond before `whitelist_until`
+    UpdateWhitelist { add: Vec<String>, remove: Vec<String> },
+    /// Only admin. Notifies the contract about cw4 weight changes with `MemberChangedHookMsg`
+    AddHook { addr: String },
+    /// Only admin. Stops notifying the contract.
+    RemoveHook { addr: String },
     /// Unbond will start the unbonding process for the given number of tokens.
     /// The sender immediately loses weight from these tokens, and can claim them
     /// back to his wallet after `unbonding_period`
     Unbond { tokens: Uint128 },
+    /// Bonds back amount of unbonded tokens, taken from the newest claims first
+    Rebond { amount: Uint128 },
     /// Claim is used to claim your native tokens that you previously "unbonded"
-    /// after the contract-defined waiting period (eg. 1 week)
-    Claim {},
-    /// Claim without waiting period, but with percentage fee
-    InstantClaim {},
-    /// Withdraw reward
-    Withdraw {},
+    /// after the contract-defined waiting period (eg. 1 week). Releases up to amount
+    /// tokens, all released ones if not set.
+    Claim { amount: Option<Uint128> },
+    /// Claim without waiting period, but with percentage fee decreasing with the time left
+    /// until the release. Takes up to amount tokens from the given claims, oldest first,
+    /// all of them if not set.
+    InstantClaim {
+        amount: Option<Uint128>,
+        claim_ids: Option<Vec<u64>>,
+    },
+    /// Withdraw reward in the given assets, all of them if not set
+    Withdraw { assets: Option<Vec<String>> },
+    /// Bond reward in staking tokens, if they are a reward asset
+    Compound {},
+    /// Let `Harvest` compound the sender's reward
+    SetAutoCompound { enabled: bool },
+    /// Compound reward of up to limit members with auto compound enabled,
+    /// continuing after the members compounded by the previous call
+    Harvest { limit: Option<u32> },
 
     /// This accepts a properly-encoded ReceiveMsg from a cw20 contract
     Receive(Cw20ReceiveMsg),
+    /// Bonds the attached native staking coins, works like the cw20 `ReceiveMsg::Bond`
+    Bond { lock_duration: Option<u64> },
+
+    /// Only position holder. Starts unbonding tokens of the position NFT like `Unbond`.
+    /// The position is burnt once nothing is left and its rewards are sent to the holder.
+    UnbondPosition { token_id: String, tokens: Uint128 },
+    /// cw721. Moves the position NFT to the recipient, rewards accrued until now are sent
+    /// to the previous holder
+    TransferNft { recipient: String, token_id: String },
+    /// cw721. Moves the position NFT to the contract and calls it with `Cw721ReceiveMsg`
+    SendNft {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    },
+    /// cw721. Allows the spender to transfer the position NFT until the approval expires
+    Approve {
+        spender: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+    /// cw721. Removes the approval of the spender
+    Revoke { spender: String, token_id: String },
+
+    /// Only owner or oracle. Takes the percentage (0 to 1) of the member stake, held positions
+    /// and claims. Slashed tokens go to the penalty destination.
+    Slash {
+        address: String,
+        percentage: Decimal,
+        reason: String,
+    },
+    /// Only pauser. Turns the emergency mode on, it cannot be turned off. Bonding and
+    /// rewards stop, members take their tokens back with `EmergencyWithdraw`.
+    EnableEmergency {},
+    /// Only in emergency mode. Returns the whole stake of the sender, including the held
+    /// positions, and all claims, released or not. Rewards are forfeited.
+    EmergencyWithdraw {},
 
-    /// Withdraw ust from smart contract by owner
+    /// Withdraw ust from smart contract by treasurer
     UstWithdraw {
         recipient: String,
         amount: Uint128,
     },
-    /// Withdraw tokens from smart contract by owner
+    /// Withdraw tokens from smart contract by treasurer
     TokenWithdraw {
         token: String,
         recipient: String,
@@ -68,8 +185,9 @@ pub struct MigrateMsg {}
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ReceiveMsg {
-    /// Only valid cw20 message is to bond the tokens
-    Bond {},
+    /// Only valid cw20 message is to bond the tokens. Tokens bonded with the duration
+    /// of a lock tier cannot be unbonded until the lock ends.
+    Bond { lock_duration: Option<u64> },
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -78,26 +196,187 @@ pub enum QueryMsg {
     /// Return config
     Config {},
 
+    /// Return proposed owner and when the proposal expires
+    PendingOwner {},
+
+    /// Return addresses holding the given role
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
     /// Return state
     State {},
 
-    /// Return staker info
-    Member { address: String },
+    /// Return staker info, queried as `Member` before the cw4 queries
+    Staker { address: String },
 
-    /// Return stakers
+    /// Return stakers, queried as `ListMembers` before the cw4 queries
+    ListStakers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Return cw4 weight of the member, equal to the stake, at the beginning of the block
+    /// with the given height or current one if not set. Returns cw4::MemberResponse.
+    Member {
+        addr: String,
+        at_height: Option<u64>,
+    },
+
+    /// Return cw4 weights of the members. Returns cw4::MemberListResponse.
     ListMembers {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+
+    /// Return total cw4 weight, equal to the total stake. Returns cw4::TotalWeightResponse.
+    TotalWeight {},
+
+    /// Return contracts notified about cw4 weight changes. Returns cw_controllers::HooksResponse.
+    Hooks {},
+
+    /// Return tokens distributed in the second starting at the time, as accrued by the
+    /// members, current time if not set
+    RewardRate { at_time: Option<u64> },
+
+    /// Return current reward per staked token per year, ignoring prices
+    Apr {},
+
+    /// Return staker rewards at the given time, assuming no stake changes until then
+    ProjectedReward { address: String, until: u64 },
+
+    /// Return rewards not distributed yet
+    RemainingRewards {},
+
+    /// Return tokens released and burnt by the instant claim with the given parameters
+    SimulateInstantClaim {
+        address: String,
+        amount: Option<Uint128>,
+        claim_ids: Option<Vec<u64>>,
+    },
+
+    /// Return stake caps and the room left under them
+    Capacity {},
+
+    /// Return addresses allowed to bond before the whitelist window ends
+    Whitelist {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Return history of the member, oldest first
+    MemberHistory {
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Return slashes of the member, oldest first
+    Slashes {
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// cw721. Return holder of the position NFT and its approvals
+    OwnerOf {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+
+    /// cw721. Return stake, lock end and rewards of the position NFT
+    NftInfo { token_id: String },
+
+    /// cw721. Return both the holder and the info of the position NFT
+    AllNftInfo {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+
+    /// cw721. Return position NFTs held by the owner
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// cw721. Return all position NFTs
+    AllTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// cw721. Return number of position NFTs
+    NumTokens {},
+
+    /// cw721. Return name and symbol of the position NFTs
+    ContractInfo {},
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AssetAmount {
+    pub asset: String,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RewardRateResponse {
+    pub rates: Vec<AssetAmount>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AprResponse {
+    pub aprs: Vec<AssetApr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AssetApr {
+    pub asset: String,
+    pub apr: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ProjectedRewardResponse {
+    pub rewards: Vec<RewardResponseItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SimulateInstantClaimResponse {
+    pub release: Uint128,
+    pub fee: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RemainingRewardsResponse {
+    pub remaining: Vec<AssetAmount>,
 }
 
 #[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct MemberResponseItem {
     pub stake: Uint128,
+    pub weight: Uint128,
+    pub rewards: Vec<RewardResponseItem>,
+    pub positions: Vec<PositionResponseItem>,
+    pub claims: Vec<Claim>,
+    pub auto_compound: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PositionResponseItem {
+    pub amount: Uint128,
+    pub multiplier: Decimal,
+    pub lock_end: u64,
+    pub weight: Uint128,
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RewardResponseItem {
+    pub asset: String,
     pub reward: Uint128,
-    pub reward_index: Decimal,
+    pub reward_index: Decimal256,
     pub withdrawn: Uint128,
-    pub claims: Vec<Claim>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -115,3 +394,104 @@ pub struct MemberResponse {
 pub struct MemberListResponse {
     pub members: Vec<MemberListResponseItem>,
 }
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CapacityResponse {
+    pub total_stake: Uint128,
+    pub max_total_stake: Option<Uint128>,
+    /// Tokens which can still be bonded in total, not set without the cap
+    pub remaining_total_stake: Option<Uint128>,
+    pub max_stake_per_address: Option<Uint128>,
+    /// Only whitelisted addresses can bond until then
+    pub whitelist_until: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct WhitelistResponse {
+    pub addresses: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SlashesResponse {
+    pub slashes: Vec<SlashRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MemberHistoryResponse {
+    pub entries: Vec<HistoryEntry>,
+}
+
+/// Sent to the contract receiving a position NFT with `SendNft`, as in cw721
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Cw721ReceiveMsg {
+    pub sender: String,
+    pub token_id: String,
+    pub msg: Binary,
+}
+
+impl Cw721ReceiveMsg {
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        let msg = to_binary(&ReceiverExecuteMsg::ReceiveNft(self))?;
+        Ok(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        }.into())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+enum ReceiverExecuteMsg {
+    ReceiveNft(Cw721ReceiveMsg),
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct NftApproval {
+    pub spender: String,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OwnerOfResponse {
+    pub owner: String,
+    pub approvals: Vec<NftApproval>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct NftInfoResponse {
+    pub token_uri: Option<String>,
+    pub extension: PositionNftExtension,
+}
+
+/// Staking data of the position NFT
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PositionNftExtension {
+    pub stake: Uint128,
+    pub weight: Uint128,
+    /// Time in seconds the position can be unbonded after, if still locked
+    pub lock_end: Option<u64>,
+    pub rewards: Vec<RewardResponseItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AllNftInfoResponse {
+    pub access: OwnerOfResponse,
+    pub info: NftInfoResponse,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokensResponse {
+    pub tokens: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct NumTokensResponse {
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ContractInfoResponse {
+    pub name: String,
+    pub symbol: String,
+}
@@ -1,19 +1,105 @@
-use cosmwasm_std::{Addr, Decimal, Uint128};
-use cw_controllers::Claims;
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Decimal, Decimal256, Empty, Uint128};
+use cw0::Expiration;
+use cw_controllers::Hooks;
+use cw_storage_plus::{Item, Map, SnapshotMap, Strategy, U64Key};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct Config {
-    pub owner: Addr,
-    pub staking_token: Addr,
-    pub terraland_token: Addr,
+    /// None once the ownership is renounced
+    pub owner: Option<Addr>,
+    /// cw20 token bonded with `Receive` or native coin bonded with `Bond`
+    pub staking_token: AssetInfo,
     pub unbonding_period: u64,
     pub burn_address: Addr,
+    /// Percentage burnt by the instant claim of just unbonded tokens. It decreases linearly
+    /// with the time left until the release.
     pub instant_claim_percentage_loss: u64,
-    pub distribution_schedule: Vec<Schedule>,
+    pub reward_assets: Vec<RewardAsset>,
     pub fee_config: Vec<FeeConfig>,
+    #[serde(default)]
+    pub lock_tiers: Vec<LockTier>,
+    /// Unbonded tokens cannot be bonded back with `Rebond`
+    #[serde(default)]
+    pub disable_rebond: bool,
+    /// Every bond mints a transferable position NFT instead of adding to the member stake
+    #[serde(default)]
+    pub position_nfts: bool,
+    /// Set by the owner for good when rewards cannot be computed anymore. Members can only
+    /// take their stake and claims back with `EmergencyWithdraw`.
+    #[serde(default)]
+    pub emergency: bool,
+    #[serde(default)]
+    pub penalty_destination: PenaltyDestination,
+    /// Total stake cannot grow above the cap with bonds
+    #[serde(default)]
+    pub max_total_stake: Option<Uint128>,
+    /// Stake of a single address, including the held positions, cannot grow above the cap
+    /// with bonds
+    #[serde(default)]
+    pub max_stake_per_address: Option<Uint128>,
+    /// Until then (time in seconds) only whitelisted addresses can bond
+    #[serde(default)]
+    pub whitelist_until: u64,
+    /// Member history entries older than that (in seconds) are pruned when new ones are
+    /// recorded, kept forever if not set
+    #[serde(default)]
+    pub history_retention: Option<u64>,
+    /// Assets removed from the reward assets. They do not accrue anymore, but rewards accrued
+    /// before stay withdrawable.
+    #[serde(default)]
+    pub retired_assets: Vec<AssetInfo>,
+}
+
+/// Where penalties in staking tokens go, the instant claim fee and slashed tokens
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PenaltyDestination {
+    /// Sent to the burn address
+    Burn {},
+    /// Sent to the treasury
+    Treasury { address: String },
+    /// Distributed to the remaining stakers as a reward in staking tokens, which have to be
+    /// a reward asset
+    Redistribute {},
+}
+
+impl Default for PenaltyDestination {
+    fn default() -> Self {
+        PenaltyDestination::Burn {}
+    }
+}
+
+/// Bonds locked for the duration (in seconds) earn rewards with the weight multiplied
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LockTier {
+    pub duration: u64,
+    pub multiplier: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Token { contract_addr: String },
+    NativeToken { denom: String },
+}
+
+impl AssetInfo {
+    /// Contract address of the token or denom of the native coin
+    pub fn id(&self) -> &str {
+        match self {
+            AssetInfo::Token { contract_addr } => contract_addr,
+            AssetInfo::NativeToken { denom } => denom,
+        }
+    }
+}
+
+/// Reward distributed to stakers according to its own schedule
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RewardAsset {
+    pub info: AssetInfo,
+    pub distribution_schedule: Vec<Schedule>,
 }
 
 #[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -32,21 +118,259 @@ pub struct FeeConfig {
 
 #[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct MemberInfo {
+    /// All bonded tokens, including the locked ones
     pub stake: Uint128,
+    pub rewards: Vec<MemberReward>,
+    #[serde(default)]
+    pub positions: Vec<LockedPosition>,
+}
+
+impl MemberInfo {
+    pub fn locked_stake(&self) -> Uint128 {
+        self.positions.iter().map(|p| p.amount).sum()
+    }
+
+    /// Stake the rewards are distributed by, locked tokens count with the tier multiplier
+    pub fn weight(&self) -> Uint128 {
+        let locked_weight: Uint128 = self.positions.iter().map(|p| p.weight()).sum();
+        self.stake - self.locked_stake() + locked_weight
+    }
+
+    /// Positions past the lock end become a regular stake. Returns the released positions.
+    pub fn release_expired_positions(&mut self, time: u64) -> Vec<LockedPosition> {
+        let (expired, locked) = self.positions.drain(..).partition(|p| p.lock_end <= time);
+        self.positions = locked;
+        expired
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LockedPosition {
+    pub amount: Uint128,
+    pub multiplier: Decimal,
+    /// Time in seconds the tokens can be unbonded after
+    pub lock_end: u64,
+}
+
+impl LockedPosition {
+    pub fn weight(&self) -> Uint128 {
+        self.amount * self.multiplier
+    }
+
+    /// Weight above the locked tokens, it counts until the lock end
+    pub fn extra_weight(&self) -> Uint128 {
+        self.weight().saturating_sub(self.amount)
+    }
+}
+
+/// Locked positions ending at the same time
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LockEnd {
+    /// Extra weight of the positions, removed from the total weight at the lock end
+    pub extra_weight: Uint128,
+    /// Global reward indexes at the lock end, set once it is passed
+    pub reward_index: Option<Vec<RewardIndex>>,
+}
+
+/// Reward of a member in a single asset
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MemberReward {
+    pub asset: String,
+    /// Total reward accrued so far, including the withdrawn part
     pub pending_reward: Uint128,
-    pub reward_index: Decimal,
+    pub reward_index: Decimal256,
     pub withdrawn: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct State {
     pub total_stake: Uint128,
+    /// Sum of member weights, equal to the total stake when nothing is locked
+    #[serde(default)]
+    pub total_weight: Uint128,
     pub last_updated: u64,
-    pub global_reward_index: Decimal,
+    pub global_reward_index: Vec<RewardIndex>,
     pub num_of_members: u64,
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RewardIndex {
+    pub asset: String,
+    pub index: Decimal256,
+    /// Distributed amount too small to increase the index yet, in 10^-18 tokens.
+    /// It is carried to the next update, so no reward is lost to rounding.
+    #[serde(default)]
+    pub remainder: Uint128,
+    /// Distributed amount not withdrawn yet. It includes the rounding kept by the index,
+    /// so it is never below the rewards owed to the members.
+    #[serde(default)]
+    pub unwithdrawn: Uint128,
+}
+
+/// Unbonded tokens, which can be claimed after the release
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Claim {
+    /// Claims created before the ids were introduced get them in the migration
+    #[serde(default)]
+    pub id: u64,
+    pub amount: Uint128,
+    pub release_at: Expiration,
+}
+
+/// Bond represented by a cw721 token. The holder gets its rewards and can unbond it.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakingPosition {
+    pub owner: Addr,
+    /// Addresses allowed to transfer the position on behalf of the owner
+    pub approvals: Vec<PositionApproval>,
+    /// Stake, lock and reward index of the position
+    pub info: MemberInfo,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PositionApproval {
+    pub spender: Addr,
+    pub expires: Expiration,
+}
+
+/// Stake and claims taken from the member for platform abuse
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SlashRecord {
+    pub id: u64,
+    pub percentage: Decimal,
+    pub reason: String,
+    /// Tokens taken from the stake, positions and claims together
+    pub amount: Uint128,
+    pub time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryAction {
+    Bond,
+    Rebond,
+    Unbond,
+    Claim,
+    InstantClaim,
+    /// Part of the instant claim taken as the penalty
+    InstantClaimFee,
+    /// Reward withdrawn, or paid out with the transfer or burn of a position
+    Withdraw,
+    /// Reward bonded by `Compound` or `Harvest`
+    Compound,
+    Slash,
+    EmergencyWithdraw,
+}
+
+/// Entry of the member history kept for reporting
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub action: HistoryAction,
+    /// Staking token or reward asset id
+    pub asset: String,
+    pub amount: Uint128,
+    pub time: u64,
+    pub height: u64,
+}
+
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const MEMBERS: Map<&Addr, MemberInfo> = Map::new("members");
 pub const STATE: Item<State> = Item::new("state");
-pub const CLAIMS: Claims = Claims::new("claims");
+/// Claims of every member, oldest first. The key is shared with cw-controllers claims
+/// used before.
+pub const CLAIMS: Map<&Addr, Vec<Claim>> = Map::new("claims");
+/// Id of the last claim
+pub const CLAIM_SEQ: Item<u64> = Item::new("claim_seq");
+/// Tokens in the claims of all members
+pub const TOTAL_CLAIMS: Item<Uint128> = Item::new("total_claims");
+/// Position NFTs by the token id
+pub const POSITIONS: Map<&str, StakingPosition> = Map::new("positions");
+/// Token ids of the positions held by the owner
+pub const OWNER_POSITIONS: Map<(&Addr, &str), Empty> = Map::new("owner_positions");
+/// Id of the last minted position
+pub const POSITION_SEQ: Item<u64> = Item::new("position_seq");
+/// Number of positions not burnt yet
+pub const NUM_POSITIONS: Item<u64> = Item::new("num_positions");
+/// Addresses allowed to bond before the whitelist window ends
+pub const WHITELIST: Map<&Addr, Empty> = Map::new("whitelist");
+/// Slashes of every member, by id
+pub const SLASHES: Map<(&Addr, U64Key), SlashRecord> = Map::new("slashes");
+/// Id of the last slash
+pub const SLASH_SEQ: Item<u64> = Item::new("slash_seq");
+/// Activity of every member, by id
+pub const MEMBER_HISTORY: Map<(&Addr, U64Key), HistoryEntry> = Map::new("member_history");
+/// Id of the last history entry
+pub const HISTORY_SEQ: Item<u64> = Item::new("history_seq");
+/// Locked positions by the lock end. Passed lock ends are kept for the members releasing
+/// their positions later.
+pub const LOCK_ENDS: Map<U64Key, LockEnd> = Map::new("lock_ends");
+/// Members whose reward is compounded by `Harvest`
+pub const AUTO_COMPOUND: Map<&Addr, bool> = Map::new("auto_compound");
+/// Last member compounded by `Harvest`, the next one starts after it
+pub const HARVEST_CURSOR: Item<Addr> = Item::new("harvest_cursor");
+/// cw4 weights of the members, equal to their stake, with the history of changes
+pub const MEMBER_WEIGHTS: SnapshotMap<&Addr, u64> = SnapshotMap::new(
+    "member_weights",
+    "member_weights__checkpoints",
+    "member_weights__changelog",
+    Strategy::EveryBlock,
+);
+/// Contracts notified about cw4 weight changes
+pub const HOOKS: Hooks = Hooks::new("cw4_hooks");
+
+/// Storage layout of version 0.1.0 with a single reward in terraland tokens,
+/// converted by the migration
+pub mod legacy {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+    pub struct Config {
+        pub owner: Option<Addr>,
+        pub staking_token: Addr,
+        pub terraland_token: Addr,
+        pub unbonding_period: u64,
+        pub burn_address: Addr,
+        pub instant_claim_percentage_loss: u64,
+        pub distribution_schedule: Vec<Schedule>,
+        pub fee_config: Vec<FeeConfig>,
+    }
+
+    #[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+    pub struct MemberInfo {
+        pub stake: Uint128,
+        pub pending_reward: Uint128,
+        pub reward_index: Decimal,
+        pub withdrawn: Uint128,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+    pub struct State {
+        pub total_stake: Uint128,
+        pub last_updated: u64,
+        pub global_reward_index: Decimal,
+        pub num_of_members: u64,
+    }
+
+    pub const CONFIG: Item<Config> = Item::new("config");
+
+    /// Config with reward assets, before the staking token could be a native coin
+    #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+    pub struct Cw20StakingConfig {
+        pub owner: Option<Addr>,
+        pub staking_token: Addr,
+        pub unbonding_period: u64,
+        pub burn_address: Addr,
+        pub instant_claim_percentage_loss: u64,
+        pub reward_assets: Vec<RewardAsset>,
+        pub fee_config: Vec<FeeConfig>,
+        #[serde(default)]
+        pub lock_tiers: Vec<LockTier>,
+        #[serde(default)]
+        pub disable_rebond: bool,
+    }
+
+    pub const CW20_STAKING_CONFIG: Item<Cw20StakingConfig> = Item::new("config");
+    pub const MEMBERS: Map<&Addr, MemberInfo> = Map::new("members");
+    pub const STATE: Item<State> = Item::new("state");
+}
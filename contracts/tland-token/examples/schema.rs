@@ -5,10 +5,16 @@ use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use cw20::{
     AllAccountsResponse, AllAllowancesResponse, AllowanceResponse, BalanceResponse,
-    TokenInfoResponse,
+    MinterResponse, TokenInfoResponse,
+};
+use tland_token::msg::{
+    BlockedAccountsResponse, DelegationResponse, ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg,
+    NonceResponse, PauseInfoResponse, PermitPayload,
+    SimulateTransferResponse, TotalSupplyResponse, VotingPowerResponse,
 };
-use tland_token::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg};
 use tland_token::state::Config as ConfigResponse;
+use ownership::PendingOwnerResponse;
+use roles::RoleMembersResponse;
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -24,7 +30,18 @@ fn main() {
     export_schema(&schema_for!(AllowanceResponse), &out_dir);
     export_schema(&schema_for!(BalanceResponse), &out_dir);
     export_schema(&schema_for!(TokenInfoResponse), &out_dir);
+    export_schema(&schema_for!(MinterResponse), &out_dir);
+    export_schema(&schema_for!(TotalSupplyResponse), &out_dir);
     export_schema(&schema_for!(AllAllowancesResponse), &out_dir);
     export_schema(&schema_for!(AllAccountsResponse), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(PendingOwnerResponse), &out_dir);
+    export_schema(&schema_for!(RoleMembersResponse), &out_dir);
+    export_schema(&schema_for!(PauseInfoResponse), &out_dir);
+    export_schema(&schema_for!(SimulateTransferResponse), &out_dir);
+    export_schema(&schema_for!(BlockedAccountsResponse), &out_dir);
+    export_schema(&schema_for!(VotingPowerResponse), &out_dir);
+    export_schema(&schema_for!(DelegationResponse), &out_dir);
+    export_schema(&schema_for!(NonceResponse), &out_dir);
+    export_schema(&schema_for!(PermitPayload), &out_dir);
 }
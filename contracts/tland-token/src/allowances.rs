@@ -4,8 +4,9 @@ use cosmwasm_std::{
 };
 use cw20::{AllowanceResponse, Cw20ReceiveMsg, Expiration};
 
+use crate::contract::{add_tax_attributes, assert_not_frozen, assert_not_paused, burn_tokens, transfer_tokens};
 use crate::error::ContractError;
-use crate::state::{ALLOWANCES, BALANCES, CONFIG, TOKEN_INFO};
+use crate::state::{ALLOWANCES, CONFIG};
 
 pub fn execute_increase_allowance(
     deps: DepsMut,
@@ -19,6 +20,7 @@ pub fn execute_increase_allowance(
     if spender_addr == info.sender {
         return Err(ContractError::CannotSetOwnAccount {});
     }
+    assert_not_frozen(deps.storage, &[&info.sender, &spender_addr])?;
 
     ALLOWANCES.update(
         deps.storage,
@@ -116,24 +118,16 @@ pub fn execute_transfer_from(
     recipient: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, &info.sender)?;
+
     let rcpt_addr = deps.api.addr_validate(&recipient)?;
     let owner_addr = deps.api.addr_validate(&owner)?;
+    assert_not_frozen(deps.storage, &[&owner_addr, &info.sender, &rcpt_addr])?;
 
     // deduct allowance before doing anything else have enough allowance
     deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
-    BALANCES.update(
-        deps.storage,
-        &owner_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    let transfer = transfer_tokens(deps.storage, &owner_addr, &rcpt_addr, amount, env.block.height)?;
 
     let res = Response::new().add_attributes(vec![
         attr("action", "transfer_from"),
@@ -142,7 +136,7 @@ pub fn execute_transfer_from(
         attr("by", info.sender),
         attr("amount", amount),
     ]);
-    Ok(res)
+    Ok(add_tax_attributes(res, &transfer))
 }
 
 pub fn execute_burn_from(
@@ -155,28 +149,19 @@ pub fn execute_burn_from(
 ) -> Result<Response, ContractError> {
     // authorized owner
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if cfg.owner.as_ref() != Some(&info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
+    assert_not_paused(deps.storage, &info.sender)?;
+
     let owner_addr = deps.api.addr_validate(&owner)?;
+    assert_not_frozen(deps.storage, &[&owner_addr, &info.sender])?;
 
     // deduct allowance before doing anything else have enough allowance
     deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
-    // lower balance
-    BALANCES.update(
-        deps.storage,
-        &owner_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    // reduce total_supply
-    TOKEN_INFO.update(deps.storage, |mut meta| -> StdResult<_> {
-        meta.total_supply = meta.total_supply.checked_sub(amount)?;
-        Ok(meta)
-    })?;
+    burn_tokens(deps.storage, &owner_addr, amount, env.block.height)?;
 
     let res = Response::new().add_attributes(vec![
         attr("action", "burn_from"),
@@ -196,25 +181,17 @@ pub fn execute_send_from(
     amount: Uint128,
     msg: Binary,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, &info.sender)?;
+
     let rcpt_addr = deps.api.addr_validate(&contract)?;
     let owner_addr = deps.api.addr_validate(&owner)?;
+    assert_not_frozen(deps.storage, &[&owner_addr, &info.sender, &rcpt_addr])?;
 
     // deduct allowance before doing anything else have enough allowance
     deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
     // move the tokens to the contract
-    BALANCES.update(
-        deps.storage,
-        &owner_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    let transfer = transfer_tokens(deps.storage, &owner_addr, &rcpt_addr, amount, env.block.height)?;
 
     let attrs = vec![
         attr("action", "send_from"),
@@ -227,13 +204,13 @@ pub fn execute_send_from(
     // create a send message
     let msg = Cw20ReceiveMsg {
         sender: info.sender.into(),
-        amount,
+        amount: transfer.net,
         msg,
     }
         .into_cosmos_msg(contract)?;
 
     let res = Response::new().add_message(msg).add_attributes(attrs);
-    Ok(res)
+    Ok(add_tax_attributes(res, &transfer))
 }
 
 pub fn query_allowance(deps: Deps, owner: String, spender: String) -> StdResult<AllowanceResponse> {
@@ -275,6 +252,7 @@ mod tests {
                 address: addr.into(),
                 amount,
             }],
+            mint: None,
             marketing: None,
         };
         let info = mock_info("creator", &[]);
@@ -569,6 +547,213 @@ mod tests {
         assert_eq!(err, ContractError::Expired {});
     }
 
+    #[test]
+    fn transfer_from_respects_pause() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = String::from("addr0001");
+        let spender = String::from("addr0002");
+        let rcpt = String::from("addr0003");
+
+        let start = Uint128::new(999999);
+        do_instantiate(deps.as_mut(), &owner, start);
+
+        // provide an allowance
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(77777),
+            expires: None,
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // pause token transfers (owner of the tokens is also the contract owner)
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {}).unwrap();
+
+        let msg = ExecuteMsg::TransferFrom {
+            owner: owner.clone(),
+            recipient: rcpt.clone(),
+            amount: Uint128::new(44444),
+        };
+        let info = mock_info(spender.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+
+        let msg = ExecuteMsg::SendFrom {
+            owner: owner.clone(),
+            contract: rcpt.clone(),
+            amount: Uint128::new(44444),
+            msg: Binary::default(),
+        };
+        let info = mock_info(spender.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+
+        // allowance is untouched
+        let allowance = query_allowance(deps.as_ref(), owner.clone(), spender).unwrap();
+        assert_eq!(allowance.allowance, Uint128::new(77777));
+        assert_eq!(get_balance(deps.as_ref(), owner), start);
+        assert_eq!(get_balance(deps.as_ref(), rcpt), Uint128::zero());
+    }
+
+    #[test]
+    fn frozen_accounts_cannot_use_allowances() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = String::from("addr0001");
+        let spender = String::from("addr0002");
+        let rcpt = String::from("addr0003");
+
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(100000));
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(30000),
+            expires: None,
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // owner of the tokens is also the contract owner
+        let msg = ExecuteMsg::UpdateBlocklist {
+            add: vec![spender.clone()],
+            remove: vec![],
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let frozen = ContractError::AddressFrozen {
+            address: spender.clone(),
+        };
+
+        // frozen spender can neither get nor use an allowance
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(1000),
+            expires: None,
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, frozen);
+
+        let msg = ExecuteMsg::TransferFrom {
+            owner: owner.clone(),
+            recipient: rcpt.clone(),
+            amount: Uint128::new(1000),
+        };
+        let info = mock_info(spender.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, frozen);
+
+        let msg = ExecuteMsg::SendFrom {
+            owner: owner.clone(),
+            contract: rcpt.clone(),
+            amount: Uint128::new(1000),
+            msg: Binary::default(),
+        };
+        let info = mock_info(spender.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, frozen);
+
+        // frozen recipient cannot receive
+        let msg = ExecuteMsg::UpdateBlocklist {
+            add: vec![rcpt.clone()],
+            remove: vec![spender.clone()],
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::TransferFrom {
+            owner: owner.clone(),
+            recipient: rcpt.clone(),
+            amount: Uint128::new(1000),
+        };
+        let info = mock_info(spender.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::AddressFrozen { address: rcpt.clone() });
+
+        let allowance = query_allowance(deps.as_ref(), owner.clone(), spender).unwrap();
+        assert_eq!(allowance.allowance, Uint128::new(30000));
+        assert_eq!(get_balance(deps.as_ref(), owner), Uint128::new(100000));
+    }
+
+    #[test]
+    fn transfer_from_with_tax() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = String::from("addr0001");
+        let spender = String::from("addr0002");
+        let rcpt = String::from("addr0003");
+        let contract = String::from("cool-dex");
+        let treasury = String::from("treasury");
+
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(100000));
+
+        // 1% tax, half of it is burned
+        let msg = ExecuteMsg::UpdateTransferTax {
+            rate: 100,
+            burn_ratio: 5000,
+            treasury: treasury.clone(),
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(30000),
+            expires: None,
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // allowance covers the gross amount
+        let msg = ExecuteMsg::TransferFrom {
+            owner: owner.clone(),
+            recipient: rcpt.clone(),
+            amount: Uint128::new(10000),
+        };
+        let info = mock_info(spender.as_ref(), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[5], attr("fee", "100"));
+        assert_eq!(get_balance(deps.as_ref(), &owner), Uint128::new(90000));
+        assert_eq!(get_balance(deps.as_ref(), &rcpt), Uint128::new(9900));
+        assert_eq!(get_balance(deps.as_ref(), &treasury), Uint128::new(50));
+
+        let send_msg = Binary::from(r#"{"some":123}"#.as_bytes());
+        let msg = ExecuteMsg::SendFrom {
+            owner: owner.clone(),
+            contract: contract.clone(),
+            amount: Uint128::new(20000),
+            msg: send_msg.clone(),
+        };
+        let info = mock_info(spender.as_ref(), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let binary_msg = Cw20ReceiveMsg {
+            sender: spender.clone(),
+            amount: Uint128::new(19800),
+            msg: send_msg,
+        }
+            .into_binary()
+            .unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract.clone(),
+                msg: binary_msg,
+                funds: vec![],
+            }))
+        );
+
+        assert_eq!(get_balance(deps.as_ref(), &owner), Uint128::new(70000));
+        assert_eq!(get_balance(deps.as_ref(), &contract), Uint128::new(19800));
+        assert_eq!(get_balance(deps.as_ref(), &treasury), Uint128::new(150));
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(99850)
+        );
+        let allowance = query_allowance(deps.as_ref(), owner, spender).unwrap();
+        assert_eq!(allowance.allowance, Uint128::zero());
+    }
+
     #[test]
     fn burn_from_respects_limits() {
         let mut deps = mock_dependencies(&[]);
@@ -1,21 +1,31 @@
-use cosmwasm_std::{BankMsg, Binary, coin, Coin, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, SubMsg, to_binary, Uint128};
+use cosmwasm_std::{Addr, Api, BankMsg, Binary, coin, Coin, Decimal, Deps, DepsMut, Env, Event, MessageInfo, Response, StdError, StdResult, Storage, SubMsg, to_binary, Uint128};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cw2::{get_contract_version, set_contract_version};
 use cw20::{
     BalanceResponse, Cw20Coin, Cw20ReceiveMsg, DownloadLogoResponse, EmbeddedLogo, Logo, LogoInfo,
-    MarketingInfoResponse, TokenInfoResponse,
+    MarketingInfoResponse, MinterResponse, TokenInfoResponse,
 };
+use ownership::{Expiration, query_pending_owner};
+use roles::{is_authorized, query_role_members, Role};
 use terra_cosmwasm::TerraQuerier;
 
 use crate::allowances::{
     execute_burn_from, execute_decrease_allowance, execute_increase_allowance, execute_send_from,
     execute_transfer_from, query_allowance,
 };
-use crate::enumerable::{query_all_accounts, query_all_allowances};
+use crate::delegation::{
+    decrease_voting_power, execute_delegate, increase_voting_power, query_delegation,
+    query_total_voting_power, query_voting_power,
+};
+use crate::permit::{execute_permit, query_nonce};
+use crate::enumerable::{query_all_accounts, query_all_allowances, query_blocked_accounts};
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::state::{BALANCES, CONFIG, Config, LOGO, MARKETING_INFO, TOKEN_INFO, TokenInfo};
+use crate::msg::{
+    ExecuteMsg, InstantiateMsg, MigrateMsg, PauseInfoResponse, QueryMsg, SendItem,
+    SimulateTransferResponse, TotalSupplyResponse, TransferItem,
+};
+use crate::state::{BALANCES, BLOCKED, CONFIG, Config, HISTORY_START_HEIGHT, LOGO, MARKETING_INFO, MinterData, TOKEN_INFO, TokenInfo, TransferTax};
 
 static DECIMAL_FRACTION: Uint128 = Uint128::new(1_000_000_000_000_000_000u128);
 
@@ -25,6 +35,8 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const LOGO_SIZE_CAP: usize = 5 * 1024;
 
+const BASIS_POINTS: u64 = 10_000;
+
 /// Checks if data starts with XML preamble
 fn verify_xml_preamble(data: &[u8]) -> Result<(), ContractError> {
     // The easiest way to perform this check would be just match on regex, however regex
@@ -80,8 +92,8 @@ fn verify_png_logo(logo: &[u8]) -> Result<(), ContractError> {
 /// Checks if passed logo is correct, and if not, returns an error
 fn verify_logo(logo: &Logo) -> Result<(), ContractError> {
     match logo {
-        Logo::Embedded(EmbeddedLogo::Svg(logo)) => verify_xml_logo(&logo),
-        Logo::Embedded(EmbeddedLogo::Png(logo)) => verify_png_logo(&logo),
+        Logo::Embedded(EmbeddedLogo::Svg(logo)) => verify_xml_logo(logo),
+        Logo::Embedded(EmbeddedLogo::Png(logo)) => verify_png_logo(logo),
         Logo::Url(_) => Ok(()), // Any reasonable url validation would be regex based, probably not worth it
     }
 }
@@ -89,7 +101,7 @@ fn verify_logo(logo: &Logo) -> Result<(), ContractError> {
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     mut deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -97,7 +109,21 @@ pub fn instantiate(
     // check valid token info
     msg.validate()?;
     // create initial accounts
-    let total_supply = create_accounts(&mut deps, &msg.initial_balances)?;
+    let total_supply = create_accounts(&mut deps, &msg.initial_balances, env.block.height)?;
+
+    if let Some(limit) = msg.get_cap() {
+        if total_supply > limit {
+            return Err(StdError::generic_err("Initial supply greater than cap").into());
+        }
+    }
+
+    let mint = match msg.mint {
+        Some(m) => Some(MinterData {
+            minter: deps.api.addr_validate(&m.minter)?,
+            cap: m.cap,
+        }),
+        None => None,
+    };
 
     // store token info
     let data = TokenInfo {
@@ -105,10 +131,19 @@ pub fn instantiate(
         symbol: msg.symbol,
         decimals: msg.decimals,
         total_supply,
+        mint,
+    };
+    TOKEN_INFO.save(deps.storage, &data, env.block.height)?;
+    HISTORY_START_HEIGHT.save(deps.storage, &env.block.height)?;
+
+    let cfg = Config {
+        owner: Some(deps.api.addr_validate(&msg.owner)?),
+        paused: false,
+        pause_updated_at: 0,
+        pause_allowlist: vec![],
+        transfer_tax: None,
+        tax_exempt: vec![],
     };
-    TOKEN_INFO.save(deps.storage, &data)?;
-
-    let cfg = Config { owner: deps.api.addr_validate(&msg.owner)? };
     CONFIG.save(deps.storage, &cfg)?;
 
     if let Some(marketing) = msg.marketing {
@@ -140,21 +175,32 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
     let version = get_contract_version(deps.storage)?;
     if version.contract != CONTRACT_NAME {
         return Err(ContractError::CannotMigrate {
             previous_contract: version.contract,
         });
     }
+
+    // balances are kept under the same key, so the history starts with the first migration
+    // to the snapshot storage
+    if HISTORY_START_HEIGHT.may_load(deps.storage)?.is_none() {
+        HISTORY_START_HEIGHT.save(deps.storage, &env.block.height)?;
+    }
+
     Ok(Response::default())
 }
 
-pub fn create_accounts(deps: &mut DepsMut, accounts: &[Cw20Coin]) -> StdResult<Uint128> {
+pub fn create_accounts(
+    deps: &mut DepsMut,
+    accounts: &[Cw20Coin],
+    height: u64,
+) -> StdResult<Uint128> {
     let mut total_supply = Uint128::zero();
     for row in accounts {
         let address = deps.api.addr_validate(&row.address)?;
-        BALANCES.save(deps.storage, &address, &row.amount)?;
+        BALANCES.save(deps.storage, &address, &row.amount, height)?;
         total_supply += row.amount;
     }
     Ok(total_supply)
@@ -168,17 +214,41 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateConfig { owner } =>
-            execute_update_config(deps, env, info, owner),
+        ExecuteMsg::ProposeOwner { new_owner, expires } =>
+            execute_propose_owner(deps, env, info, new_owner, expires),
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => execute_cancel_ownership_proposal(deps, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, info),
+        ExecuteMsg::GrantRole { role, address } => execute_grant_role(deps, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => execute_revoke_role(deps, info, role, address),
+        ExecuteMsg::Pause {} => execute_set_paused(deps, env, info, true),
+        ExecuteMsg::Unpause {} => execute_set_paused(deps, env, info, false),
+        ExecuteMsg::UpdatePauseAllowlist { add, remove } =>
+            execute_update_pause_allowlist(deps, env, info, add, remove),
+        ExecuteMsg::UpdateBlocklist { add, remove } =>
+            execute_update_blocklist(deps, env, info, add, remove),
+        ExecuteMsg::ForceTransfer { owner, recipient, amount } =>
+            execute_force_transfer(deps, env, info, owner, recipient, amount),
+        ExecuteMsg::UpdateTransferTax { rate, burn_ratio, treasury } =>
+            execute_update_transfer_tax(deps, env, info, rate, burn_ratio, treasury),
+        ExecuteMsg::UpdateTaxExempt { add, remove } =>
+            execute_update_tax_exempt(deps, env, info, add, remove),
         ExecuteMsg::Transfer { recipient, amount } => {
             execute_transfer(deps, env, info, recipient, amount)
         }
+        ExecuteMsg::BatchTransfer { transfers } =>
+            execute_batch_transfer(deps, env, info, transfers),
         ExecuteMsg::Burn { amount } => execute_burn(deps, env, info, amount),
+        ExecuteMsg::Mint { recipient, amount } => execute_mint(deps, env, info, recipient, amount),
+        ExecuteMsg::UpdateMinter { minter, cap } =>
+            execute_update_minter(deps, env, info, minter, cap),
         ExecuteMsg::Send {
             contract,
             amount,
             msg,
         } => execute_send(deps, env, info, contract, amount, msg),
+        ExecuteMsg::BatchSend { sends } => execute_batch_send(deps, env, info, sends),
+        ExecuteMsg::Delegate { delegatee } => execute_delegate(deps, env, info, delegatee),
         ExecuteMsg::IncreaseAllowance {
             spender,
             amount,
@@ -189,6 +259,15 @@ pub fn execute(
             amount,
             expires,
         } => execute_decrease_allowance(deps, env, info, spender, amount, expires),
+        ExecuteMsg::Permit {
+            owner,
+            spender,
+            amount,
+            expires,
+            nonce,
+            pubkey,
+            signature,
+        } => execute_permit(deps, env, owner, spender, amount, expires, nonce, pubkey, signature),
         ExecuteMsg::TransferFrom {
             owner,
             recipient,
@@ -215,33 +294,391 @@ pub fn execute(
     }
 }
 
-pub fn execute_update_config(
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_owner: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(ownership::execute_propose_owner(deps, &env, &info, &cfg.owner, new_owner, expires)?)
+}
+
+pub fn execute_accept_ownership(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let (new_owner, res) = ownership::execute_accept_ownership(deps.branch(), &env, &info)?;
+
+    CONFIG.update(deps.storage, |mut existing_config| -> StdResult<_> {
+        existing_config.owner = Some(new_owner);
+        Ok(existing_config)
+    })?;
+
+    Ok(res)
+}
+
+pub fn execute_cancel_ownership_proposal(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(ownership::execute_cancel_ownership_proposal(deps, &info, &cfg.owner)?)
+}
+
+pub fn execute_renounce_ownership(
+    mut deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    let res = ownership::execute_renounce_ownership(deps.branch(), &info, &cfg.owner)?;
+
+    cfg.owner = None;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(res)
+}
+
+pub fn execute_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(roles::execute_grant_role(deps, &info, &cfg.owner, role, address)?)
+}
+
+pub fn execute_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(roles::execute_revoke_role(deps, &info, &cfg.owner, role, address)?)
+}
+
+pub fn execute_set_paused(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    // authorized pauser
+    let mut cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Pauser, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    cfg.paused = paused;
+    cfg.pause_updated_at = env.block.time.seconds();
+    CONFIG.save(deps.storage, &cfg)?;
+
+    let action = if paused { "pause" } else { "unpause" };
+
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_update_pause_allowlist(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    new_owner: Option<String>,
+    add: Vec<String>,
+    remove: Vec<String>,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized pauser
+    let mut cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Pauser, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    update_addr_list(deps.api, &mut cfg.pause_allowlist, add, remove)?;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_pause_allowlist")
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_update_blocklist(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    // authorized compliance
+    let cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Compliance, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for addr in add.iter() {
+        let addr = deps.api.addr_validate(addr)?;
+        if !BLOCKED.has(deps.storage, &addr) {
+            BLOCKED.save(deps.storage, &addr, &env.block.time.seconds())?;
+        }
+    }
+    for addr in remove.iter() {
+        let addr = deps.api.addr_validate(addr)?;
+        BLOCKED.remove(deps.storage, &addr);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_blocklist")
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_force_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    // authorized compliance
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Compliance, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
-    let api = deps.api;
+    if amount == Uint128::zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
 
-    CONFIG.update(deps.storage, |mut existing_config| -> StdResult<_> {
-        // update new owner if set
-        if let Some(addr) = new_owner {
-            existing_config.owner = api.addr_validate(&addr)?;
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    // recovered tokens cannot go to another frozen account
+    assert_not_frozen(deps.storage, &[&rcpt_addr])?;
+
+    // neither allowance nor the transfer tax applies
+    move_tokens(deps.storage, &owner_addr, &rcpt_addr, amount, env.block.height)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "force_transfer")
+        .add_attribute("from", owner)
+        .add_attribute("to", recipient)
+        .add_attribute("by", info.sender)
+        .add_attribute("amount", amount))
+}
+
+/// Fails with `ContractError::AddressFrozen` for the first frozen address
+pub fn assert_not_frozen(storage: &dyn Storage, addrs: &[&Addr]) -> Result<(), ContractError> {
+    for addr in addrs {
+        if BLOCKED.has(storage, addr) {
+            return Err(ContractError::AddressFrozen {
+                address: addr.to_string(),
+            });
         }
-        Ok(existing_config)
-    })?;
+    }
+    Ok(())
+}
+
+pub fn execute_update_transfer_tax(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    rate: u64,
+    burn_ratio: u64,
+    treasury: String,
+) -> Result<Response, ContractError> {
+    // authorized admin
+    let mut cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Admin, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if rate > BASIS_POINTS || burn_ratio > BASIS_POINTS {
+        return Err(ContractError::InvalidTax {});
+    }
+
+    cfg.transfer_tax = Some(TransferTax {
+        rate,
+        burn_ratio,
+        treasury: deps.api.addr_validate(&treasury)?,
+    });
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_transfer_tax")
+        .add_attribute("sender", info.sender)
+        .add_attribute("rate", rate.to_string())
+        .add_attribute("burn_ratio", burn_ratio.to_string())
+        .add_attribute("treasury", treasury))
+}
+
+pub fn execute_update_tax_exempt(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    // authorized admin
+    let mut cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Admin, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    update_addr_list(deps.api, &mut cfg.tax_exempt, add, remove)?;
+    CONFIG.save(deps.storage, &cfg)?;
 
     Ok(Response::new()
-        .add_attribute("action", "update_config")
+        .add_attribute("action", "update_tax_exempt")
         .add_attribute("sender", info.sender))
 }
 
+fn update_addr_list(
+    api: &dyn Api,
+    list: &mut Vec<Addr>,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> StdResult<()> {
+    for addr in add.iter() {
+        let addr = api.addr_validate(addr)?;
+        if !list.contains(&addr) {
+            list.push(addr);
+        }
+    }
+    for addr in remove.iter() {
+        let addr = api.addr_validate(addr)?;
+        list.retain(|a| a != &addr);
+    }
+    Ok(())
+}
+
+/// Fails with `ContractError::Paused` if the sender cannot move tokens at the moment
+pub fn assert_not_paused(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    let cfg = CONFIG.load(storage)?;
+    if !cfg.can_move_tokens(sender) {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
+}
+
+/// Moves tokens between balances together with the voting power, fails if the sender
+/// has not enough tokens
+pub fn move_tokens(
+    storage: &mut dyn Storage,
+    from: &Addr,
+    to: &Addr,
+    amount: Uint128,
+    height: u64,
+) -> Result<(), ContractError> {
+    BALANCES.update(
+        storage,
+        from,
+        height,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(amount)?)
+        },
+    )?;
+    decrease_voting_power(storage, from, amount, height)?;
+    BALANCES.update(
+        storage,
+        to,
+        height,
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+    )?;
+    increase_voting_power(storage, to, amount, height)?;
+    Ok(())
+}
+
+/// Removes tokens from the balance and the total supply
+pub fn burn_tokens(
+    storage: &mut dyn Storage,
+    from: &Addr,
+    amount: Uint128,
+    height: u64,
+) -> Result<(), ContractError> {
+    // lower balance
+    BALANCES.update(
+        storage,
+        from,
+        height,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(amount)?)
+        },
+    )?;
+    decrease_voting_power(storage, from, amount, height)?;
+    // reduce total_supply
+    let mut meta = TOKEN_INFO.load(storage)?;
+    meta.total_supply = meta.total_supply.checked_sub(amount)?;
+    TOKEN_INFO.save(storage, &meta, height)?;
+    Ok(())
+}
+
+/// Splits the transferred amount into the transfer tax and the amount the recipient gets
+pub fn compute_transfer(
+    cfg: &Config,
+    from: &Addr,
+    to: &Addr,
+    amount: Uint128,
+) -> SimulateTransferResponse {
+    let (fee, burn) = match &cfg.transfer_tax {
+        Some(tax) if !cfg.is_tax_exempt(from, to) => {
+            let fee = amount.multiply_ratio(tax.rate, BASIS_POINTS);
+            (fee, fee.multiply_ratio(tax.burn_ratio, BASIS_POINTS))
+        }
+        _ => (Uint128::zero(), Uint128::zero()),
+    };
+    SimulateTransferResponse {
+        gross: amount,
+        fee,
+        burn,
+        net: amount - fee,
+    }
+}
+
+/// Moves tokens between balances charging the transfer tax. The part of the tax which is
+/// not burned goes to the treasury.
+pub fn transfer_tokens(
+    storage: &mut dyn Storage,
+    from: &Addr,
+    to: &Addr,
+    amount: Uint128,
+    height: u64,
+) -> Result<SimulateTransferResponse, ContractError> {
+    let cfg = CONFIG.load(storage)?;
+    let transfer = compute_transfer(&cfg, from, to, amount);
+
+    move_tokens(storage, from, to, transfer.net, height)?;
+    if let Some(tax) = cfg.transfer_tax {
+        let treasury_amount = transfer.fee - transfer.burn;
+        if !treasury_amount.is_zero() {
+            move_tokens(storage, from, &tax.treasury, treasury_amount, height)?;
+        }
+    }
+    if !transfer.burn.is_zero() {
+        burn_tokens(storage, from, transfer.burn, height)?;
+    }
+
+    Ok(transfer)
+}
+
+/// Adds tax attributes to the response if the transfer was taxed
+pub fn add_tax_attributes(res: Response, transfer: &SimulateTransferResponse) -> Response {
+    if transfer.fee.is_zero() {
+        return res;
+    }
+    res.add_attribute("fee", transfer.fee)
+        .add_attribute("burn", transfer.burn)
+}
+
+fn tax_event_attributes(event: Event, transfer: &SimulateTransferResponse) -> Event {
+    if transfer.fee.is_zero() {
+        return event;
+    }
+    event.add_attribute("fee", transfer.fee)
+        .add_attribute("burn", transfer.burn)
+}
+
 pub fn execute_withdraw_locked_funds(
     deps: DepsMut,
     info: MessageInfo,
@@ -249,9 +686,9 @@ pub fn execute_withdraw_locked_funds(
     amount: Uint128,
     recipient: String,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized treasurer
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Treasurer, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -269,67 +706,98 @@ pub fn execute_withdraw_locked_funds(
 
 pub fn execute_transfer(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     recipient: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, &info.sender)?;
+
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
 
     let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    assert_not_frozen(deps.storage, &[&info.sender, &rcpt_addr])?;
 
-    BALANCES.update(
-        deps.storage,
-        &info.sender,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    let transfer = transfer_tokens(deps.storage, &info.sender, &rcpt_addr, amount, env.block.height)?;
 
     let res = Response::new()
         .add_attribute("action", "transfer")
         .add_attribute("from", info.sender)
         .add_attribute("to", recipient)
         .add_attribute("amount", amount);
+    Ok(add_tax_attributes(res, &transfer))
+}
+
+pub fn execute_batch_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    transfers: Vec<TransferItem>,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, &info.sender)?;
+
+    if transfers.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+    assert_not_frozen(deps.storage, &[&info.sender])?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "batch_transfer")
+        .add_attribute("from", &info.sender);
+
+    // validate all legs before moving any tokens
+    let legs = transfers
+        .into_iter()
+        .map(|transfer| {
+            if transfer.amount == Uint128::zero() {
+                return Err(ContractError::InvalidZeroAmount {});
+            }
+            let rcpt_addr = deps.api.addr_validate(&transfer.recipient)?;
+            assert_not_frozen(deps.storage, &[&rcpt_addr])?;
+            Ok((rcpt_addr, transfer.amount))
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+
+    for (rcpt_addr, amount) in legs {
+        let transfer = transfer_tokens(deps.storage, &info.sender, &rcpt_addr, amount, env.block.height)?;
+
+        res = res.add_event(
+            tax_event_attributes(
+                Event::new("transfer")
+                    .add_attribute("from", &info.sender)
+                    .add_attribute("to", rcpt_addr)
+                    .add_attribute("amount", amount),
+                &transfer,
+            ),
+        );
+    }
+
     Ok(res)
 }
 
 pub fn execute_burn(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
     // authorized owner
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if cfg.owner.as_ref() != Some(&info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
+    assert_not_paused(deps.storage, &info.sender)?;
+
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
 
-    // lower balance
-    BALANCES.update(
-        deps.storage,
-        &info.sender,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    // reduce total_supply
-    TOKEN_INFO.update(deps.storage, |mut info| -> StdResult<_> {
-        info.total_supply = info.total_supply.checked_sub(amount)?;
-        Ok(info)
-    })?;
+    assert_not_frozen(deps.storage, &[&info.sender])?;
+
+    burn_tokens(deps.storage, &info.sender, amount, env.block.height)?;
 
     let res = Response::new()
         .add_attribute("action", "burn")
@@ -338,33 +806,110 @@ pub fn execute_burn(
     Ok(res)
 }
 
-pub fn execute_send(
+pub fn execute_mint(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    contract: String,
+    recipient: String,
     amount: Uint128,
-    msg: Binary,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, &info.sender)?;
+
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
 
-    let rcpt_addr = deps.api.addr_validate(&contract)?;
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    if config.mint.is_none() || config.mint.as_ref().unwrap().minter != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    // move the tokens to the contract
-    BALANCES.update(
-        deps.storage,
-        &info.sender,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
+    let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    assert_not_frozen(deps.storage, &[&rcpt_addr])?;
+
+    // update supply and enforce cap
+    config.total_supply += amount;
+    if let Some(limit) = config.get_cap() {
+        if config.total_supply > limit {
+            return Err(ContractError::CannotExceedCap {});
+        }
+    }
+    TOKEN_INFO.save(deps.storage, &config, env.block.height)?;
+
+    // add amount to recipient balance
     BALANCES.update(
         deps.storage,
         &rcpt_addr,
+        env.block.height,
         |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
     )?;
+    increase_voting_power(deps.storage, &rcpt_addr, amount, env.block.height)?;
+
+    let res = Response::new()
+        .add_attribute("action", "mint")
+        .add_attribute("to", recipient)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+pub fn execute_update_minter(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_minter: Option<String>,
+    new_cap: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    // authorized admin
+    let cfg = CONFIG.load(deps.storage)?;
+    if !is_authorized(deps.storage, &cfg.owner, Role::Admin, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut token_info = TOKEN_INFO.load(deps.storage)?;
+    if let Some(limit) = new_cap {
+        if token_info.total_supply > limit {
+            return Err(ContractError::CannotExceedCap {});
+        }
+    }
+
+    token_info.mint = match new_minter {
+        Some(addr) => Some(MinterData {
+            minter: deps.api.addr_validate(&addr)?,
+            cap: new_cap,
+        }),
+        None => None,
+    };
+    TOKEN_INFO.save(deps.storage, &token_info, env.block.height)?;
+
+    let minter = token_info.mint
+        .map(|m| m.minter.to_string())
+        .unwrap_or_default();
+
+    Ok(Response::new()
+        .add_attribute("action", "update_minter")
+        .add_attribute("sender", info.sender)
+        .add_attribute("minter", minter))
+}
+
+pub fn execute_send(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    amount: Uint128,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, &info.sender)?;
+
+    if amount == Uint128::zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let rcpt_addr = deps.api.addr_validate(&contract)?;
+    assert_not_frozen(deps.storage, &[&info.sender, &rcpt_addr])?;
+
+    // move the tokens to the contract
+    let transfer = transfer_tokens(deps.storage, &info.sender, &rcpt_addr, amount, env.block.height)?;
 
     let res = Response::new()
         .add_attribute("action", "send")
@@ -374,11 +919,67 @@ pub fn execute_send(
         .add_message(
             Cw20ReceiveMsg {
                 sender: info.sender.into(),
-                amount,
+                amount: transfer.net,
                 msg,
             }
                 .into_cosmos_msg(contract)?,
         );
+    Ok(add_tax_attributes(res, &transfer))
+}
+
+pub fn execute_batch_send(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sends: Vec<SendItem>,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, &info.sender)?;
+
+    if sends.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+    assert_not_frozen(deps.storage, &[&info.sender])?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "batch_send")
+        .add_attribute("from", &info.sender);
+
+    // validate all legs before moving any tokens
+    let legs = sends
+        .into_iter()
+        .map(|send| {
+            if send.amount == Uint128::zero() {
+                return Err(ContractError::InvalidZeroAmount {});
+            }
+            let rcpt_addr = deps.api.addr_validate(&send.contract)?;
+            assert_not_frozen(deps.storage, &[&rcpt_addr])?;
+            Ok((rcpt_addr, send.amount, send.msg))
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+
+    for (rcpt_addr, amount, msg) in legs {
+        let transfer = transfer_tokens(deps.storage, &info.sender, &rcpt_addr, amount, env.block.height)?;
+
+        res = res
+            .add_event(
+                tax_event_attributes(
+                    Event::new("send")
+                        .add_attribute("from", &info.sender)
+                        .add_attribute("to", &rcpt_addr)
+                        .add_attribute("amount", amount),
+                    &transfer,
+                ),
+            )
+            .add_message(
+                Cw20ReceiveMsg {
+                    sender: info.sender.to_string(),
+                    amount: transfer.net,
+                    msg,
+                }
+                    .into_cosmos_msg(rcpt_addr)?,
+            );
+    }
+
     Ok(res)
 }
 
@@ -447,11 +1048,10 @@ pub fn execute_upload_logo(
 
     verify_logo(&logo)?;
 
-    if marketing_info
-        .marketing
-        .as_ref()
-        .ok_or(ContractError::Unauthorized {})?
-        != &info.sender
+    // marketing address or authorized operator
+    let cfg = CONFIG.load(deps.storage)?;
+    if marketing_info.marketing.as_ref() != Some(&info.sender)
+        && !is_authorized(deps.storage, &cfg.owner, Role::Operator, &info.sender)
     {
         return Err(ContractError::Unauthorized {});
     }
@@ -474,11 +1074,30 @@ pub fn execute_upload_logo(
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps.storage)?),
+        QueryMsg::RoleMembers { role, start_after, limit } =>
+            to_binary(&query_role_members(deps, role, start_after, limit)?),
+        QueryMsg::PauseInfo {} => to_binary(&query_pause_info(deps)?),
         QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
+        QueryMsg::BalanceAtHeight { address, height } =>
+            to_binary(&query_balance_at_height(deps, address, height)?),
+        QueryMsg::BlockedAccounts { start_after, limit } =>
+            to_binary(&query_blocked_accounts(deps, start_after, limit)?),
+        QueryMsg::SimulateTransfer { sender, recipient, amount } =>
+            to_binary(&query_simulate_transfer(deps, sender, recipient, amount)?),
+        QueryMsg::VotingPower { address, height } =>
+            to_binary(&query_voting_power(deps, address, height)?),
+        QueryMsg::Delegation { address } => to_binary(&query_delegation(deps, address)?),
+        QueryMsg::TotalVotingPower { height } =>
+            to_binary(&query_total_voting_power(deps, height)?),
         QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
+        QueryMsg::TotalSupplyAtHeight { height } =>
+            to_binary(&query_total_supply_at_height(deps, height)?),
+        QueryMsg::Minter {} => to_binary(&query_minter(deps)?),
         QueryMsg::Allowance { owner, spender } => {
             to_binary(&query_allowance(deps, owner, spender)?)
         }
+        QueryMsg::Nonce { owner } => to_binary(&query_nonce(deps, owner)?),
         QueryMsg::AllAllowances {
             owner,
             start_after,
@@ -493,7 +1112,16 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
 }
 
 pub fn query_config(deps: Deps) -> StdResult<Config> {
-    Ok(CONFIG.load(deps.storage)?)
+    CONFIG.load(deps.storage)
+}
+
+pub fn query_pause_info(deps: Deps) -> StdResult<PauseInfoResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(PauseInfoResponse {
+        paused: cfg.paused,
+        updated_at: cfg.pause_updated_at,
+        allowlist: cfg.pause_allowlist.into_iter().map(String::from).collect(),
+    })
 }
 
 pub fn query_balance(deps: Deps, address: String) -> StdResult<BalanceResponse> {
@@ -504,6 +1132,51 @@ pub fn query_balance(deps: Deps, address: String) -> StdResult<BalanceResponse>
     Ok(BalanceResponse { balance })
 }
 
+pub fn query_balance_at_height(
+    deps: Deps,
+    address: String,
+    height: u64,
+) -> StdResult<BalanceResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    assert_history_available(deps, height)?;
+    let balance = BALANCES
+        .may_load_at_height(deps.storage, &address, height)?
+        .unwrap_or_default();
+    Ok(BalanceResponse { balance })
+}
+
+pub fn query_total_supply_at_height(deps: Deps, height: u64) -> StdResult<TotalSupplyResponse> {
+    assert_history_available(deps, height)?;
+    let total_supply = TOKEN_INFO
+        .may_load_at_height(deps.storage, height)?
+        .map(|info| info.total_supply)
+        .unwrap_or_default();
+    Ok(TotalSupplyResponse { total_supply })
+}
+
+pub fn assert_history_available(deps: Deps, height: u64) -> StdResult<()> {
+    let start = HISTORY_START_HEIGHT.load(deps.storage)?;
+    if height < start {
+        return Err(StdError::generic_err(format!(
+            "No balance history before height {}",
+            start
+        )));
+    }
+    Ok(())
+}
+
+pub fn query_simulate_transfer(
+    deps: Deps,
+    sender: String,
+    recipient: String,
+    amount: Uint128,
+) -> StdResult<SimulateTransferResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let sender = deps.api.addr_validate(&sender)?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+    Ok(compute_transfer(&cfg, &sender, &recipient, amount))
+}
+
 pub fn query_token_info(deps: Deps) -> StdResult<TokenInfoResponse> {
     let info = TOKEN_INFO.load(deps.storage)?;
     let res = TokenInfoResponse {
@@ -515,6 +1188,15 @@ pub fn query_token_info(deps: Deps) -> StdResult<TokenInfoResponse> {
     Ok(res)
 }
 
+pub fn query_minter(deps: Deps) -> StdResult<Option<MinterResponse>> {
+    let meta = TOKEN_INFO.load(deps.storage)?;
+    let minter = meta.mint.map(|m| MinterResponse {
+        minter: m.minter.into(),
+        cap: m.cap,
+    });
+    Ok(minter)
+}
+
 pub fn query_marketing_info(deps: Deps) -> StdResult<MarketingInfoResponse> {
     Ok(MARKETING_INFO.may_load(deps.storage)?.unwrap_or_default())
 }
@@ -563,9 +1245,12 @@ fn deduct_tax(deps: DepsMut, coin: Coin) -> Result<Coin, ContractError> {
 
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::{Addr, coins, CosmosMsg, from_binary, StdError, SubMsg, WasmMsg};
+    use cosmwasm_std::{Addr, attr, coins, CosmosMsg, from_binary, StdError, SubMsg, WasmMsg};
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
 
+    use ownership::{OwnershipError, PendingOwnerResponse};
+    use roles::{RoleMembersResponse, RolesError};
+
     use crate::msg::InstantiateMarketingInfo;
 
     use super::*;
@@ -574,9 +1259,28 @@ mod tests {
         query_balance(deps, address.into()).unwrap().balance
     }
 
+    // this will set up the instantiation for other tests
+    fn do_instantiate_with_minter(
+        deps: DepsMut,
+        addr: &str,
+        amount: Uint128,
+        minter: &str,
+        cap: Option<Uint128>,
+    ) -> TokenInfoResponse {
+        _do_instantiate(
+            deps,
+            addr,
+            amount,
+            Some(MinterResponse {
+                minter: minter.to_string(),
+                cap,
+            }),
+        )
+    }
+
     // this will set up the instantiation for other tests
     fn do_instantiate(deps: DepsMut, addr: &str, amount: Uint128) -> TokenInfoResponse {
-        _do_instantiate(deps, addr, amount)
+        _do_instantiate(deps, addr, amount, None)
     }
 
     // this will set up the instantiation for other tests
@@ -584,6 +1288,7 @@ mod tests {
         mut deps: DepsMut,
         addr: &str,
         amount: Uint128,
+        mint: Option<MinterResponse>,
     ) -> TokenInfoResponse {
         let instantiate_msg = InstantiateMsg {
             owner: "OWNER".to_string(),
@@ -594,6 +1299,7 @@ mod tests {
                 address: addr.to_string(),
                 amount,
             }],
+            mint: mint.clone(),
             marketing: None,
         };
         let info = mock_info("creator", &[]);
@@ -612,6 +1318,7 @@ mod tests {
             }
         );
         assert_eq!(get_balance(deps.as_ref(), addr), amount);
+        assert_eq!(query_minter(deps.as_ref()).unwrap(), mint);
         meta
     }
 
@@ -633,6 +1340,7 @@ mod tests {
                     address: String::from("addr0000"),
                     amount,
                 }],
+                mint: None,
                 marketing: None,
             };
             let info = mock_info("creator", &[]);
@@ -667,6 +1375,7 @@ mod tests {
                     symbol: "CASH".to_string(),
                     decimals: 9,
                     initial_balances: vec![],
+                    mint: None,
                     marketing: Some(InstantiateMarketingInfo {
                         project: Some("Project".to_owned()),
                         description: Some("Description".to_owned()),
@@ -707,6 +1416,7 @@ mod tests {
                     symbol: "CASH".to_string(),
                     decimals: 9,
                     initial_balances: vec![],
+                    mint: None,
                     marketing: Some(InstantiateMarketingInfo {
                         project: Some("Project".to_owned()),
                         description: Some("Description".to_owned()),
@@ -751,6 +1461,7 @@ mod tests {
                     amount: amount2,
                 },
             ],
+            mint: None,
             marketing: None,
         };
         let info = mock_info("creator", &[]);
@@ -771,6 +1482,225 @@ mod tests {
         assert_eq!(get_balance(deps.as_ref(), addr2), amount2);
     }
 
+    #[test]
+    fn instantiate_mintable() {
+        let mut deps = mock_dependencies(&[]);
+        let amount = Uint128::new(11223344);
+        let minter = String::from("asmodat");
+        let limit = Uint128::new(511223344);
+        do_instantiate_with_minter(deps.as_mut(), "addr0000", amount, &minter, Some(limit));
+
+        assert_eq!(
+            query_minter(deps.as_ref()).unwrap(),
+            Some(MinterResponse {
+                minter,
+                cap: Some(limit),
+            }),
+        );
+    }
+
+    #[test]
+    fn instantiate_mintable_over_cap() {
+        let mut deps = mock_dependencies(&[]);
+        let amount = Uint128::new(11223344);
+        let minter = String::from("asmodat");
+        let limit = Uint128::new(11223300);
+        let instantiate_msg = InstantiateMsg {
+            owner: "OWNER".to_string(),
+            name: "Cash Token".to_string(),
+            symbol: "CASH".to_string(),
+            decimals: 9,
+            initial_balances: vec![Cw20Coin {
+                address: String::from("addr0000"),
+                amount,
+            }],
+            mint: Some(MinterResponse {
+                minter,
+                cap: Some(limit),
+            }),
+            marketing: None,
+        };
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+        let err = instantiate(deps.as_mut(), env, info, instantiate_msg).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Initial supply greater than cap").into()
+        );
+    }
+
+    #[test]
+    fn can_mint_by_minter() {
+        let mut deps = mock_dependencies(&[]);
+
+        let genesis = String::from("genesis");
+        let amount = Uint128::new(11223344);
+        let minter = String::from("asmodat");
+        let limit = Uint128::new(511223344);
+        do_instantiate_with_minter(deps.as_mut(), &genesis, amount, &minter, Some(limit));
+
+        // minter can mint coins to some winner
+        let winner = String::from("lucky");
+        let prize = Uint128::new(222_222_222);
+        let msg = ExecuteMsg::Mint {
+            recipient: winner.clone(),
+            amount: prize,
+        };
+
+        let info = mock_info(minter.as_ref(), &[]);
+        let env = mock_env();
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(get_balance(deps.as_ref(), genesis), amount);
+        assert_eq!(get_balance(deps.as_ref(), winner.clone()), prize);
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            amount + prize
+        );
+
+        // but cannot mint nothing
+        let msg = ExecuteMsg::Mint {
+            recipient: winner.clone(),
+            amount: Uint128::zero(),
+        };
+        let info = mock_info(minter.as_ref(), &[]);
+        let env = mock_env();
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidZeroAmount {});
+
+        // but if it exceeds cap (even over multiple rounds), it fails
+        // cap is enforced
+        let msg = ExecuteMsg::Mint {
+            recipient: winner,
+            amount: Uint128::new(333_222_222),
+        };
+        let info = mock_info(minter.as_ref(), &[]);
+        let env = mock_env();
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::CannotExceedCap {});
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            amount + prize
+        );
+    }
+
+    #[test]
+    fn others_cannot_mint() {
+        let mut deps = mock_dependencies(&[]);
+        do_instantiate_with_minter(
+            deps.as_mut(),
+            &String::from("genesis"),
+            Uint128::new(1234),
+            &String::from("minter"),
+            None,
+        );
+
+        let msg = ExecuteMsg::Mint {
+            recipient: String::from("lucky"),
+            amount: Uint128::new(222),
+        };
+        let info = mock_info("anyone else", &[]);
+        let env = mock_env();
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // the owner is not a minter either
+        let msg = ExecuteMsg::Mint {
+            recipient: String::from("lucky"),
+            amount: Uint128::new(222),
+        };
+        let info = mock_info("OWNER", &[]);
+        let env = mock_env();
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn no_one_mints_if_minter_unset() {
+        let mut deps = mock_dependencies(&[]);
+        do_instantiate(deps.as_mut(), &String::from("genesis"), Uint128::new(1234));
+
+        let msg = ExecuteMsg::Mint {
+            recipient: String::from("lucky"),
+            amount: Uint128::new(222),
+        };
+        let info = mock_info("genesis", &[]);
+        let env = mock_env();
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn update_minter() {
+        let mut deps = mock_dependencies(&[]);
+        let minter = String::from("minter");
+        let new_minter = String::from("new_minter");
+        do_instantiate_with_minter(
+            deps.as_mut(),
+            &String::from("genesis"),
+            Uint128::new(1234),
+            &minter,
+            Some(Uint128::new(2000)),
+        );
+
+        // only owner can update minter
+        let msg = ExecuteMsg::UpdateMinter {
+            minter: Some(new_minter.clone()),
+            cap: Some(Uint128::new(5000)),
+        };
+        let info = mock_info(minter.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // cap cannot be lower than current supply
+        let bad_msg = ExecuteMsg::UpdateMinter {
+            minter: Some(new_minter.clone()),
+            cap: Some(Uint128::new(1000)),
+        };
+        let info = mock_info("OWNER", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, bad_msg).unwrap_err();
+        assert_eq!(err, ContractError::CannotExceedCap {});
+
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            query_minter(deps.as_ref()).unwrap(),
+            Some(MinterResponse {
+                minter: new_minter.clone(),
+                cap: Some(Uint128::new(5000)),
+            }),
+        );
+
+        // old minter lost the role, new one can mint up to the new cap
+        let msg = ExecuteMsg::Mint {
+            recipient: String::from("lucky"),
+            amount: Uint128::new(3000),
+        };
+        let info = mock_info(minter.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info(new_minter.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "lucky"), Uint128::new(3000));
+
+        // unset minter disables minting
+        let msg = ExecuteMsg::UpdateMinter {
+            minter: None,
+            cap: None,
+        };
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(query_minter(deps.as_ref()).unwrap(), None);
+
+        let msg = ExecuteMsg::Mint {
+            recipient: String::from("lucky"),
+            amount: Uint128::new(1),
+        };
+        let info = mock_info(new_minter.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
     #[test]
     fn queries_work() {
         let mut deps = mock_dependencies(&coins(2, "token"));
@@ -869,52 +1799,528 @@ mod tests {
     }
 
     #[test]
-    fn burn() {
-        let mut deps = mock_dependencies(&coins(2, "token"));
-        // only owner can burn
+    fn burn() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+        // only owner can burn
+        let addr1 = String::from("OWNER");
+        let amount1 = Uint128::from(12340000u128);
+        let burn = Uint128::from(76543u128);
+        let too_much = Uint128::from(12340321u128);
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+
+        // cannot burn nothing
+        let info = mock_info(addr1.as_ref(), &[]);
+        let env = mock_env();
+        let msg = ExecuteMsg::Burn {
+            amount: Uint128::zero(),
+        };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidZeroAmount {});
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            amount1
+        );
+
+        // cannot burn more than we have
+        let info = mock_info(addr1.as_ref(), &[]);
+        let env = mock_env();
+        let msg = ExecuteMsg::Burn { amount: too_much };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            amount1
+        );
+
+        // valid burn reduces total supply
+        let info = mock_info(addr1.as_ref(), &[]);
+        let env = mock_env();
+        let msg = ExecuteMsg::Burn { amount: burn };
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(res.messages.len(), 0);
+
+        let remainder = amount1.checked_sub(burn).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), addr1), remainder);
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            remainder
+        );
+    }
+
+    #[test]
+    fn two_step_ownership() {
+        let mut deps = mock_dependencies(&[]);
+        do_instantiate(deps.as_mut(), "addr0001", Uint128::new(1000));
+
+        // only owner can propose
+        let msg = ExecuteMsg::ProposeOwner {
+            new_owner: "NEW_OWNER".to_string(),
+            expires: None,
+        };
+        let info = mock_info("NEW_OWNER", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Ownership(OwnershipError::Unauthorized {}));
+
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let pending: PendingOwnerResponse = from_binary(
+            &query(deps.as_ref(), mock_env(), QueryMsg::PendingOwner {}).unwrap(),
+        )
+            .unwrap();
+        assert_eq!(pending.pending_owner, Some("NEW_OWNER".to_string()));
+
+        // old owner keeps control until the proposal is accepted
+        let info = mock_info("NEW_OWNER", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {}).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {}).unwrap();
+
+        // only the proposed owner can accept
+        let info = mock_info("OWNER", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::AcceptOwnership {})
+            .unwrap_err();
+        assert_eq!(err, ContractError::Ownership(OwnershipError::Unauthorized {}));
+
+        let info = mock_info("NEW_OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::AcceptOwnership {}).unwrap();
+        assert_eq!(
+            query_config(deps.as_ref()).unwrap().owner,
+            Some(Addr::unchecked("NEW_OWNER"))
+        );
+
+        let info = mock_info("OWNER", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Unpause {}).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info("NEW_OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Unpause {}).unwrap();
+
+        // expired proposal cannot be accepted
+        let msg = ExecuteMsg::ProposeOwner {
+            new_owner: "OWNER".to_string(),
+            expires: Some(Expiration::AtHeight(mock_env().block.height + 1)),
+        };
+        let info = mock_info("NEW_OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let mut env = mock_env();
+        env.block.height += 1;
+        let info = mock_info("OWNER", &[]);
+        let err = execute(deps.as_mut(), env, info, ExecuteMsg::AcceptOwnership {}).unwrap_err();
+        assert_eq!(err, ContractError::Ownership(OwnershipError::ProposalExpired {}));
+
+        let info = mock_info("NEW_OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::CancelOwnershipProposal {}).unwrap();
+        let pending = query_pending_owner(deps.as_ref().storage).unwrap();
+        assert_eq!(pending.pending_owner, None);
+
+        // nobody controls the contract after renouncing
+        let info = mock_info("NEW_OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::RenounceOwnership {}).unwrap();
+        assert_eq!(query_config(deps.as_ref()).unwrap().owner, None);
+        let info = mock_info("NEW_OWNER", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {}).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn roles_are_independent() {
+        let mut deps = mock_dependencies(&[]);
+        let pauser = String::from("pauser");
+        do_instantiate(deps.as_mut(), "addr0001", Uint128::new(1000));
+
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::Pauser,
+            address: pauser.clone(),
+        };
+        let info = mock_info(pauser.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Roles(RolesError::Unauthorized {}));
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res: RoleMembersResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::RoleMembers {
+                    role: Role::Pauser,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+                .unwrap(),
+        )
+            .unwrap();
+        assert_eq!(res.members, vec![pauser.clone()]);
+
+        // pauser pauses
+        let info = mock_info(pauser.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {}).unwrap();
+
+        // but cannot touch anything else
+        let msg = ExecuteMsg::UpdateTransferTax {
+            rate: 100,
+            burn_ratio: 0,
+            treasury: pauser.clone(),
+        };
+        let info = mock_info(pauser.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let msg = ExecuteMsg::WithdrawLockedFunds {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(100),
+            recipient: pauser.clone(),
+        };
+        let info = mock_info(pauser.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let msg = ExecuteMsg::RevokeRole {
+            role: Role::Pauser,
+            address: pauser.clone(),
+        };
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info(pauser.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Unpause {}).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn pause_and_unpause() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let addr2 = String::from("addr0002");
+        let amount1 = Uint128::from(12340000u128);
+        let transfer = Uint128::from(76543u128);
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+        assert_eq!(
+            query_pause_info(deps.as_ref()).unwrap(),
+            PauseInfoResponse {
+                paused: false,
+                updated_at: 0,
+                allowlist: vec![],
+            }
+        );
+
+        // only owner can pause
+        let info = mock_info(addr1.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {}).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let env = mock_env();
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Pause {}).unwrap();
+        assert_eq!(
+            query_pause_info(deps.as_ref()).unwrap(),
+            PauseInfoResponse {
+                paused: true,
+                updated_at: env.block.time.seconds(),
+                allowlist: vec![],
+            }
+        );
+
+        // transfer and send are stopped
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: transfer,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::Send {
+            contract: addr2.clone(),
+            amount: transfer,
+            msg: Binary::default(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+
+        // allowlisted address can still move tokens
+        let info = mock_info("OWNER", &[]);
+        let msg = ExecuteMsg::UpdatePauseAllowlist {
+            add: vec![addr1.clone()],
+            remove: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            query_pause_info(deps.as_ref()).unwrap().allowlist,
+            vec![addr1.clone()]
+        );
+
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: transfer,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), addr2.clone()), transfer);
+
+        // but not the one who just received tokens
+        let info = mock_info(addr2.as_ref(), &[]);
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr1.clone(),
+            amount: transfer,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+
+        // after unpause everybody can transfer again
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Unpause {}).unwrap();
+        let pause_info = query_pause_info(deps.as_ref()).unwrap();
+        assert!(!pause_info.paused);
+        assert_eq!(pause_info.updated_at, env.block.time.seconds());
+
+        let info = mock_info(addr2.as_ref(), &[]);
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr1.clone(),
+            amount: transfer,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), addr1), amount1);
+    }
+
+    #[test]
+    fn blocklist_and_force_transfer() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let addr2 = String::from("addr0002");
+        let addr3 = String::from("addr0003");
+        let compliance = String::from("compliance");
+
+        do_instantiate(deps.as_mut(), &addr1, Uint128::new(100000));
+
+        // only admin grants the compliance role
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::Compliance,
+            address: compliance.clone(),
+        };
+        let info = mock_info(addr1.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Roles(RolesError::Unauthorized {}));
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // others cannot freeze
+        let msg = ExecuteMsg::UpdateBlocklist {
+            add: vec![addr2.clone()],
+            remove: vec![],
+        };
+        let info = mock_info(addr1.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: Uint128::new(30000),
+        };
+        let info = mock_info(addr1.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateBlocklist {
+            add: vec![addr2.clone()],
+            remove: vec![],
+        };
+        let info = mock_info(compliance.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let frozen = ContractError::AddressFrozen {
+            address: addr2.clone(),
+        };
+
+        // frozen account cannot receive
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: Uint128::new(1000),
+        };
+        let info = mock_info(addr1.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, frozen);
+
+        let msg = ExecuteMsg::BatchTransfer {
+            transfers: vec![
+                TransferItem {
+                    recipient: addr3.clone(),
+                    amount: Uint128::new(1000),
+                },
+                TransferItem {
+                    recipient: addr2.clone(),
+                    amount: Uint128::new(1000),
+                },
+            ],
+        };
+        let info = mock_info(addr1.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, frozen);
+
+        // nor send
+        let msg = ExecuteMsg::Send {
+            contract: addr3.clone(),
+            amount: Uint128::new(1000),
+            msg: Binary::default(),
+        };
+        let info = mock_info(addr2.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, frozen);
+
+        // tokens can be recovered without an allowance
+        let msg = ExecuteMsg::ForceTransfer {
+            owner: addr2.clone(),
+            recipient: addr3.clone(),
+            amount: Uint128::new(30000),
+        };
+        let info = mock_info(addr1.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info(compliance.as_ref(), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "force_transfer"));
+        assert_eq!(get_balance(deps.as_ref(), &addr2), Uint128::zero());
+        assert_eq!(get_balance(deps.as_ref(), &addr3), Uint128::new(30000));
+
+        // but not to another frozen account
+        let msg = ExecuteMsg::ForceTransfer {
+            owner: addr3.clone(),
+            recipient: addr2.clone(),
+            amount: Uint128::new(1000),
+        };
+        let info = mock_info("OWNER", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, frozen);
+
+        // unfrozen account works again
+        let msg = ExecuteMsg::UpdateBlocklist {
+            add: vec![],
+            remove: vec![addr2.clone()],
+        };
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: Uint128::new(1000),
+        };
+        let info = mock_info(addr1.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), addr2), Uint128::new(1000));
+    }
+
+    #[test]
+    fn burn_stopped_when_paused() {
+        let mut deps = mock_dependencies(&[]);
+        let amount1 = Uint128::from(12340000u128);
+        do_instantiate(deps.as_mut(), "OWNER", amount1);
+
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {}).unwrap();
+
+        let info = mock_info("OWNER", &[]);
+        let msg = ExecuteMsg::Burn { amount: Uint128::new(100) };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            amount1
+        );
+    }
+
+    #[test]
+    fn balance_and_supply_history() {
+        let mut deps = mock_dependencies(&[]);
         let addr1 = String::from("OWNER");
+        let addr2 = String::from("addr0002");
         let amount1 = Uint128::from(12340000u128);
-        let burn = Uint128::from(76543u128);
-        let too_much = Uint128::from(12340321u128);
+        let transfer = Uint128::from(76543u128);
+        let burn = Uint128::from(1000u128);
 
+        let env = mock_env();
+        let start = env.block.height;
         do_instantiate(deps.as_mut(), &addr1, amount1);
 
-        // cannot burn nothing
+        // transfer in the next block
+        let mut env = mock_env();
+        env.block.height = start + 1;
         let info = mock_info(addr1.as_ref(), &[]);
-        let env = mock_env();
-        let msg = ExecuteMsg::Burn {
-            amount: Uint128::zero(),
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: transfer,
         };
-        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
-        assert_eq!(err, ContractError::InvalidZeroAmount {});
+        execute(deps.as_mut(), env, info, msg).unwrap();
+
+        // burn a few blocks later
+        let mut env = mock_env();
+        env.block.height = start + 5;
+        let info = mock_info(addr1.as_ref(), &[]);
+        execute(deps.as_mut(), env, info, ExecuteMsg::Burn { amount: burn }).unwrap();
+
+        let balance_at = |deps: Deps, addr: &String, height: u64| {
+            query_balance_at_height(deps, addr.clone(), height).unwrap().balance
+        };
+        let supply_at = |deps: Deps, height: u64| {
+            query_total_supply_at_height(deps, height).unwrap().total_supply
+        };
+
+        // value at height is the value at the beginning of the block
+        assert_eq!(balance_at(deps.as_ref(), &addr1, start), Uint128::zero());
+        assert_eq!(balance_at(deps.as_ref(), &addr1, start + 1), amount1);
+        assert_eq!(balance_at(deps.as_ref(), &addr1, start + 2), amount1 - transfer);
+        assert_eq!(balance_at(deps.as_ref(), &addr1, start + 6), amount1 - transfer - burn);
+        assert_eq!(balance_at(deps.as_ref(), &addr2, start + 1), Uint128::zero());
+        assert_eq!(balance_at(deps.as_ref(), &addr2, start + 2), transfer);
+        assert_eq!(balance_at(deps.as_ref(), &addr2, start + 100), transfer);
+
+        assert_eq!(supply_at(deps.as_ref(), start + 1), amount1);
+        assert_eq!(supply_at(deps.as_ref(), start + 5), amount1);
+        assert_eq!(supply_at(deps.as_ref(), start + 6), amount1 - burn);
+
+        // no history before instantiation
+        query_balance_at_height(deps.as_ref(), addr1, start - 1).unwrap_err();
+        query_total_supply_at_height(deps.as_ref(), start - 1).unwrap_err();
+    }
+
+    #[test]
+    fn migrate_starts_balance_history() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let amount1 = Uint128::from(12340000u128);
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+
+        // contracts instantiated before snapshots were introduced have no history
+        HISTORY_START_HEIGHT.remove(deps.as_mut().storage);
+
+        let mut env = mock_env();
+        env.block.height += 100;
+        migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
         assert_eq!(
-            query_token_info(deps.as_ref()).unwrap().total_supply,
-            amount1
+            HISTORY_START_HEIGHT.load(deps.as_ref().storage).unwrap(),
+            env.block.height
         );
 
-        // cannot burn more than we have
-        let info = mock_info(addr1.as_ref(), &[]);
-        let env = mock_env();
-        let msg = ExecuteMsg::Burn { amount: too_much };
-        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
-        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+        // existing balances are available from the migration height
         assert_eq!(
-            query_token_info(deps.as_ref()).unwrap().total_supply,
+            query_balance_at_height(deps.as_ref(), addr1.clone(), env.block.height)
+                .unwrap()
+                .balance,
             amount1
         );
+        query_balance_at_height(deps.as_ref(), addr1, env.block.height - 1).unwrap_err();
 
-        // valid burn reduces total supply
-        let info = mock_info(addr1.as_ref(), &[]);
-        let env = mock_env();
-        let msg = ExecuteMsg::Burn { amount: burn };
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(res.messages.len(), 0);
-
-        let remainder = amount1.checked_sub(burn).unwrap();
-        assert_eq!(get_balance(deps.as_ref(), addr1), remainder);
+        // migrating again does not move the start
+        let mut later = env.clone();
+        later.block.height += 100;
+        migrate(deps.as_mut(), later, MigrateMsg {}).unwrap();
         assert_eq!(
-            query_token_info(deps.as_ref()).unwrap().total_supply,
-            remainder
+            HISTORY_START_HEIGHT.load(deps.as_ref().storage).unwrap(),
+            env.block.height
         );
     }
 
@@ -992,6 +2398,368 @@ mod tests {
         );
     }
 
+    #[test]
+    fn transfer_tax() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let addr2 = String::from("addr0002");
+        let pair = String::from("pair");
+        let treasury = String::from("treasury");
+        let amount1 = Uint128::from(100000u128);
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+
+        // only owner can set the tax
+        let msg = ExecuteMsg::UpdateTransferTax {
+            rate: 200,
+            burn_ratio: 2500,
+            treasury: treasury.clone(),
+        };
+        let info = mock_info(addr1.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // rate is limited to 100%
+        let msg = ExecuteMsg::UpdateTransferTax {
+            rate: 10001,
+            burn_ratio: 2500,
+            treasury: treasury.clone(),
+        };
+        let info = mock_info("OWNER", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidTax {});
+
+        // 2% tax, a quarter of it is burned
+        let msg = ExecuteMsg::UpdateTransferTax {
+            rate: 200,
+            burn_ratio: 2500,
+            treasury: treasury.clone(),
+        };
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let simulation = query_simulate_transfer(
+            deps.as_ref(),
+            addr1.clone(),
+            addr2.clone(),
+            Uint128::new(10000),
+        )
+            .unwrap();
+        assert_eq!(
+            simulation,
+            SimulateTransferResponse {
+                gross: Uint128::new(10000),
+                fee: Uint128::new(200),
+                burn: Uint128::new(50),
+                net: Uint128::new(9800),
+            }
+        );
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: Uint128::new(10000),
+        };
+        let info = mock_info(addr1.as_ref(), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[4], attr("fee", "200"));
+        assert_eq!(res.attributes[5], attr("burn", "50"));
+
+        assert_eq!(get_balance(deps.as_ref(), &addr1), Uint128::new(90000));
+        assert_eq!(get_balance(deps.as_ref(), &addr2), Uint128::new(9800));
+        assert_eq!(get_balance(deps.as_ref(), &treasury), Uint128::new(150));
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(99950)
+        );
+
+        // transfers to exempt addresses are not taxed
+        let msg = ExecuteMsg::UpdateTaxExempt {
+            add: vec![pair.clone()],
+            remove: vec![],
+        };
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let simulation = query_simulate_transfer(
+            deps.as_ref(),
+            addr1.clone(),
+            pair.clone(),
+            Uint128::new(10000),
+        )
+            .unwrap();
+        assert_eq!(simulation.fee, Uint128::zero());
+        assert_eq!(simulation.net, Uint128::new(10000));
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: pair.clone(),
+            amount: Uint128::new(10000),
+        };
+        let info = mock_info(addr1.as_ref(), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes.len(), 4);
+        assert_eq!(get_balance(deps.as_ref(), &pair), Uint128::new(10000));
+
+        // neither from them
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: Uint128::new(5000),
+        };
+        let info = mock_info(pair.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), &addr2), Uint128::new(14800));
+        assert_eq!(get_balance(deps.as_ref(), &treasury), Uint128::new(150));
+
+        // tax can be disabled
+        let msg = ExecuteMsg::UpdateTransferTax {
+            rate: 0,
+            burn_ratio: 0,
+            treasury,
+        };
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let simulation = query_simulate_transfer(
+            deps.as_ref(),
+            addr1,
+            addr2,
+            Uint128::new(10000),
+        )
+            .unwrap();
+        assert_eq!(simulation.net, Uint128::new(10000));
+    }
+
+    #[test]
+    fn send_with_tax() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let contract = String::from("addr0002");
+        let treasury = String::from("treasury");
+        let send_msg = Binary::from(r#"{"some":123}"#.as_bytes());
+
+        do_instantiate(deps.as_mut(), &addr1, Uint128::new(100000));
+
+        let msg = ExecuteMsg::UpdateTransferTax {
+            rate: 100,
+            burn_ratio: 10000,
+            treasury: treasury.clone(),
+        };
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Send {
+            contract: contract.clone(),
+            amount: Uint128::new(10000),
+            msg: send_msg.clone(),
+        };
+        let info = mock_info(addr1.as_ref(), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // the receiving contract is told about the amount it actually got
+        let binary_msg = Cw20ReceiveMsg {
+            sender: addr1.clone(),
+            amount: Uint128::new(9900),
+            msg: send_msg,
+        }
+            .into_binary()
+            .unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract.clone(),
+                msg: binary_msg,
+                funds: vec![],
+            }))
+        );
+
+        assert_eq!(get_balance(deps.as_ref(), addr1), Uint128::new(90000));
+        assert_eq!(get_balance(deps.as_ref(), contract), Uint128::new(9900));
+        // whole fee is burned
+        assert_eq!(get_balance(deps.as_ref(), treasury), Uint128::zero());
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(99900)
+        );
+    }
+
+    #[test]
+    fn batch_transfer() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let addr2 = String::from("addr0002");
+        let addr3 = String::from("addr0003");
+        let amount1 = Uint128::from(12340000u128);
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+
+        // cannot send an empty batch
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::BatchTransfer { transfers: vec![] };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::EmptyBatch {});
+
+        // invalid recipient fails the whole batch
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::BatchTransfer {
+            transfers: vec![
+                TransferItem {
+                    recipient: addr2.clone(),
+                    amount: Uint128::new(100),
+                },
+                TransferItem {
+                    recipient: String::from("x"),
+                    amount: Uint128::new(200),
+                },
+            ],
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(get_balance(deps.as_ref(), addr1.clone()), amount1);
+        assert_eq!(get_balance(deps.as_ref(), addr2.clone()), Uint128::zero());
+
+        // zero amount fails the whole batch
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::BatchTransfer {
+            transfers: vec![
+                TransferItem {
+                    recipient: addr2.clone(),
+                    amount: Uint128::new(100),
+                },
+                TransferItem {
+                    recipient: addr3.clone(),
+                    amount: Uint128::zero(),
+                },
+            ],
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidZeroAmount {});
+
+        // valid batch
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::BatchTransfer {
+            transfers: vec![
+                TransferItem {
+                    recipient: addr2.clone(),
+                    amount: Uint128::new(100),
+                },
+                TransferItem {
+                    recipient: addr3.clone(),
+                    amount: Uint128::new(200),
+                },
+                TransferItem {
+                    recipient: addr2.clone(),
+                    amount: Uint128::new(300),
+                },
+            ],
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.messages.len(), 0);
+        assert_eq!(res.events.len(), 3);
+        assert_eq!(
+            res.events[1],
+            Event::new("transfer")
+                .add_attribute("from", &addr1)
+                .add_attribute("to", &addr3)
+                .add_attribute("amount", "200")
+        );
+
+        assert_eq!(
+            get_balance(deps.as_ref(), addr1.clone()),
+            amount1.checked_sub(Uint128::new(600)).unwrap()
+        );
+        assert_eq!(get_balance(deps.as_ref(), addr2.clone()), Uint128::new(400));
+        assert_eq!(get_balance(deps.as_ref(), addr3.clone()), Uint128::new(200));
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            amount1
+        );
+
+        // cannot send more than we have in total
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::BatchTransfer {
+            transfers: vec![
+                TransferItem {
+                    recipient: addr2,
+                    amount: Uint128::new(100),
+                },
+                TransferItem {
+                    recipient: addr3,
+                    amount: amount1,
+                },
+            ],
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+    }
+
+    #[test]
+    fn batch_send() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let contract1 = String::from("contract0001");
+        let contract2 = String::from("contract0002");
+        let amount1 = Uint128::from(12340000u128);
+        let send_msg = Binary::from(r#"{"some":123}"#.as_bytes());
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::BatchSend {
+            sends: vec![
+                SendItem {
+                    contract: contract1.clone(),
+                    amount: Uint128::new(100),
+                    msg: send_msg.clone(),
+                },
+                SendItem {
+                    contract: contract2.clone(),
+                    amount: Uint128::new(200),
+                    msg: Binary::default(),
+                },
+            ],
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.events.len(), 2);
+        assert_eq!(res.messages.len(), 2);
+
+        // every contract gets its own receive message
+        let binary_msg = Cw20ReceiveMsg {
+            sender: addr1.clone(),
+            amount: Uint128::new(100),
+            msg: send_msg,
+        }
+            .into_binary()
+            .unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract1.clone(),
+                msg: binary_msg,
+                funds: vec![],
+            }))
+        );
+        let binary_msg = Cw20ReceiveMsg {
+            sender: addr1.clone(),
+            amount: Uint128::new(200),
+            msg: Binary::default(),
+        }
+            .into_binary()
+            .unwrap();
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract2.clone(),
+                msg: binary_msg,
+                funds: vec![],
+            }))
+        );
+
+        assert_eq!(
+            get_balance(deps.as_ref(), addr1),
+            amount1.checked_sub(Uint128::new(300)).unwrap()
+        );
+        assert_eq!(get_balance(deps.as_ref(), contract1), Uint128::new(100));
+        assert_eq!(get_balance(deps.as_ref(), contract2), Uint128::new(200));
+    }
+
     mod marketing {
         use super::*;
 
@@ -1004,6 +2772,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1058,6 +2827,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1111,6 +2881,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1164,6 +2935,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1217,6 +2989,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1270,6 +3043,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1323,6 +3097,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1380,6 +3155,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1424,6 +3200,46 @@ mod tests {
             );
         }
 
+        #[test]
+        fn update_logo_by_operator() {
+            let mut deps = mock_dependencies(&[]);
+            let instantiate_msg = InstantiateMsg {
+                owner: "OWNER".to_string(),
+                name: "Cash Token".to_string(),
+                symbol: "CASH".to_string(),
+                decimals: 9,
+                initial_balances: vec![],
+                mint: None,
+                marketing: Some(InstantiateMarketingInfo {
+                    project: Some("Project".to_owned()),
+                    description: Some("Description".to_owned()),
+                    marketing: Some("marketing".to_owned()),
+                    logo: Some(Logo::Url("url".to_owned())),
+                }),
+            };
+
+            let info = mock_info("creator", &[]);
+
+            instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+
+            let msg = ExecuteMsg::UploadLogo(Logo::Url("new_url".to_owned()));
+            let err = execute(deps.as_mut(), mock_env(), mock_info("operator", &[]), msg.clone())
+                .unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+
+            let grant = ExecuteMsg::GrantRole {
+                role: Role::Operator,
+                address: "operator".to_owned(),
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), grant).unwrap();
+            execute(deps.as_mut(), mock_env(), mock_info("operator", &[]), msg).unwrap();
+
+            assert_eq!(
+                query_marketing_info(deps.as_ref()).unwrap().logo,
+                Some(LogoInfo::Url("new_url".to_owned()))
+            );
+        }
+
         #[test]
         fn update_logo_url() {
             let mut deps = mock_dependencies(&[]);
@@ -1433,6 +3249,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1482,6 +3299,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1532,6 +3350,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1583,6 +3402,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1633,6 +3453,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1690,6 +3511,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1740,6 +3562,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -0,0 +1,288 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128};
+
+use crate::contract::assert_history_available;
+use crate::error::ContractError;
+use crate::msg::{DelegationResponse, VotingPowerResponse};
+use crate::state::{BALANCES, DELEGATED_POWER, DELEGATIONS, TOKEN_INFO};
+
+pub fn execute_delegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    delegatee: String,
+) -> Result<Response, ContractError> {
+    let delegatee_addr = deps.api.addr_validate(&delegatee)?;
+    let height = env.block.height;
+
+    let balance = BALANCES
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+
+    // take the voting power back from the previous delegatee
+    if let Some(previous) = DELEGATIONS.may_load(deps.storage, &info.sender)? {
+        sub_delegated_power(deps.storage, &previous, balance, height)?;
+    }
+
+    if delegatee_addr == info.sender {
+        DELEGATIONS.remove(deps.storage, &info.sender, height)?;
+    } else {
+        DELEGATIONS.save(deps.storage, &info.sender, &delegatee_addr, height)?;
+        add_delegated_power(deps.storage, &delegatee_addr, balance, height)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "delegate")
+        .add_attribute("from", info.sender)
+        .add_attribute("to", delegatee))
+}
+
+/// Follows an increase of the holder's balance with the voting power of its delegatee
+pub fn increase_voting_power(
+    storage: &mut dyn Storage,
+    holder: &Addr,
+    amount: Uint128,
+    height: u64,
+) -> StdResult<()> {
+    match DELEGATIONS.may_load(storage, holder)? {
+        Some(delegatee) => add_delegated_power(storage, &delegatee, amount, height),
+        None => Ok(()),
+    }
+}
+
+/// Follows a decrease of the holder's balance with the voting power of its delegatee
+pub fn decrease_voting_power(
+    storage: &mut dyn Storage,
+    holder: &Addr,
+    amount: Uint128,
+    height: u64,
+) -> StdResult<()> {
+    match DELEGATIONS.may_load(storage, holder)? {
+        Some(delegatee) => sub_delegated_power(storage, &delegatee, amount, height),
+        None => Ok(()),
+    }
+}
+
+fn add_delegated_power(
+    storage: &mut dyn Storage,
+    delegatee: &Addr,
+    amount: Uint128,
+    height: u64,
+) -> StdResult<()> {
+    DELEGATED_POWER.update(storage, delegatee, height, |power| -> StdResult<_> {
+        Ok(power.unwrap_or_default() + amount)
+    })?;
+    Ok(())
+}
+
+fn sub_delegated_power(
+    storage: &mut dyn Storage,
+    delegatee: &Addr,
+    amount: Uint128,
+    height: u64,
+) -> StdResult<()> {
+    DELEGATED_POWER.update(storage, delegatee, height, |power| -> StdResult<_> {
+        Ok(power.unwrap_or_default().checked_sub(amount)?)
+    })?;
+    Ok(())
+}
+
+pub fn query_voting_power(
+    deps: Deps,
+    address: String,
+    height: Option<u64>,
+) -> StdResult<VotingPowerResponse> {
+    let address = deps.api.addr_validate(&address)?;
+
+    let (delegation, balance, delegated) = match height {
+        Some(height) => {
+            assert_history_available(deps, height)?;
+            (
+                DELEGATIONS.may_load_at_height(deps.storage, &address, height)?,
+                BALANCES.may_load_at_height(deps.storage, &address, height)?,
+                DELEGATED_POWER.may_load_at_height(deps.storage, &address, height)?,
+            )
+        }
+        None => (
+            DELEGATIONS.may_load(deps.storage, &address)?,
+            BALANCES.may_load(deps.storage, &address)?,
+            DELEGATED_POWER.may_load(deps.storage, &address)?,
+        ),
+    };
+
+    // own balance counts only if it is not delegated away
+    let own = match delegation {
+        Some(_) => Uint128::zero(),
+        None => balance.unwrap_or_default(),
+    };
+    Ok(VotingPowerResponse {
+        power: own + delegated.unwrap_or_default(),
+    })
+}
+
+pub fn query_delegation(deps: Deps, address: String) -> StdResult<DelegationResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let delegatee = DELEGATIONS
+        .may_load(deps.storage, &address)?
+        .unwrap_or(address);
+    Ok(DelegationResponse {
+        delegatee: delegatee.into(),
+    })
+}
+
+pub fn query_total_voting_power(deps: Deps, height: Option<u64>) -> StdResult<VotingPowerResponse> {
+    let token_info = match height {
+        Some(height) => {
+            assert_history_available(deps, height)?;
+            TOKEN_INFO.may_load_at_height(deps.storage, height)?
+        }
+        None => TOKEN_INFO.may_load(deps.storage)?,
+    };
+    Ok(VotingPowerResponse {
+        power: token_info.map(|info| info.total_supply).unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coins, DepsMut};
+    use cw20::Cw20Coin;
+
+    use crate::contract::{execute, instantiate};
+    use crate::msg::{ExecuteMsg, InstantiateMsg};
+
+    fn do_instantiate(deps: DepsMut, balances: Vec<(&str, u128)>) {
+        // alice burns own tokens
+        let instantiate_msg = InstantiateMsg {
+            owner: "alice".to_string(),
+            name: "Auto Gen".to_string(),
+            symbol: "AUTO".to_string(),
+            decimals: 3,
+            initial_balances: balances
+                .into_iter()
+                .map(|(address, amount)| Cw20Coin {
+                    address: address.into(),
+                    amount: Uint128::new(amount),
+                })
+                .collect(),
+            mint: None,
+            marketing: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps, mock_env(), info, instantiate_msg).unwrap();
+    }
+
+    fn power(deps: Deps, address: &str, height: Option<u64>) -> u128 {
+        query_voting_power(deps, address.to_string(), height)
+            .unwrap()
+            .power
+            .u128()
+    }
+
+    #[test]
+    fn delegation_follows_balance() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+        do_instantiate(deps.as_mut(), vec![("alice", 1000), ("bob", 500)]);
+        let mut env = mock_env();
+
+        // everybody votes with own balance by default
+        assert_eq!(power(deps.as_ref(), "alice", None), 1000);
+        assert_eq!(
+            query_delegation(deps.as_ref(), "alice".to_string()).unwrap().delegatee,
+            "alice"
+        );
+
+        env.block.height += 1;
+        let msg = ExecuteMsg::Delegate {
+            delegatee: "carol".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(power(deps.as_ref(), "alice", None), 0);
+        assert_eq!(power(deps.as_ref(), "carol", None), 1000);
+        assert_eq!(
+            query_delegation(deps.as_ref(), "alice".to_string()).unwrap().delegatee,
+            "carol"
+        );
+
+        // incoming transfer increases the delegated power
+        env.block.height += 1;
+        let msg = ExecuteMsg::Transfer {
+            recipient: "alice".to_string(),
+            amount: Uint128::new(200),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), msg).unwrap();
+        assert_eq!(power(deps.as_ref(), "bob", None), 300);
+        assert_eq!(power(deps.as_ref(), "carol", None), 1200);
+
+        // burn decreases it
+        env.block.height += 1;
+        let msg = ExecuteMsg::Burn {
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(power(deps.as_ref(), "carol", None), 1100);
+
+        // re-delegation moves the whole balance
+        env.block.height += 1;
+        let msg = ExecuteMsg::Delegate {
+            delegatee: "bob".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(power(deps.as_ref(), "carol", None), 0);
+        assert_eq!(power(deps.as_ref(), "bob", None), 1400);
+
+        // delegating to self takes the power back
+        env.block.height += 1;
+        let msg = ExecuteMsg::Delegate {
+            delegatee: "alice".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(power(deps.as_ref(), "alice", None), 1100);
+        assert_eq!(power(deps.as_ref(), "bob", None), 300);
+        assert_eq!(
+            query_delegation(deps.as_ref(), "alice".to_string()).unwrap().delegatee,
+            "alice"
+        );
+
+        // total voting power is the supply
+        let total = query_total_voting_power(deps.as_ref(), None).unwrap();
+        assert_eq!(total.power, Uint128::new(1400));
+    }
+
+    #[test]
+    fn voting_power_at_height() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+        do_instantiate(deps.as_mut(), vec![("alice", 1000)]);
+        let start = mock_env().block.height;
+
+        let mut env = mock_env();
+        env.block.height = start + 1;
+        let msg = ExecuteMsg::Delegate {
+            delegatee: "carol".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+
+        env.block.height = start + 2;
+        let msg = ExecuteMsg::Burn {
+            amount: Uint128::new(400),
+        };
+        execute(deps.as_mut(), env, mock_info("alice", &[]), msg).unwrap();
+
+        // values are taken at the beginning of the block
+        assert_eq!(power(deps.as_ref(), "alice", Some(start + 1)), 1000);
+        assert_eq!(power(deps.as_ref(), "carol", Some(start + 1)), 0);
+        assert_eq!(power(deps.as_ref(), "alice", Some(start + 2)), 0);
+        assert_eq!(power(deps.as_ref(), "carol", Some(start + 2)), 1000);
+        assert_eq!(power(deps.as_ref(), "carol", Some(start + 3)), 600);
+
+        let total = query_total_voting_power(deps.as_ref(), Some(start + 2)).unwrap();
+        assert_eq!(total.power, Uint128::new(1000));
+        let total = query_total_voting_power(deps.as_ref(), Some(start + 3)).unwrap();
+        assert_eq!(total.power, Uint128::new(600));
+
+        // no history before instantiation
+        query_voting_power(deps.as_ref(), "alice".to_string(), Some(start - 1)).unwrap_err();
+    }
+}
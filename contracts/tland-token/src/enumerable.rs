@@ -1,7 +1,8 @@
 use cosmwasm_std::{Deps, Order, StdResult};
 use cw20::{AllAccountsResponse, AllAllowancesResponse, AllowanceInfo};
 
-use crate::state::{ALLOWANCES, BALANCES};
+use crate::msg::BlockedAccountsResponse;
+use crate::state::{ALLOWANCES, BALANCES, BLOCKED};
 use cw_storage_plus::Bound;
 
 // settings for pagination
@@ -44,10 +45,13 @@ pub fn query_all_accounts(
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(Bound::exclusive);
 
-    let accounts: Result<Vec<_>, _> = BALANCES
-        .keys(deps.storage, start, None, Order::Ascending)
-        .map(String::from_utf8)
+    let accounts: StdResult<Vec<_>> = BALANCES
+        .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
+        .map(|item| {
+            let (k, _) = item?;
+            Ok(String::from_utf8(k)?)
+        })
         .collect();
 
     Ok(AllAccountsResponse {
@@ -55,6 +59,25 @@ pub fn query_all_accounts(
     })
 }
 
+pub fn query_blocked_accounts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<BlockedAccountsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let accounts: StdResult<Vec<_>> = BLOCKED
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|k| Ok(String::from_utf8(k)?))
+        .collect();
+
+    Ok(BlockedAccountsResponse {
+        accounts: accounts?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,6 +100,7 @@ mod tests {
                 address: addr.into(),
                 amount,
             }],
+            mint: None,
             marketing: None,
         };
         let info = mock_info("creator", &[]);
@@ -212,4 +236,27 @@ mod tests {
                 .unwrap();
         assert_eq!(accounts.accounts, expected_order[3..].to_vec());
     }
+
+    #[test]
+    fn query_blocked_accounts_works() {
+        let mut deps = mock_dependencies(&[]);
+        do_instantiate(deps.as_mut(), "acct01", Uint128::new(12340000));
+
+        let accounts = query_blocked_accounts(deps.as_ref(), None, None).unwrap();
+        assert_eq!(accounts.accounts, Vec::<String>::new());
+
+        let info = mock_info("OWNER", &[]);
+        let msg = ExecuteMsg::UpdateBlocklist {
+            add: vec!["zebra".to_string(), "nice".to_string(), "aaaardvark".to_string()],
+            remove: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let accounts = query_blocked_accounts(deps.as_ref(), None, Some(2)).unwrap();
+        assert_eq!(accounts.accounts, vec!["aaaardvark", "nice"]);
+
+        let accounts =
+            query_blocked_accounts(deps.as_ref(), Some("nice".to_string()), None).unwrap();
+        assert_eq!(accounts.accounts, vec!["zebra"]);
+    }
 }
@@ -1,4 +1,6 @@
 use cosmwasm_std::{OverflowError, StdError};
+use ownership::OwnershipError;
+use roles::RolesError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -9,21 +11,48 @@ pub enum ContractError {
     #[error("{0}")]
     Overflow(#[from] OverflowError),
 
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
+
+    #[error("{0}")]
+    Roles(#[from] RolesError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Token transfers are paused")]
+    Paused {},
+
+    #[error("Address {address} is frozen")]
+    AddressFrozen { address: String },
+
     #[error("Cannot set to own account")]
     CannotSetOwnAccount {},
 
     #[error("Invalid zero amount")]
     InvalidZeroAmount {},
 
+    #[error("Batch cannot be empty")]
+    EmptyBatch {},
+
+    #[error("Minting cannot exceed the cap")]
+    CannotExceedCap {},
+
+    #[error("Tax rate and burn ratio cannot exceed 10000 basis points")]
+    InvalidTax {},
+
     #[error("Allowance is expired")]
     Expired {},
 
     #[error("No allowance for this account")]
     NoAllowance {},
 
+    #[error("Invalid permit signature")]
+    InvalidSignature {},
+
+    #[error("Invalid permit nonce, expected {expected}")]
+    InvalidNonce { expected: u64 },
+
     #[error("Logo binary data exceeds 5KB limit")]
     LogoTooBig {},
 
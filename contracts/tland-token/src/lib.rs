@@ -1,8 +1,10 @@
 pub mod allowances;
 pub mod contract;
+pub mod delegation;
 pub mod enumerable;
 mod error;
 pub mod msg;
+pub mod permit;
 pub mod state;
 
 pub use crate::error::ContractError;
@@ -1,8 +1,9 @@
 use cosmwasm_std::{StdError, StdResult, Uint128, Binary};
-use cw20::{Cw20Coin, Logo};
+use cw20::{Cw20Coin, Logo, MinterResponse};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use cw0::Expiration;
+use roles::Role;
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMarketingInfo {
@@ -19,10 +20,15 @@ pub struct InstantiateMsg {
     pub symbol: String,
     pub decimals: u8,
     pub initial_balances: Vec<Cw20Coin>,
+    pub mint: Option<MinterResponse>,
     pub marketing: Option<InstantiateMarketingInfo>,
 }
 
 impl InstantiateMsg {
+    pub fn get_cap(&self) -> Option<Uint128> {
+        self.mint.as_ref().and_then(|v| v.cap)
+    }
+
     pub fn validate(&self) -> StdResult<()> {
         // Check name, symbol, decimals
         if !is_valid_name(&self.name) {
@@ -67,16 +73,71 @@ fn is_valid_symbol(symbol: &str) -> bool {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    /// Returns the proposed owner and when the proposal expires.
+    /// Return type: PendingOwnerResponse.
+    PendingOwner {},
+    /// Returns addresses holding the given role. Supports pagination.
+    /// Return type: RoleMembersResponse.
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns whether token transfers are paused, when it was toggled and who can still
+    /// move tokens.
+    /// Return type: PauseInfoResponse.
+    PauseInfo {},
     /// Returns the current balance of the given address, 0 if unset.
     /// Return type: BalanceResponse.
     Balance { address: String },
+    /// Returns the balance of the given address at the beginning of the block at given height,
+    /// 0 if unset. Errors for heights before the history was started.
+    /// Return type: BalanceResponse.
+    BalanceAtHeight { address: String, height: u64 },
+    /// Returns frozen accounts. Supports pagination.
+    /// Return type: BlockedAccountsResponse.
+    BlockedAccounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns how a transfer of the given amount would be split between the recipient,
+    /// the treasury and the burn.
+    /// Return type: SimulateTransferResponse.
+    SimulateTransfer {
+        sender: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    /// Returns the voting power of the given address: its own balance, unless delegated away,
+    /// plus balances delegated to it. Height works as in `BalanceAtHeight`, current if not set.
+    /// Return type: VotingPowerResponse.
+    VotingPower {
+        address: String,
+        height: Option<u64>,
+    },
+    /// Returns who votes with the balance of the given address.
+    /// Return type: DelegationResponse.
+    Delegation { address: String },
+    /// Returns the sum of all voting power, equal to the total supply.
+    /// Return type: VotingPowerResponse.
+    TotalVotingPower { height: Option<u64> },
     /// Returns metadata on the contract - name, decimals, supply, etc.
     /// Return type: TokenInfoResponse.
     TokenInfo {},
+    /// Returns the total supply at the beginning of the block at given height.
+    /// Return type: TotalSupplyResponse.
+    TotalSupplyAtHeight { height: u64 },
+    /// Only with "mintable" extension.
+    /// Returns who can mint and the hard cap on maximum tokens after minting.
+    /// Return type: MinterResponse.
+    Minter {},
     /// Only with "allowance" extension.
     /// Returns how much spender can use from owner account, 0 if unset.
     /// Return type: AllowanceResponse.
     Allowance { owner: String, spender: String },
+    /// Returns the nonce the next permit of the owner has to be signed with.
+    /// Return type: NonceResponse.
+    Nonce { owner: String },
     /// Only with "enumerable" extension (and "allowances")
     /// Returns all allowances this owner has approved. Supports pagination.
     /// Return type: AllAllowancesResponse.
@@ -95,6 +156,7 @@ pub enum QueryMsg {
     /// Only with "marketing" extension
     /// Returns more metadata on the contract to display in the client:
     /// - description, logo, project url, etc.
+    ///
     /// Return type: MarketingInfoResponse
     MarketingInfo {},
     /// Only with "marketing" extension
@@ -104,19 +166,149 @@ pub enum QueryMsg {
     DownloadLogo {},
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransferItem {
+    pub recipient: String,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SendItem {
+    pub contract: String,
+    pub amount: Uint128,
+    pub msg: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TotalSupplyResponse {
+    pub total_supply: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VotingPowerResponse {
+    pub power: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NonceResponse {
+    pub nonce: u64,
+}
+
+/// Message signed by the owner to approve a `Permit`. Fields are kept in alphabetical
+/// order, so the compact JSON serialization is canonical.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitPayload {
+    pub amount: Uint128,
+    pub chain_id: String,
+    pub contract: String,
+    pub expires: Expiration,
+    pub nonce: u64,
+    pub owner: String,
+    pub spender: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DelegationResponse {
+    /// The address itself when not delegated
+    pub delegatee: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PauseInfoResponse {
+    pub paused: bool,
+    pub updated_at: u64,
+    pub allowlist: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BlockedAccountsResponse {
+    pub accounts: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateTransferResponse {
+    /// Amount taken from the sender
+    pub gross: Uint128,
+    /// Whole tax, including the burned part
+    pub fee: Uint128,
+    /// Part of the tax which is burned
+    pub burn: Uint128,
+    /// Amount received by the recipient
+    pub net: Uint128,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct MigrateMsg {}
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    UpdateConfig {
-        owner: Option<String>,
+    /// Only owner. Proposes a new owner, who has to accept the ownership before it expires.
+    /// The current owner keeps control until then.
+    ProposeOwner {
+        new_owner: String,
+        expires: Option<ownership::Expiration>,
+    },
+    /// Only pending owner. Takes over the ownership.
+    AcceptOwnership {},
+    /// Only owner or pending owner. Drops the ownership proposal.
+    CancelOwnershipProposal {},
+    /// Only owner. Leaves the contract without an owner, forever.
+    RenounceOwnership {},
+    /// Only admin. Gives the role to the address.
+    GrantRole { role: Role, address: String },
+    /// Only admin. Takes the role from the address.
+    RevokeRole { role: Role, address: String },
+    /// Only pauser. Stops all token movements except for addresses from the pause allowlist
+    Pause {},
+    /// Only pauser. Resumes token movements
+    Unpause {},
+    /// Only pauser. Adds or removes addresses which can move tokens while the contract is paused
+    UpdatePauseAllowlist {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Only compliance. Freezes or unfreezes accounts. Frozen accounts cannot send,
+    /// receive, spend or approve tokens.
+    UpdateBlocklist {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Only compliance. Moves tokens out of any account, frozen ones included,
+    /// without an allowance. Meant for court-ordered recovery.
+    ForceTransfer {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    /// Only admin. Sets the transfer tax. Rate is in basis points of the transferred amount, burn ratio
+    /// in basis points of the tax. The part of the tax which is not burned goes to the treasury.
+    /// Rate 0 disables the tax.
+    UpdateTransferTax {
+        rate: u64,
+        burn_ratio: u64,
+        treasury: String,
+    },
+    /// Only admin. Adds or removes addresses which neither pay nor trigger the transfer tax
+    UpdateTaxExempt {
+        add: Vec<String>,
+        remove: Vec<String>,
     },
     /// Transfer is a base message to move tokens to another account without triggering actions
     Transfer { recipient: String, amount: Uint128 },
-    /// Burn is a base message to destroy tokens forever
+    /// Moves tokens to many accounts at once. Fails if any of the transfers fails.
+    BatchTransfer { transfers: Vec<TransferItem> },
+    /// Only treasurer. Burn is a base message to destroy tokens forever
     Burn { amount: Uint128 },
+    /// Only with the "mintable" extension. If authorized, creates amount new tokens
+    /// and adds to the recipient balance.
+    Mint { recipient: String, amount: Uint128 },
+    /// Only with the "mintable" extension. If authorized by an admin, replaces the minter
+    /// and the cap. Setting minter to None disables minting.
+    UpdateMinter {
+        minter: Option<String>,
+        cap: Option<Uint128>,
+    },
     /// Send is a base message to transfer tokens to a contract and trigger an action
     /// on the receiving contract.
     Send {
@@ -124,6 +316,12 @@ pub enum ExecuteMsg {
         amount: Uint128,
         msg: Binary,
     },
+    /// Delegates voting power of the sender's whole balance, including future changes.
+    /// Delegating to self takes the voting power back.
+    Delegate { delegatee: String },
+    /// Sends tokens to many contracts at once, each one gets its own `Cw20ReceiveMsg`.
+    /// Fails if any of the sends fails.
+    BatchSend { sends: Vec<SendItem> },
     /// Only with "approval" extension. Allows spender to access an additional amount tokens
     /// from the owner's (env.sender) account. If expires is Some(), overwrites current allowance
     /// expiration with this one.
@@ -140,6 +338,19 @@ pub enum ExecuteMsg {
         amount: Uint128,
         expires: Option<Expiration>,
     },
+    /// Sets the spender's allowance on the owner's account to amount, approved off-chain.
+    /// Signature is a 64 bytes secp256k1 signature of the sha256 hash of the `PermitPayload`
+    /// JSON, made with the owner's key. Can be submitted by anybody, e.g. a relayer.
+    Permit {
+        owner: String,
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+        nonce: u64,
+        /// Compressed secp256k1 public key of the owner
+        pubkey: Binary,
+        signature: Binary,
+    },
     /// Only with "approval" extension. Transfers amount tokens from owner -> recipient
     /// if `env.sender` has sufficient pre-approval.
     TransferFrom {
@@ -170,7 +381,7 @@ pub enum ExecuteMsg {
     },
     /// If set as the "marketing" role on the contract, upload a new URL, SVG, or PNG for the token
     UploadLogo(Logo),
-    /// Withdraw locked funds
+    /// Only treasurer. Withdraw locked funds
     WithdrawLockedFunds {
         denom: String,
         amount: Uint128,
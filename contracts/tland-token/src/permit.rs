@@ -0,0 +1,275 @@
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::{attr, to_vec, Binary, Deps, DepsMut, Env, Response, StdResult, Uint128};
+use cw20::{AllowanceResponse, Expiration};
+use ripemd160::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use crate::contract::assert_not_frozen;
+use crate::error::ContractError;
+use crate::msg::{NonceResponse, PermitPayload};
+use crate::state::{ALLOWANCES, NONCES};
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_permit(
+    deps: DepsMut,
+    env: Env,
+    owner: String,
+    spender: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+    nonce: u64,
+    pubkey: Binary,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if spender_addr == owner_addr {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+    assert_not_frozen(deps.storage, &[&owner_addr, &spender_addr])?;
+
+    let expected = NONCES.may_load(deps.storage, &owner_addr)?.unwrap_or_default();
+    if nonce != expected {
+        return Err(ContractError::InvalidNonce { expected });
+    }
+
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+
+    // the key has to belong to the owner
+    if !is_owner_pubkey(&owner, &pubkey) {
+        return Err(ContractError::InvalidSignature {});
+    }
+
+    let payload = PermitPayload {
+        amount,
+        chain_id: env.block.chain_id,
+        contract: env.contract.address.into(),
+        expires,
+        nonce,
+        owner: owner.clone(),
+        spender: spender.clone(),
+    };
+    let hash = Sha256::digest(&to_vec(&payload)?);
+    let verified = deps
+        .api
+        .secp256k1_verify(&hash, &signature, &pubkey)
+        .unwrap_or(false);
+    if !verified {
+        return Err(ContractError::InvalidSignature {});
+    }
+
+    NONCES.save(deps.storage, &owner_addr, &(nonce + 1))?;
+    ALLOWANCES.save(
+        deps.storage,
+        (&owner_addr, &spender_addr),
+        &AllowanceResponse {
+            allowance: amount,
+            expires,
+        },
+    )?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "permit"),
+        attr("owner", owner),
+        attr("spender", spender),
+        attr("amount", amount),
+        attr("nonce", nonce.to_string()),
+    ]);
+    Ok(res)
+}
+
+/// Checks the owner's address is derived from the public key, as it is done for
+/// Cosmos SDK accounts: bech32(ripemd160(sha256(pubkey))) with the owner's prefix
+fn is_owner_pubkey(owner: &str, pubkey: &[u8]) -> bool {
+    let hrp = match bech32::decode(owner) {
+        Ok((hrp, _, Variant::Bech32)) => hrp,
+        _ => return false,
+    };
+    let hash = Ripemd160::digest(&Sha256::digest(pubkey));
+    match bech32::encode(&hrp, hash.to_base32(), Variant::Bech32) {
+        Ok(address) => address == owner,
+        Err(_) => false,
+    }
+}
+
+pub fn query_nonce(deps: Deps, owner: String) -> StdResult<NonceResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let nonce = NONCES.may_load(deps.storage, &owner_addr)?.unwrap_or_default();
+    Ok(NonceResponse { nonce })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coins, DepsMut};
+    use cw20::Cw20Coin;
+    use k256::ecdsa::signature::Signer;
+    use k256::ecdsa::{Signature, SigningKey};
+
+    use crate::allowances::query_allowance;
+    use crate::contract::{execute, instantiate, query_balance};
+    use crate::msg::{ExecuteMsg, InstantiateMsg};
+
+    const SPENDER: &str = "spender";
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32]).unwrap()
+    }
+
+    fn pubkey(key: &SigningKey) -> Binary {
+        Binary::from(key.verifying_key().to_bytes().as_slice())
+    }
+
+    fn address(key: &SigningKey) -> String {
+        let hash = Ripemd160::digest(&Sha256::digest(&pubkey(key)));
+        bech32::encode("terra", hash.to_base32(), Variant::Bech32).unwrap()
+    }
+
+    fn sign(key: &SigningKey, payload: &PermitPayload) -> Binary {
+        let signature: Signature = key.sign(&to_vec(payload).unwrap());
+        Binary::from(signature.as_ref())
+    }
+
+    fn permit_payload(owner: &str, amount: u128, nonce: u64) -> PermitPayload {
+        let env = mock_env();
+        PermitPayload {
+            amount: Uint128::new(amount),
+            chain_id: env.block.chain_id,
+            contract: env.contract.address.into(),
+            expires: Expiration::Never {},
+            nonce,
+            owner: owner.to_string(),
+            spender: SPENDER.to_string(),
+        }
+    }
+
+    fn permit_msg(payload: &PermitPayload, key: &SigningKey, signature: Binary) -> ExecuteMsg {
+        ExecuteMsg::Permit {
+            owner: payload.owner.clone(),
+            spender: payload.spender.clone(),
+            amount: payload.amount,
+            expires: Some(payload.expires),
+            nonce: payload.nonce,
+            pubkey: pubkey(key),
+            signature,
+        }
+    }
+
+    fn do_instantiate(mut deps: DepsMut, addr: &str, amount: Uint128) {
+        let instantiate_msg = InstantiateMsg {
+            owner: "OWNER".to_string(),
+            name: "Auto Gen".to_string(),
+            symbol: "AUTO".to_string(),
+            decimals: 3,
+            initial_balances: vec![Cw20Coin {
+                address: addr.into(),
+                amount,
+            }],
+            mint: None,
+            marketing: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.branch(), mock_env(), info, instantiate_msg).unwrap();
+    }
+
+    #[test]
+    fn permit_sets_allowance() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+        let key = signing_key(1);
+        let owner = address(&key);
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(1000));
+
+        assert_eq!(query_nonce(deps.as_ref(), owner.clone()).unwrap().nonce, 0);
+
+        // anybody can submit the permit
+        let payload = permit_payload(&owner, 300, 0);
+        let msg = permit_msg(&payload, &key, sign(&key, &payload));
+        execute(deps.as_mut(), mock_env(), mock_info("relayer", &[]), msg.clone()).unwrap();
+
+        let allowance = query_allowance(deps.as_ref(), owner.clone(), SPENDER.to_string()).unwrap();
+        assert_eq!(allowance.allowance, Uint128::new(300));
+        assert_eq!(query_nonce(deps.as_ref(), owner.clone()).unwrap().nonce, 1);
+
+        // permit cannot be replayed
+        let err = execute(deps.as_mut(), mock_env(), mock_info("relayer", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidNonce { expected: 1 });
+
+        // next permit overwrites the allowance
+        let payload = permit_payload(&owner, 100, 1);
+        let msg = permit_msg(&payload, &key, sign(&key, &payload));
+        execute(deps.as_mut(), mock_env(), mock_info("relayer", &[]), msg).unwrap();
+        let allowance = query_allowance(deps.as_ref(), owner.clone(), SPENDER.to_string()).unwrap();
+        assert_eq!(allowance.allowance, Uint128::new(100));
+
+        // spender uses it
+        let msg = ExecuteMsg::TransferFrom {
+            owner: owner.clone(),
+            recipient: "recipient".to_string(),
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(SPENDER, &[]), msg).unwrap();
+        assert_eq!(
+            query_balance(deps.as_ref(), owner).unwrap().balance,
+            Uint128::new(900)
+        );
+    }
+
+    #[test]
+    fn invalid_permits_rejected() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+        let key = signing_key(1);
+        let owner = address(&key);
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(1000));
+        let info = mock_info("relayer", &[]);
+
+        // signed for another chain
+        let mut other_chain = permit_payload(&owner, 300, 0);
+        other_chain.chain_id = "other-chain".to_string();
+        let msg = permit_msg(&permit_payload(&owner, 300, 0), &key, sign(&key, &other_chain));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidSignature {});
+
+        // signed for another contract
+        let mut other_contract = permit_payload(&owner, 300, 0);
+        other_contract.contract = "other_contract".to_string();
+        let msg = permit_msg(&permit_payload(&owner, 300, 0), &key, sign(&key, &other_contract));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidSignature {});
+
+        // amount differs from the signed one
+        let signature = sign(&key, &permit_payload(&owner, 300, 0));
+        let msg = permit_msg(&permit_payload(&owner, 3000, 0), &key, signature);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidSignature {});
+
+        // valid signature made with a key of someone else
+        let other_key = signing_key(2);
+        let payload = permit_payload(&owner, 300, 0);
+        let msg = permit_msg(&payload, &other_key, sign(&other_key, &payload));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidSignature {});
+
+        // wrong nonce
+        let payload = permit_payload(&owner, 300, 5);
+        let msg = permit_msg(&payload, &key, sign(&key, &payload));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidNonce { expected: 0 });
+
+        // already expired
+        let mut expired = permit_payload(&owner, 300, 0);
+        expired.expires = Expiration::AtHeight(mock_env().block.height);
+        let msg = permit_msg(&expired, &key, sign(&key, &expired));
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Expired {});
+
+        // nothing changed
+        let allowance = query_allowance(deps.as_ref(), owner.clone(), SPENDER.to_string()).unwrap();
+        assert_eq!(allowance.allowance, Uint128::zero());
+        assert_eq!(query_nonce(deps.as_ref(), owner).unwrap().nonce, 0);
+    }
+}
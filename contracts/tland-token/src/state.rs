@@ -2,7 +2,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy};
 
 use cw20::{AllowanceResponse, Logo, MarketingInfoResponse};
 
@@ -13,16 +13,97 @@ pub struct TokenInfo {
     pub symbol: String,
     pub decimals: u8,
     pub total_supply: Uint128,
+    pub mint: Option<MinterData>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MinterData {
+    pub minter: Addr,
+    /// cap is a hard cap on total supply that can be achieved by minting.
+    pub cap: Option<Uint128>,
+}
+
+impl TokenInfo {
+    pub fn get_cap(&self) -> Option<Uint128> {
+        self.mint.as_ref().and_then(|v| v.cap)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
-    pub owner: Addr,
+    /// None once the ownership is renounced
+    pub owner: Option<Addr>,
+    /// When set, only addresses from `pause_allowlist` can move tokens
+    #[serde(default)]
+    pub paused: bool,
+    /// Block time (in seconds) of the last pause or unpause
+    #[serde(default)]
+    pub pause_updated_at: u64,
+    #[serde(default)]
+    pub pause_allowlist: Vec<Addr>,
+    /// Fee charged on transfers, disabled when not set
+    #[serde(default)]
+    pub transfer_tax: Option<TransferTax>,
+    /// Transfers from or to these addresses are not taxed
+    #[serde(default)]
+    pub tax_exempt: Vec<Addr>,
+}
+
+impl Config {
+    pub fn can_move_tokens(&self, sender: &Addr) -> bool {
+        !self.paused || self.pause_allowlist.contains(sender)
+    }
+
+    pub fn is_tax_exempt(&self, from: &Addr, to: &Addr) -> bool {
+        self.tax_exempt.contains(from) || self.tax_exempt.contains(to)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransferTax {
+    /// Fee in basis points of the transferred amount
+    pub rate: u64,
+    /// Part of the fee which is burned, in basis points of the fee. The rest goes to the treasury.
+    pub burn_ratio: u64,
+    pub treasury: Addr,
 }
 
-pub const TOKEN_INFO: Item<TokenInfo> = Item::new("token_info");
+/// Balances and total supply are checkpointed on every change. Value at a given height is the
+/// value at the beginning of that block.
+pub const TOKEN_INFO: SnapshotItem<TokenInfo> = SnapshotItem::new(
+    "token_info",
+    "token_info__checkpoints",
+    "token_info__changelog",
+    Strategy::EveryBlock,
+);
 pub const MARKETING_INFO: Item<MarketingInfoResponse> = Item::new("marketing_info");
 pub const LOGO: Item<Logo> = Item::new("logo");
-pub const BALANCES: Map<&Addr, Uint128> = Map::new("balance");
+pub const BALANCES: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "balance",
+    "balance__checkpoints",
+    "balance__changelog",
+    Strategy::EveryBlock,
+);
+/// Accounts which delegated their voting power to someone else. Accounts without an entry
+/// vote with their own balance.
+pub const DELEGATIONS: SnapshotMap<&Addr, Addr> = SnapshotMap::new(
+    "delegation",
+    "delegation__checkpoints",
+    "delegation__changelog",
+    Strategy::EveryBlock,
+);
+/// Sum of balances delegated to an account by others
+pub const DELEGATED_POWER: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "delegated_power",
+    "delegated_power__checkpoints",
+    "delegated_power__changelog",
+    Strategy::EveryBlock,
+);
+/// First height for which balance history is available
+pub const HISTORY_START_HEIGHT: Item<u64> = Item::new("history_start_height");
 pub const ALLOWANCES: Map<(&Addr, &Addr), AllowanceResponse> = Map::new("allowance");
+/// Next nonce expected in a permit signed by the owner
+pub const NONCES: Map<&Addr, u64> = Map::new("nonces");
 pub const CONFIG: Item<Config> = Item::new("config");
+/// Frozen accounts with the block time (in seconds) they were frozen at
+pub const BLOCKED: Map<&Addr, u64> = Map::new("blocked");
@@ -5,6 +5,8 @@ use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use vesting::msg::{MemberResponse, ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg, MemberListResponse};
 use vesting::state::Config as ConfigResponse;
+use ownership::PendingOwnerResponse;
+use roles::RoleMembersResponse;
 use vesting::state::State as StateResponse;
 
 fn main() {
@@ -19,6 +21,8 @@ fn main() {
     export_schema(&schema_for!(MigrateMsg), &out_dir);
 
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(PendingOwnerResponse), &out_dir);
+    export_schema(&schema_for!(RoleMembersResponse), &out_dir);
     export_schema(&schema_for!(StateResponse), &out_dir);
     export_schema(&schema_for!(MemberResponse), &out_dir);
     export_schema(&schema_for!(MemberListResponse), &out_dir);
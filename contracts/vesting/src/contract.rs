@@ -1,11 +1,14 @@
 use cosmwasm_std::{BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, SubMsg, to_binary, Uint128, WasmMsg, WasmQuery};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cw0::{maybe_addr, must_pay};
+use cw0::{Expiration, maybe_addr, must_pay};
 use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
 use cw2::{get_contract_version, set_contract_version};
 use cw_storage_plus::Bound;
 
+use ownership::{query_pending_owner};
+use roles::{is_authorized, query_role_members, Role};
+
 use crate::error::ContractError;
 use crate::msg::{ExecuteMsg, InstantiateMsg, MemberListResponse, MemberListResponseItem, MemberResponse, MemberResponseItem, MigrateMsg, QueryMsg, RegisterMemberItem};
 use crate::state::{CONFIG, Config, FeeConfig, Member, MEMBERS, State, STATE, Vesting};
@@ -24,7 +27,7 @@ pub fn instantiate(
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     let config = Config {
-        owner: deps.api.addr_validate(&msg.owner)?,
+        owner: Some(deps.api.addr_validate(&msg.owner)?),
         terraland_token: deps.api.addr_validate(&msg.terraland_token)?,
         name: msg.name,
         fee_config: msg.fee_config,
@@ -56,8 +59,15 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateConfig { owner, name, fee_config, vesting } =>
-            execute_update_config(deps, env, info, owner, name, fee_config, vesting),
+        ExecuteMsg::UpdateConfig { name, fee_config, vesting } =>
+            execute_update_config(deps, env, info, name, fee_config, vesting),
+        ExecuteMsg::ProposeOwner { new_owner, expires } =>
+            execute_propose_owner(deps, env, info, new_owner, expires),
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => execute_cancel_ownership_proposal(deps, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, info),
+        ExecuteMsg::GrantRole { role, address } => execute_grant_role(deps, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => execute_revoke_role(deps, info, role, address),
         ExecuteMsg::RegisterMembers(members) =>
             execute_register_members(deps, env, info, members),
         ExecuteMsg::Claim {} => execute_claim(deps, env, info),
@@ -72,24 +82,17 @@ pub fn execute_update_config(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    new_owner: Option<String>,
     new_name: Option<String>,
     new_fee_config: Option<Vec<FeeConfig>>,
     new_vesting: Option<Vesting>,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized admin
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Admin, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
-    let api = deps.api;
-
     CONFIG.update(deps.storage, |mut existing_config| -> StdResult<_> {
-        // update new owner if set
-        if let Some(addr) = new_owner {
-            existing_config.owner = api.addr_validate(&addr)?;
-        }
         if let Some(name) = new_name {
             existing_config.name = name;
         }
@@ -107,15 +110,82 @@ pub fn execute_update_config(
         .add_attribute("sender", info.sender))
 }
 
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_owner: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(ownership::execute_propose_owner(deps, &env, &info, &cfg.owner, new_owner, expires)?)
+}
+
+pub fn execute_accept_ownership(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let (new_owner, res) = ownership::execute_accept_ownership(deps.branch(), &env, &info)?;
+
+    CONFIG.update(deps.storage, |mut existing_config| -> StdResult<_> {
+        existing_config.owner = Some(new_owner);
+        Ok(existing_config)
+    })?;
+
+    Ok(res)
+}
+
+pub fn execute_cancel_ownership_proposal(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(ownership::execute_cancel_ownership_proposal(deps, &info, &cfg.owner)?)
+}
+
+pub fn execute_renounce_ownership(
+    mut deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    let res = ownership::execute_renounce_ownership(deps.branch(), &info, &cfg.owner)?;
+
+    cfg.owner = None;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(res)
+}
+
+pub fn execute_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(roles::execute_grant_role(deps, &info, &cfg.owner, role, address)?)
+}
+
+pub fn execute_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(roles::execute_revoke_role(deps, &info, &cfg.owner, role, address)?)
+}
+
 pub fn execute_register_members(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     members: Vec<RegisterMemberItem>,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized operator
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Operator, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -190,9 +260,9 @@ pub fn execute_claim(
 
 fn compute_available_amount(member: &Member, cfg: &Config, time: u64) -> Uint128 {
     // calculate released amount for the member
-    let released_amount = compute_released_amount(&member, &cfg, time);
+    let released_amount = compute_released_amount(member, cfg, time);
     // available amount to claim is decreased by already claimed tokens
-    return released_amount - member.claimed;
+    released_amount - member.claimed
 }
 
 fn compute_released_amount(member: &Member, cfg: &Config, time: u64) -> Uint128 {
@@ -219,7 +289,7 @@ fn compute_released_amount(member: &Member, cfg: &Config, time: u64) -> Uint128
     let days_passed = (time - cfg.vesting.cliff_end_time) / DAY;
 
     // after cliff ends smart contract release initial_amount + rest daily
-    return (member.amount - initial_amount) * Uint128::from(days_passed) / Uint128::from(total_days) + initial_amount;
+    (member.amount - initial_amount) * Uint128::from(days_passed) / Uint128::from(total_days) + initial_amount
 }
 
 pub fn execute_ust_withdraw(
@@ -229,9 +299,9 @@ pub fn execute_ust_withdraw(
     recipient: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized treasurer
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Treasurer, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -254,9 +324,9 @@ pub fn execute_token_withdraw(
     token: String,
     recipient: String,
 ) -> Result<Response, ContractError> {
-    // authorized owner
+    // authorized treasurer
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.owner {
+    if !is_authorized(deps.storage, &cfg.owner, Role::Treasurer, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -314,6 +384,9 @@ fn must_pay_fee(info: &MessageInfo, cfg: &Config, operation: String) -> Result<(
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps.storage)?),
+        QueryMsg::RoleMembers { role, start_after, limit } =>
+            to_binary(&query_role_members(deps, role, start_after, limit)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
         QueryMsg::Member { address } =>
             to_binary(&query_member(deps, address, env.block.time.seconds())?),
@@ -323,11 +396,11 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
 }
 
 pub fn query_config(deps: Deps) -> StdResult<Config> {
-    Ok(CONFIG.load(deps.storage)?)
+    CONFIG.load(deps.storage)
 }
 
 pub fn query_state(deps: Deps) -> StdResult<State> {
-    Ok(STATE.load(deps.storage)?)
+    STATE.load(deps.storage)
 }
 
 pub fn query_member(deps: Deps, addr: String, time: u64) -> StdResult<MemberResponse> {
@@ -335,14 +408,11 @@ pub fn query_member(deps: Deps, addr: String, time: u64) -> StdResult<MemberResp
     let cfg = CONFIG.load(deps.storage)?;
     let member = MEMBERS.may_load(deps.storage, &addr)?;
 
-    let res: Option<MemberResponseItem> = match member {
-        Some(m) => Some(MemberResponseItem {
-            amount: m.amount,
-            available_to_claim: compute_available_amount(&m, &cfg, time),
-            claimed: m.claimed,
-        }),
-        None => None,
-    };
+    let res: Option<MemberResponseItem> = member.map(|m| MemberResponseItem {
+        amount: m.amount,
+        available_to_claim: compute_available_amount(&m, &cfg, time),
+        claimed: m.claimed,
+    });
 
     Ok(MemberResponse { member: res })
 }
@@ -386,10 +456,13 @@ fn query_member_list(
 
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::{Coin, Deps, DepsMut, Env, Uint128};
+    use cosmwasm_std::{Addr, Coin, Deps, DepsMut, Env, Uint128};
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
 
     use crate::contract::{execute, instantiate, query_config, query_member};
+    use roles::{query_role_members, Role, RolesError};
+
+    use crate::ContractError;
     use crate::msg::{ExecuteMsg, InstantiateMsg, MemberResponseItem, RegisterMemberItem};
     use crate::state::{FeeConfig, Vesting};
 
@@ -434,7 +507,7 @@ mod tests {
 
         // it worked, let's query the state
         let res = query_config(deps.as_ref()).unwrap();
-        assert_eq!(INIT_ADMIN, res.owner.as_str());
+        assert_eq!(Some(Addr::unchecked(INIT_ADMIN)), res.owner);
         assert_eq!(NAME, res.name.as_str());
 
         let res = query_member(deps.as_ref(), USER1.into(), env.block.time.seconds()).unwrap();
@@ -445,7 +518,7 @@ mod tests {
         let mut env = mock_env();
         env.block.height += height_delta;
         env.block.time = env.block.time.plus_seconds(height_delta * 6);
-        return env;
+        env
     }
 
     fn assert_users(
@@ -543,4 +616,89 @@ mod tests {
         assert_available_to_claim(deps.as_ref(), 0, 500_000, 100800);
         assert_claimed(deps.as_ref(), 100_000, 0, 100800);
     }
+
+    #[test]
+    fn ownership_transfer() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+
+        let msg = ExecuteMsg::ProposeOwner {
+            new_owner: USER1.to_string(),
+            expires: None,
+        };
+        let info = mock_info(INIT_ADMIN, &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // old owner keeps control until the proposal is accepted
+        let info = mock_info(USER1, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, register_msg()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info(INIT_ADMIN, &[]);
+        execute(deps.as_mut(), mock_env(), info, register_msg()).unwrap();
+
+        let info = mock_info(USER1, &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::AcceptOwnership {}).unwrap();
+
+        let res = query_config(deps.as_ref()).unwrap();
+        assert_eq!(Some(Addr::unchecked(USER1)), res.owner);
+
+        let info = mock_info(INIT_ADMIN, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, register_msg()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info(USER1, &[]);
+        execute(deps.as_mut(), mock_env(), info, register_msg()).unwrap();
+    }
+
+    #[test]
+    fn operator_registers_members() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+
+        // only admin grants roles
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::Operator,
+            address: USER1.to_string(),
+        };
+        let info = mock_info(USER1, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Roles(RolesError::Unauthorized {}));
+        let info = mock_info(INIT_ADMIN, &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query_role_members(deps.as_ref(), Role::Operator, None, None).unwrap();
+        assert_eq!(res.members, vec![USER1.to_string()]);
+
+        // operator registers members
+        let info = mock_info(USER1, &[]);
+        execute(deps.as_mut(), mock_env(), info, register_msg()).unwrap();
+
+        // but cannot withdraw funds
+        let msg = ExecuteMsg::UstWithdraw {
+            recipient: USER1.to_string(),
+            amount: Uint128::new(1000),
+        };
+        let info = mock_info(USER1, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let msg = ExecuteMsg::RevokeRole {
+            role: Role::Operator,
+            address: USER1.to_string(),
+        };
+        let info = mock_info(INIT_ADMIN, &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info(USER1, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, register_msg()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    fn register_msg() -> ExecuteMsg {
+        ExecuteMsg::RegisterMembers(Vec::from([
+            RegisterMemberItem {
+                address: USER2.to_string(),
+                amount: Uint128::new(1_000_000),
+                claimed: None,
+            }]))
+    }
 }
@@ -1,5 +1,7 @@
 use cosmwasm_std::StdError;
 use cw0::PaymentError;
+use ownership::OwnershipError;
+use roles::RolesError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -10,6 +12,12 @@ pub enum ContractError {
     #[error("{0}")]
     Payment(#[from] PaymentError),
 
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
+
+    #[error("{0}")]
+    Roles(#[from] RolesError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -1,6 +1,8 @@
 use cosmwasm_std::Uint128;
+use cw0::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use roles::Role;
 use crate::state::{FeeConfig, Vesting};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -16,11 +18,26 @@ pub struct InstantiateMsg {
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     UpdateConfig {
-        owner: Option<String>,
         name: Option<String>,
         fee_config: Option<Vec<FeeConfig>>,
         vesting: Option<Vesting>,
     },
+    /// Only owner. Proposes a new owner, who has to accept the ownership before it expires.
+    /// The current owner keeps control until then.
+    ProposeOwner {
+        new_owner: String,
+        expires: Option<Expiration>,
+    },
+    /// Only pending owner. Takes over the ownership.
+    AcceptOwnership {},
+    /// Only owner or pending owner. Drops the ownership proposal.
+    CancelOwnershipProposal {},
+    /// Only owner. Leaves the contract without an owner, forever.
+    RenounceOwnership {},
+    /// Only admin. Gives the role to the address.
+    GrantRole { role: Role, address: String },
+    /// Only admin. Takes the role from the address.
+    RevokeRole { role: Role, address: String },
     Claim {},
     RegisterMembers (
         Vec<RegisterMemberItem>
@@ -42,6 +59,12 @@ pub struct MigrateMsg {}
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    PendingOwner {},
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     State {},
     Member {
         address: String
@@ -0,0 +1,17 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum OwnershipError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("There is no pending ownership proposal")]
+    NoPendingOwner {},
+
+    #[error("Ownership proposal is expired")]
+    ProposalExpired {},
+}
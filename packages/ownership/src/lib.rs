@@ -0,0 +1,12 @@
+mod error;
+mod ownership;
+
+pub use cw0::Expiration;
+
+pub use crate::error::OwnershipError;
+pub use crate::ownership::{
+    accept_ownership, assert_owner, cancel_ownership_proposal, execute_accept_ownership,
+    execute_cancel_ownership_proposal, execute_propose_owner, execute_renounce_ownership,
+    propose_owner, query_pending_owner, renounce_ownership, PendingOwner, PendingOwnerResponse,
+    PENDING_OWNER,
+};
@@ -0,0 +1,316 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Api, BlockInfo, DepsMut, Env, MessageInfo, Response, StdResult, Storage};
+use cw0::Expiration;
+use cw_storage_plus::Item;
+
+use crate::error::OwnershipError;
+
+/// Owner proposed by the current one. It becomes the owner only after accepting the proposal.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwner {
+    pub owner: Addr,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwnerResponse {
+    pub pending_owner: Option<String>,
+    pub expires: Option<Expiration>,
+}
+
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("pending_owner");
+
+/// Fails with `OwnershipError::Unauthorized` unless the sender is the owner.
+/// Contracts with renounced ownership have no owner.
+pub fn assert_owner(owner: &Option<Addr>, sender: &Addr) -> Result<(), OwnershipError> {
+    if owner.as_ref() != Some(sender) {
+        return Err(OwnershipError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Only owner. Replaces any previous proposal. Proposal without expiration never expires.
+pub fn propose_owner(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    block: &BlockInfo,
+    sender: &Addr,
+    owner: &Option<Addr>,
+    new_owner: &str,
+    expires: Option<Expiration>,
+) -> Result<PendingOwner, OwnershipError> {
+    assert_owner(owner, sender)?;
+
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(block) {
+        return Err(OwnershipError::ProposalExpired {});
+    }
+
+    let pending = PendingOwner {
+        owner: api.addr_validate(new_owner)?,
+        expires,
+    };
+    PENDING_OWNER.save(storage, &pending)?;
+    Ok(pending)
+}
+
+/// Only pending owner. Returns the new owner which has to be saved by the contract.
+pub fn accept_ownership(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    sender: &Addr,
+) -> Result<Addr, OwnershipError> {
+    let pending = PENDING_OWNER
+        .may_load(storage)?
+        .ok_or(OwnershipError::NoPendingOwner {})?;
+    if &pending.owner != sender {
+        return Err(OwnershipError::Unauthorized {});
+    }
+    if pending.expires.is_expired(block) {
+        return Err(OwnershipError::ProposalExpired {});
+    }
+
+    PENDING_OWNER.remove(storage);
+    Ok(pending.owner)
+}
+
+/// Owner can withdraw the proposal, pending owner can decline it
+pub fn cancel_ownership_proposal(
+    storage: &mut dyn Storage,
+    sender: &Addr,
+    owner: &Option<Addr>,
+) -> Result<(), OwnershipError> {
+    let pending = PENDING_OWNER
+        .may_load(storage)?
+        .ok_or(OwnershipError::NoPendingOwner {})?;
+    if &pending.owner != sender {
+        assert_owner(owner, sender)?;
+    }
+
+    PENDING_OWNER.remove(storage);
+    Ok(())
+}
+
+/// Only owner. Drops any pending proposal, the contract has to clear its owner afterwards.
+pub fn renounce_ownership(
+    storage: &mut dyn Storage,
+    sender: &Addr,
+    owner: &Option<Addr>,
+) -> Result<(), OwnershipError> {
+    assert_owner(owner, sender)?;
+
+    PENDING_OWNER.remove(storage);
+    Ok(())
+}
+
+/// Handles `ProposeOwner` of a contract with the given owner
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    owner: &Option<Addr>,
+    new_owner: String,
+    expires: Option<Expiration>,
+) -> Result<Response, OwnershipError> {
+    let pending = propose_owner(
+        deps.storage,
+        deps.api,
+        &env.block,
+        &info.sender,
+        owner,
+        &new_owner,
+        expires,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_owner")
+        .add_attribute("sender", info.sender.clone())
+        .add_attribute("pending_owner", pending.owner)
+        .add_attribute("expires", pending.expires.to_string()))
+}
+
+/// Handles `AcceptOwnership`. Returns the new owner, which has to be saved by the contract.
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+) -> Result<(Addr, Response), OwnershipError> {
+    let new_owner = accept_ownership(deps.storage, &env.block, &info.sender)?;
+
+    let res = Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("sender", info.sender.clone());
+    Ok((new_owner, res))
+}
+
+/// Handles `CancelOwnershipProposal` of a contract with the given owner
+pub fn execute_cancel_ownership_proposal(
+    deps: DepsMut,
+    info: &MessageInfo,
+    owner: &Option<Addr>,
+) -> Result<Response, OwnershipError> {
+    cancel_ownership_proposal(deps.storage, &info.sender, owner)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_ownership_proposal")
+        .add_attribute("sender", info.sender.clone()))
+}
+
+/// Handles `RenounceOwnership` of a contract with the given owner, the contract has to
+/// clear its owner afterwards
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    info: &MessageInfo,
+    owner: &Option<Addr>,
+) -> Result<Response, OwnershipError> {
+    renounce_ownership(deps.storage, &info.sender, owner)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "renounce_ownership")
+        .add_attribute("sender", info.sender.clone()))
+}
+
+pub fn query_pending_owner(storage: &dyn Storage) -> StdResult<PendingOwnerResponse> {
+    let pending = PENDING_OWNER.may_load(storage)?;
+    Ok(PendingOwnerResponse {
+        pending_owner: pending.as_ref().map(|p| p.owner.to_string()),
+        expires: pending.map(|p| p.expires),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
+
+    const OWNER: &str = "owner";
+    const NEW_OWNER: &str = "new_owner";
+
+    fn owner() -> Option<Addr> {
+        Some(Addr::unchecked(OWNER))
+    }
+
+    #[test]
+    fn propose_and_accept() {
+        let mut storage = MockStorage::new();
+        let api = MockApi::default();
+        let env = mock_env();
+
+        // only owner can propose
+        let err = propose_owner(
+            &mut storage,
+            &api,
+            &env.block,
+            &Addr::unchecked(NEW_OWNER),
+            &owner(),
+            NEW_OWNER,
+            None,
+        )
+            .unwrap_err();
+        assert_eq!(err, OwnershipError::Unauthorized {});
+
+        // proposal cannot be expired already
+        let err = propose_owner(
+            &mut storage,
+            &api,
+            &env.block,
+            &Addr::unchecked(OWNER),
+            &owner(),
+            NEW_OWNER,
+            Some(Expiration::AtHeight(env.block.height)),
+        )
+            .unwrap_err();
+        assert_eq!(err, OwnershipError::ProposalExpired {});
+
+        propose_owner(
+            &mut storage,
+            &api,
+            &env.block,
+            &Addr::unchecked(OWNER),
+            &owner(),
+            NEW_OWNER,
+            Some(Expiration::AtHeight(env.block.height + 10)),
+        )
+            .unwrap();
+        assert_eq!(
+            query_pending_owner(&storage).unwrap(),
+            PendingOwnerResponse {
+                pending_owner: Some(NEW_OWNER.to_string()),
+                expires: Some(Expiration::AtHeight(env.block.height + 10)),
+            }
+        );
+
+        // only pending owner can accept
+        let err = accept_ownership(&mut storage, &env.block, &Addr::unchecked(OWNER)).unwrap_err();
+        assert_eq!(err, OwnershipError::Unauthorized {});
+
+        // not after the proposal expires
+        let mut block = env.block.clone();
+        block.height += 10;
+        let err = accept_ownership(&mut storage, &block, &Addr::unchecked(NEW_OWNER)).unwrap_err();
+        assert_eq!(err, OwnershipError::ProposalExpired {});
+
+        let new_owner = accept_ownership(&mut storage, &env.block, &Addr::unchecked(NEW_OWNER)).unwrap();
+        assert_eq!(new_owner, Addr::unchecked(NEW_OWNER));
+        assert_eq!(query_pending_owner(&storage).unwrap().pending_owner, None);
+
+        // proposal is used up
+        let err = accept_ownership(&mut storage, &env.block, &Addr::unchecked(NEW_OWNER)).unwrap_err();
+        assert_eq!(err, OwnershipError::NoPendingOwner {});
+    }
+
+    #[test]
+    fn cancel_and_renounce() {
+        let mut storage = MockStorage::new();
+        let api = MockApi::default();
+        let env = mock_env();
+
+        let err = cancel_ownership_proposal(&mut storage, &Addr::unchecked(OWNER), &owner())
+            .unwrap_err();
+        assert_eq!(err, OwnershipError::NoPendingOwner {});
+
+        let propose = |storage: &mut MockStorage| {
+            propose_owner(
+                storage,
+                &api,
+                &env.block,
+                &Addr::unchecked(OWNER),
+                &owner(),
+                NEW_OWNER,
+                None,
+            )
+                .unwrap();
+        };
+
+        // others cannot cancel
+        propose(&mut storage);
+        let err = cancel_ownership_proposal(&mut storage, &Addr::unchecked("other"), &owner())
+            .unwrap_err();
+        assert_eq!(err, OwnershipError::Unauthorized {});
+
+        // owner withdraws
+        cancel_ownership_proposal(&mut storage, &Addr::unchecked(OWNER), &owner()).unwrap();
+        assert_eq!(query_pending_owner(&storage).unwrap().pending_owner, None);
+
+        // pending owner declines
+        propose(&mut storage);
+        cancel_ownership_proposal(&mut storage, &Addr::unchecked(NEW_OWNER), &owner()).unwrap();
+        assert_eq!(query_pending_owner(&storage).unwrap().pending_owner, None);
+
+        // renouncing drops the proposal
+        propose(&mut storage);
+        let err = renounce_ownership(&mut storage, &Addr::unchecked(NEW_OWNER), &owner())
+            .unwrap_err();
+        assert_eq!(err, OwnershipError::Unauthorized {});
+        renounce_ownership(&mut storage, &Addr::unchecked(OWNER), &owner()).unwrap();
+        let err = accept_ownership(&mut storage, &env.block, &Addr::unchecked(NEW_OWNER)).unwrap_err();
+        assert_eq!(err, OwnershipError::NoPendingOwner {});
+
+        // nobody is the owner after renouncing
+        let err = assert_owner(&None, &Addr::unchecked(OWNER)).unwrap_err();
+        assert_eq!(err, OwnershipError::Unauthorized {});
+    }
+}
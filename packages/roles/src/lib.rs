@@ -0,0 +1,8 @@
+mod error;
+mod roles;
+
+pub use crate::error::RolesError;
+pub use crate::roles::{
+    execute_grant_role, execute_revoke_role, grant_role, has_role, is_authorized,
+    query_role_members, revoke_role, Role, RoleMembersResponse, ROLE_MEMBERS,
+};
@@ -0,0 +1,212 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Deps, DepsMut, MessageInfo, Order, Response, StdResult, Storage};
+use cw_storage_plus::{Bound, Map};
+
+use crate::error::RolesError;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Updates configuration and manages roles
+    Admin,
+    /// Runs day to day operations, e.g. registers members
+    Operator,
+    /// Withdraws funds held by the contract
+    Treasurer,
+    /// Stops and resumes the contract
+    Pauser,
+    /// Freezes accounts and recovers their funds
+    Compliance,
+    /// Reports off-chain events, e.g. platform abuse to be slashed
+    Oracle,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Operator => "operator",
+            Role::Treasurer => "treasurer",
+            Role::Pauser => "pauser",
+            Role::Compliance => "compliance",
+            Role::Oracle => "oracle",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoleMembersResponse {
+    pub members: Vec<String>,
+}
+
+/// Members of every role, keyed by role name and member address
+pub const ROLE_MEMBERS: Map<(&str, &Addr), bool> = Map::new("role_members");
+
+pub fn has_role(storage: &dyn Storage, role: Role, addr: &Addr) -> bool {
+    ROLE_MEMBERS.has(storage, (role.as_str(), addr))
+}
+
+/// Owner holds every role, other addresses have to be granted the given one
+pub fn is_authorized(storage: &dyn Storage, owner: &Option<Addr>, role: Role, sender: &Addr) -> bool {
+    owner.as_ref() == Some(sender) || has_role(storage, role, sender)
+}
+
+pub fn grant_role(storage: &mut dyn Storage, role: Role, addr: &Addr) -> StdResult<()> {
+    ROLE_MEMBERS.save(storage, (role.as_str(), addr), &true)
+}
+
+pub fn revoke_role(storage: &mut dyn Storage, role: Role, addr: &Addr) {
+    ROLE_MEMBERS.remove(storage, (role.as_str(), addr))
+}
+
+/// Only admins. Handles `GrantRole` of a contract with the given owner.
+pub fn execute_grant_role(
+    deps: DepsMut,
+    info: &MessageInfo,
+    owner: &Option<Addr>,
+    role: Role,
+    address: String,
+) -> Result<Response, RolesError> {
+    if !is_authorized(deps.storage, owner, Role::Admin, &info.sender) {
+        return Err(RolesError::Unauthorized {});
+    }
+
+    let addr = deps.api.addr_validate(&address)?;
+    grant_role(deps.storage, role, &addr)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_role")
+        .add_attribute("sender", info.sender.clone())
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+/// Only admins. Handles `RevokeRole` of a contract with the given owner.
+pub fn execute_revoke_role(
+    deps: DepsMut,
+    info: &MessageInfo,
+    owner: &Option<Addr>,
+    role: Role,
+    address: String,
+) -> Result<Response, RolesError> {
+    if !is_authorized(deps.storage, owner, Role::Admin, &info.sender) {
+        return Err(RolesError::Unauthorized {});
+    }
+
+    let addr = deps.api.addr_validate(&address)?;
+    revoke_role(deps.storage, role, &addr);
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_role")
+        .add_attribute("sender", info.sender.clone())
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn query_role_members(
+    deps: Deps,
+    role: Role,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<RoleMembersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let members: StdResult<Vec<_>> = ROLE_MEMBERS
+        .prefix(role.as_str())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|k| Ok(String::from_utf8(k)?))
+        .collect();
+
+    Ok(RoleMembersResponse {
+        members: members?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::attr;
+    use cosmwasm_std::testing::{mock_dependencies, mock_info};
+
+    #[test]
+    fn grant_and_revoke() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = Some(Addr::unchecked("owner"));
+        let operator = Addr::unchecked("operator");
+
+        assert!(is_authorized(&deps.storage, &owner, Role::Treasurer, &Addr::unchecked("owner")));
+        assert!(!is_authorized(&deps.storage, &owner, Role::Operator, &operator));
+
+        grant_role(&mut deps.storage, Role::Operator, &operator).unwrap();
+        assert!(is_authorized(&deps.storage, &owner, Role::Operator, &operator));
+        // roles are independent
+        assert!(!is_authorized(&deps.storage, &owner, Role::Treasurer, &operator));
+
+        revoke_role(&mut deps.storage, Role::Operator, &operator);
+        assert!(!is_authorized(&deps.storage, &owner, Role::Operator, &operator));
+
+        // nobody holds the roles of a renounced owner
+        assert!(!is_authorized(&deps.storage, &None, Role::Admin, &Addr::unchecked("owner")));
+    }
+
+    #[test]
+    fn grant_and_revoke_handlers() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = Some(Addr::unchecked("owner"));
+        let admin = mock_info("admin", &[]);
+
+        let err = execute_grant_role(deps.as_mut(), &admin, &owner, Role::Admin, "admin".to_string())
+            .unwrap_err();
+        assert_eq!(err, RolesError::Unauthorized {});
+
+        // owner grants admins, admins manage the other roles
+        let res = execute_grant_role(
+            deps.as_mut(), &mock_info("owner", &[]), &owner, Role::Admin, "admin".to_string())
+            .unwrap();
+        assert_eq!(res.attributes, vec![
+            attr("action", "grant_role"),
+            attr("sender", "owner"),
+            attr("role", "admin"),
+            attr("address", "admin"),
+        ]);
+        execute_grant_role(deps.as_mut(), &admin, &owner, Role::Pauser, "pauser".to_string()).unwrap();
+        assert!(has_role(&deps.storage, Role::Pauser, &Addr::unchecked("pauser")));
+
+        let pauser = mock_info("pauser", &[]);
+        let err = execute_revoke_role(deps.as_mut(), &pauser, &owner, Role::Pauser, "pauser".to_string())
+            .unwrap_err();
+        assert_eq!(err, RolesError::Unauthorized {});
+        let res = execute_revoke_role(deps.as_mut(), &admin, &owner, Role::Pauser, "pauser".to_string())
+            .unwrap();
+        assert_eq!(res.attributes[0], attr("action", "revoke_role"));
+        assert!(!has_role(&deps.storage, Role::Pauser, &Addr::unchecked("pauser")));
+    }
+
+    #[test]
+    fn role_members_pagination() {
+        let mut deps = mock_dependencies(&[]);
+        for addr in ["zebra", "nice", "aaaardvark"] {
+            grant_role(&mut deps.storage, Role::Pauser, &Addr::unchecked(addr)).unwrap();
+        }
+        grant_role(&mut deps.storage, Role::Admin, &Addr::unchecked("admin")).unwrap();
+
+        let res = query_role_members(deps.as_ref(), Role::Pauser, None, Some(2)).unwrap();
+        assert_eq!(res.members, vec!["aaaardvark", "nice"]);
+
+        let res = query_role_members(deps.as_ref(), Role::Pauser, Some("nice".to_string()), None)
+            .unwrap();
+        assert_eq!(res.members, vec!["zebra"]);
+
+        let res = query_role_members(deps.as_ref(), Role::Admin, None, None).unwrap();
+        assert_eq!(res.members, vec!["admin"]);
+    }
+}